@@ -152,11 +152,18 @@ impl TextureAtlas {
         Size::new(width, height)
     }
 
-    pub fn rasterize_characters(&mut self, label: Label, font: &mut Font, size: f32) {
+    /// Rasterizes every character of `font` at `size` pixels into the atlas.
+    ///
+    /// Does nothing if the size was already rasterized.
+    pub fn rasterize_characters(&mut self, font: &mut Font, size: u8) {
+        if font.has_size(size) {
+            return;
+        }
+
         let chars = font.chars().keys().copied().collect::<Vec<_>>();
 
         for ch in chars {
-            let (metrics, bitmap) = font.rasterize(ch, size);
+            let (metrics, bitmap) = font.rasterize(ch, size as f32);
             let width = metrics.width as u32;
             let height = metrics.height as u32;
 
@@ -164,7 +171,7 @@ impl TextureAtlas {
                 continue;
             }
 
-            font.add_glyph(ch, width, height);
+            font.add_glyph(ch, size, width, height);
 
             // Convert grayscale alpha to white RGBA
             let mut rgba = Vec::with_capacity(bitmap.len() * 4);
@@ -172,8 +179,9 @@ impl TextureAtlas {
                 rgba.extend_from_slice(&[255, 255, 255, alpha]);
             }
 
-            let glyph_label = Label::new(&format!("{}_{}", label.raw(), ch));
-            self.add_rgba(glyph_label, &rgba, width, height);
+            self.add_rgba(font.glyph_label(ch, size), &rgba, width, height);
         }
+
+        font.mark_size(size);
     }
 }
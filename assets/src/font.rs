@@ -1,6 +1,6 @@
 use macros::Get;
 use std::ops::Deref;
-use utils::Label;
+use utils::{Handle, Label};
 use wgpu::naga::FastHashMap;
 
 #[derive(Debug, Clone)]
@@ -9,17 +9,63 @@ pub struct Glyph {
     pub height: u32,
 }
 
+/// A font handle paired with the pixel size it should be rendered at.
+///
+/// Obtained with [`Font::at_size`], or converted from a plain `Handle<Font>`,
+/// in which case the font's base size (the one it was loaded with) is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Get)]
+pub struct FontFace {
+    #[get(copied)]
+    handle: Handle<Font>,
+
+    /// `None` means the base size of the font
+    size: Option<u8>,
+}
+
+impl FontFace {
+    #[inline]
+    pub fn new(handle: Handle<Font>, size: u8) -> Self {
+        Self {
+            handle,
+            size: Some(size),
+        }
+    }
+
+    /// Returns the pixel size of this face, falling back
+    /// to the base size of `font` if none was specified
+    #[inline]
+    pub fn size(&self, font: &Font) -> u8 {
+        self.size.unwrap_or(font.size)
+    }
+}
+
+impl From<Handle<Font>> for FontFace {
+    fn from(handle: Handle<Font>) -> Self {
+        Self { handle, size: None }
+    }
+}
+
 #[derive(Debug)]
 #[derive(Get)]
 pub struct Font {
     #[get]
     inner: fontdue::Font,
 
+    #[get(copied)]
+    handle: Handle<Font>,
+
     #[get]
     label: Label,
+
+    /// Base size, the one the font was loaded with
     #[get(copied)]
     size: u8,
-    glyphs: FastHashMap<char, Glyph>,
+
+    /// Sizes that have been rasterized into the atlas
+    #[get(ty = &[u8])]
+    sizes: Vec<u8>,
+    glyphs: FastHashMap<(char, u8), Glyph>,
 }
 
 impl Deref for Font {
@@ -31,25 +77,58 @@ impl Deref for Font {
 }
 
 impl Font {
-    pub fn new(label: Label, bytes: Vec<u8>, size: u8) -> Self {
+    pub fn new(handle: Handle<Font>, label: Label, bytes: Vec<u8>, size: u8) -> Self {
         let inner = fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default())
             .expect("Failed to load font");
 
         Self {
             inner,
+            handle,
             label,
             size,
+            sizes: Vec::new(),
             glyphs: FastHashMap::default(),
         }
     }
 
+    /// Returns a face of this font at a specific pixel size.
+    ///
+    /// The glyphs are shared with every other size of the font, and are rasterized
+    /// the first time the face is used. Use [`crate::AssetServer::load_font_size`]
+    /// to rasterize them ahead of time.
     #[inline]
-    pub fn get_glyph(&self, ch: &char) -> &Glyph {
-        self.glyphs.get(ch).as_ref().expect("Failed to get glyph")
+    pub fn at_size(&self, size: u8) -> FontFace {
+        FontFace::new(self.handle, size)
+    }
+
+    #[inline]
+    pub fn has_size(&self, size: u8) -> bool {
+        self.sizes.contains(&size)
+    }
+
+    #[inline]
+    pub fn get_glyph(&self, ch: &char, size: u8) -> &Glyph {
+        self.glyphs
+            .get(&(*ch, size))
+            .as_ref()
+            .expect("Failed to get glyph")
+    }
+
+    #[inline]
+    pub fn add_glyph(&mut self, ch: char, size: u8, width: u32, height: u32) {
+        self.glyphs.insert((ch, size), Glyph { width, height });
+    }
+
+    #[inline]
+    pub(crate) fn mark_size(&mut self, size: u8) {
+        if !self.has_size(size) {
+            self.sizes.push(size);
+        }
     }
 
+    /// Label of the atlas region holding `ch` rasterized at `size`
     #[inline]
-    pub fn add_glyph(&mut self, ch: char, width: u32, height: u32) {
-        self.glyphs.insert(ch, Glyph { width, height });
+    pub fn glyph_label(&self, ch: char, size: u8) -> Label {
+        Label::new(&format!("{}_{}_{}", self.label.raw(), size, ch))
     }
 }
@@ -1,16 +1,18 @@
 mod atlas;
 mod font;
+mod model;
 
 use atlas::TextureAtlas;
-use globals::consts;
+use globals::{AllocCategory, alloc_scope, consts};
 use logging::info;
 use macros::Get;
 use math::Size;
-use parking_lot::{MappedRwLockReadGuard, RwLock, RwLockReadGuard};
+use parking_lot::{MappedRwLockReadGuard, Mutex, RwLock, RwLockReadGuard};
 use std::{io::Cursor, path::Path, sync::Arc};
-use utils::{ByteSize, Handle, Label, SlotMap};
+use utils::{ByteSize, FastHashMap, Handle, Label, SlotMap};
 
 pub use font::*;
+pub use model::{Model, ModelFormat, ModelPrimitive};
 
 #[derive(Debug, Clone)]
 pub struct Image {
@@ -23,7 +25,12 @@ pub struct Image {
 pub struct AssetServer {
     atlas: Arc<RwLock<TextureAtlas>>,
     images: Arc<RwLock<SlotMap<Image>>>,
+    image_names: Arc<RwLock<FastHashMap<Label, Handle<Image>>>>,
     fonts: Arc<RwLock<SlotMap<Font>>>,
+    models: Arc<RwLock<SlotMap<Model>>>,
+
+    /// Font sizes requested by the renderer that are not rasterized yet
+    font_requests: Arc<Mutex<Vec<FontFace>>>,
 
     #[get(copied)]
     debug_font: Handle<Font>,
@@ -37,7 +44,10 @@ impl AssetServer {
         let mut server = Self {
             atlas: Arc::new(RwLock::new(atlas)),
             images: Arc::new(RwLock::new(SlotMap::new())),
+            image_names: Arc::new(RwLock::new(FastHashMap::default())),
             fonts: Arc::new(RwLock::new(SlotMap::new())),
+            models: Arc::new(RwLock::new(SlotMap::new())),
+            font_requests: Arc::new(Mutex::new(Vec::new())),
             debug_font: Handle::default(),
         };
 
@@ -56,12 +66,15 @@ impl AssetServer {
         AssetServerGuard {
             atlas: self.atlas.read(),
             images: self.images.read(),
+            image_names: self.image_names.read(),
             fonts: self.fonts.read(),
+            font_requests: &self.font_requests,
             debug_font: self.debug_font,
         }
     }
 
     pub fn load_image_bytes(&self, bytes: Vec<u8>) -> Handle<Image> {
+        let _alloc = alloc_scope(AllocCategory::Assets);
         let (rgba, width, height) = Self::decode_png(&bytes);
 
         let mut images = self.images.write();
@@ -98,7 +111,19 @@ impl AssetServer {
         self.load_image_bytes(bytes)
     }
 
+    /// Gives a name to an image, so that it can be referenced
+    /// by name, for example with `[img=name]` in rich text
+    pub fn name_image(&self, name: Label, handle: Handle<Image>) {
+        self.image_names.write().insert(name, handle);
+    }
+
+    #[inline]
+    pub fn find_image(&self, name: Label) -> Option<Handle<Image>> {
+        self.image_names.read().get(&name).copied()
+    }
+
     pub fn load_font_bytes(&self, bytes: Vec<u8>, size: u8) -> Handle<Font> {
+        let _alloc = alloc_scope(AllocCategory::Assets);
         let mut fonts = self.fonts.write();
         let mut atlas = self.atlas.write();
 
@@ -108,18 +133,76 @@ impl AssetServer {
                 ByteSize::from_bytes(bytes.len() as u64)
             );
             let label = Label::new(&format!("_font_{}", key.index()));
-            let mut font = Font::new(label, bytes, size);
+            let mut font = Font::new(key, label, bytes, size);
 
-            atlas.rasterize_characters(label, &mut font, size as f32);
+            atlas.rasterize_characters(&mut font, size);
             font
         })
     }
 
+    /// Rasterizes an already loaded font at another pixel size,
+    /// without parsing the font file again.
+    ///
+    /// Sizes are also rasterized lazily the first time a [`FontFace`] is drawn,
+    /// use this to avoid glyphs popping in on the first frame.
+    pub fn load_font_size(&self, handle: Handle<Font>, size: u8) -> FontFace {
+        let _alloc = alloc_scope(AllocCategory::Assets);
+        let mut fonts = self.fonts.write();
+        let font = fonts.get_mut(handle).expect("Font not found");
+
+        if !font.has_size(size) {
+            info!("Rasterizing font {} at {}px", handle.index(), size);
+            self.atlas.write().rasterize_characters(font, size);
+        }
+
+        FontFace::new(handle, size)
+    }
+
+    /// Rasterizes the font sizes that were requested while rendering
+    #[doc(hidden)]
+    pub fn rasterize_requested_fonts(&self) {
+        let requests = std::mem::take(&mut *self.font_requests.lock());
+
+        for face in requests {
+            let size = face.size(&self.get_font(face.handle()));
+            self.load_font_size(face.handle(), size);
+        }
+    }
+
     pub fn load_font<P: AsRef<Path>>(&self, path: P, size: u8) -> Handle<Font> {
         let bytes = std::fs::read(path).expect("Failed to read font file");
         self.load_font_bytes(bytes, size)
     }
 
+    /// Loads an OBJ or glTF model from memory. Textures and buffers in other files
+    /// cannot be found this way, only the ones embedded in a `.glb` or as data uris
+    pub fn load_model_bytes(&self, bytes: Vec<u8>, format: ModelFormat) -> Handle<Model> {
+        self.insert_model(&bytes, format, None)
+    }
+
+    /// Loads an OBJ or glTF model, the format is chosen by the extension.
+    /// Base color textures are added to the atlas, only PNG is supported
+    pub fn load_model<P: AsRef<Path>>(&self, path: P) -> Handle<Model> {
+        let path = path.as_ref();
+        let format = ModelFormat::from_path(path).expect("Unsupported model format");
+        let bytes = std::fs::read(path).expect("Failed to read model file");
+
+        self.insert_model(&bytes, format, path.parent())
+    }
+
+    fn insert_model(&self, bytes: &[u8], format: ModelFormat, dir: Option<&Path>) -> Handle<Model> {
+        let _alloc = alloc_scope(AllocCategory::Assets);
+        info!(
+            "Loading model ({})",
+            ByteSize::from_bytes(bytes.len() as u64)
+        );
+
+        let model = model::parse(self, bytes, format, dir)
+            .unwrap_or_else(|e| panic!("Failed to load model: {e}"));
+
+        self.models.write().insert(model)
+    }
+
     #[inline]
     pub fn get_image(&self, handle: Handle<Image>) -> MappedRwLockReadGuard<'_, Image> {
         let guard = self.images.read();
@@ -133,13 +216,22 @@ impl AssetServer {
 
         RwLockReadGuard::map(guard, |fonts| fonts.get(handle).expect("Font not found"))
     }
+
+    #[inline]
+    pub fn get_model(&self, handle: Handle<Model>) -> MappedRwLockReadGuard<'_, Model> {
+        let guard = self.models.read();
+
+        RwLockReadGuard::map(guard, |models| models.get(handle).expect("Model not found"))
+    }
 }
 
 #[derive(Get)]
 pub struct AssetServerGuard<'a> {
     atlas: RwLockReadGuard<'a, TextureAtlas>,
     images: RwLockReadGuard<'a, SlotMap<Image>>,
+    image_names: RwLockReadGuard<'a, FastHashMap<Label, Handle<Image>>>,
     fonts: RwLockReadGuard<'a, SlotMap<Font>>,
+    font_requests: &'a Mutex<Vec<FontFace>>,
 
     #[get(copied)]
     debug_font: Handle<Font>,
@@ -156,6 +248,11 @@ impl<'a> AssetServerGuard<'a> {
         self.fonts.get(handle).expect("Font not found")
     }
 
+    #[inline]
+    pub fn find_image(&self, name: Label) -> Option<Handle<Image>> {
+        self.image_names.get(&name).copied()
+    }
+
     // === Hidden Methods ===
     //
     // These methods are hidden since they must be used
@@ -193,11 +290,33 @@ impl<'a> AssetServerGuard<'a> {
         self.get_texture_uv_by_label(&utils::label!("_white"))
     }
 
+    /// Checks whether the glyphs of `face` are in the atlas.
+    ///
+    /// If they are not, the size is queued to be rasterized
+    /// before the next frame.
+    #[inline]
+    #[doc(hidden)]
+    pub fn font_face_ready(&self, face: FontFace) -> bool {
+        let font = self.get_font(face.handle());
+
+        if font.has_size(face.size(font)) {
+            return true;
+        }
+
+        let mut requests = self.font_requests.lock();
+
+        if !requests.contains(&face) {
+            requests.push(face);
+        }
+
+        false
+    }
+
     #[inline]
     #[doc(hidden)]
-    pub fn get_glyph_uv(&self, handle: Handle<Font>, ch: char) -> (f32, f32, f32, f32, f32, f32) {
-        let font = self.fonts.get(handle).expect("Invalid font handle");
-        let glyph_label = Label::new(&format!("{}_{}", font.label().raw(), ch));
+    pub fn get_glyph_uv(&self, face: FontFace, ch: char) -> (f32, f32, f32, f32, f32, f32) {
+        let font = self.fonts.get(face.handle()).expect("Invalid font handle");
+        let glyph_label = font.glyph_label(ch, face.size(font));
 
         self.get_texture_uv_by_label(&glyph_label)
     }
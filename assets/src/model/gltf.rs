@@ -0,0 +1,565 @@
+//! glTF 2.0, both the JSON `.gltf` and the binary `.glb` containers
+
+use super::{ModelPrimitive, decode_base64, json::Json, load_texture, read_relative};
+use crate::{AssetServer, Image, Model};
+use logging::warn;
+use math::{Matrix4, Vector2, Vector3, Vector4};
+use std::path::Path;
+use utils::Handle;
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+const MODE_TRIANGLES: usize = 4;
+const MODE_TRIANGLE_STRIP: usize = 5;
+const MODE_TRIANGLE_FAN: usize = 6;
+
+/// Deeper node hierarchies are rejected rather than recursed into
+const MAX_DEPTH: usize = 64;
+
+/// Accessors without a buffer view have no bytes to check their count
+/// against, larger ones are rejected instead of allocated
+const MAX_ZEROED_ELEMENTS: usize = 1 << 24;
+
+struct Document<'a> {
+    json: Json,
+    buffers: Vec<Vec<u8>>,
+    images: Vec<Option<Option<Handle<Image>>>>,
+    /// Nodes form disjoint trees, one reached twice is shared or in a cycle
+    visited: Vec<bool>,
+    server: &'a AssetServer,
+    dir: Option<&'a Path>,
+}
+
+pub(super) fn parse(
+    server: &AssetServer,
+    bytes: &[u8],
+    dir: Option<&Path>,
+) -> Result<Model, String> {
+    let (text, bin) = if read_u32(bytes, 0) == Some(GLB_MAGIC) {
+        split_glb(bytes)?
+    } else {
+        (bytes, None)
+    };
+
+    let text = std::str::from_utf8(text).map_err(|_| "glTF JSON is not UTF-8".to_string())?;
+    let json = Json::parse(text)?;
+
+    let version = json.get("asset").get("version").as_str().unwrap_or("");
+    if !version.starts_with('2') {
+        return Err(format!("Unsupported glTF version '{version}'"));
+    }
+
+    let mut buffers = Vec::new();
+    for buffer in json.get("buffers").items() {
+        let data = match buffer.get("uri").as_str() {
+            Some(uri) => read_uri(uri, dir)?,
+            None => bin
+                .map(<[u8]>::to_vec)
+                .ok_or("Buffer without uri outside of a .glb")?,
+        };
+
+        buffers.push(data);
+    }
+
+    let images = vec![None; json.get("images").items().len()];
+    let visited = vec![false; json.get("nodes").items().len()];
+
+    let mut document = Document {
+        json,
+        buffers,
+        images,
+        visited,
+        server,
+        dir,
+    };
+
+    let mut model = Model::default();
+
+    for root in document.roots() {
+        document.visit_node(root, Matrix4::identity(), 0, &mut model)?;
+    }
+
+    Ok(model)
+}
+
+/// The JSON chunk, and the binary chunk when there is one
+fn split_glb(bytes: &[u8]) -> Result<(&[u8], Option<&[u8]>), String> {
+    let mut offset = 12;
+    let mut text = None;
+    let mut bin = None;
+
+    while let (Some(length), Some(kind)) = (read_u32(bytes, offset), read_u32(bytes, offset + 4)) {
+        let start = offset + 8;
+        let end = start + length as usize;
+        let chunk = bytes.get(start..end).ok_or("Truncated .glb chunk")?;
+
+        match kind {
+            CHUNK_JSON => text = Some(chunk),
+            CHUNK_BIN => bin = Some(chunk),
+            _ => {}
+        }
+
+        offset = end;
+    }
+
+    Ok((text.ok_or("Missing JSON chunk in .glb")?, bin))
+}
+
+#[inline]
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let chunk = bytes.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes(chunk.try_into().ok()?))
+}
+
+/// Either an embedded `data:` uri or a file next to the model
+fn read_uri(uri: &str, dir: Option<&Path>) -> Result<Vec<u8>, String> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let (_, payload) = data
+            .split_once(";base64,")
+            .ok_or("Only base64 data uris are supported")?;
+
+        return decode_base64(payload);
+    }
+
+    read_relative(dir, uri)
+}
+
+impl Document<'_> {
+    /// Nodes of the default scene, or every node without a parent
+    fn roots(&self) -> Vec<usize> {
+        let scenes = self.json.get("scenes");
+        let scene = self.json.get("scene").as_usize().unwrap_or(0);
+
+        if !scenes.at(scene).is_null() {
+            return scenes
+                .at(scene)
+                .get("nodes")
+                .items()
+                .iter()
+                .filter_map(Json::as_usize)
+                .collect();
+        }
+
+        let nodes = self.json.get("nodes").items();
+        let mut is_child = vec![false; nodes.len()];
+
+        for node in nodes {
+            for child in node
+                .get("children")
+                .items()
+                .iter()
+                .filter_map(Json::as_usize)
+            {
+                if let Some(flag) = is_child.get_mut(child) {
+                    *flag = true;
+                }
+            }
+        }
+
+        (0..nodes.len()).filter(|&i| !is_child[i]).collect()
+    }
+
+    fn visit_node(
+        &mut self,
+        index: usize,
+        parent: Matrix4,
+        depth: usize,
+        model: &mut Model,
+    ) -> Result<(), String> {
+        if depth > MAX_DEPTH {
+            return Err("glTF node hierarchy is too deep".to_string());
+        }
+
+        if let Some(visited) = self.visited.get_mut(index) {
+            if *visited {
+                return Err(format!("glTF node {index} is reached more than once"));
+            }
+
+            *visited = true;
+        }
+
+        let node = self.json.get("nodes").at(index);
+        let transform = parent * local_transform(node);
+        let mesh = node.get("mesh").as_usize();
+
+        let children: Vec<usize> = node
+            .get("children")
+            .items()
+            .iter()
+            .filter_map(Json::as_usize)
+            .collect();
+
+        if let Some(mesh) = mesh {
+            self.load_mesh(mesh, transform, model)?;
+        }
+
+        for child in children {
+            self.visit_node(child, transform, depth + 1, model)?;
+        }
+
+        Ok(())
+    }
+
+    fn load_mesh(
+        &mut self,
+        index: usize,
+        transform: Matrix4,
+        model: &mut Model,
+    ) -> Result<(), String> {
+        let mesh = self.json.get("meshes").at(index).clone();
+        let name = mesh.get("name").as_str().unwrap_or("");
+
+        for primitive in mesh.get("primitives").items() {
+            let mode = primitive.get("mode").as_usize().unwrap_or(MODE_TRIANGLES);
+
+            if !matches!(
+                mode,
+                MODE_TRIANGLES | MODE_TRIANGLE_STRIP | MODE_TRIANGLE_FAN
+            ) {
+                warn!(
+                    "Skipping glTF primitive of mesh '{}' that is not made of triangles",
+                    name
+                );
+                continue;
+            }
+
+            let attributes = primitive.get("attributes");
+            let Some(position) = attributes.get("POSITION").as_usize() else {
+                continue;
+            };
+
+            let mut out = ModelPrimitive::new(name);
+            out.transform = transform;
+
+            out.positions = self
+                .read_accessor::<3>(position)?
+                .into_iter()
+                .map(Vector3::from_array)
+                .collect();
+
+            if let Some(normal) = attributes.get("NORMAL").as_usize() {
+                out.normals = self
+                    .read_accessor::<3>(normal)?
+                    .into_iter()
+                    .map(Vector3::from_array)
+                    .collect();
+            }
+
+            let material = self
+                .json
+                .get("materials")
+                .at(primitive.get("material").as_usize().unwrap_or(usize::MAX))
+                .clone();
+            let pbr = material.get("pbrMetallicRoughness");
+            let base_texture = pbr.get("baseColorTexture");
+            let tex_coord = base_texture.get("texCoord").as_usize().unwrap_or(0);
+
+            if let Some(uv) = attributes.get(&format!("TEXCOORD_{tex_coord}")).as_usize() {
+                out.uvs = self
+                    .read_accessor::<2>(uv)?
+                    .into_iter()
+                    .map(Vector2::from_array)
+                    .collect();
+            }
+
+            if let Some(color) = attributes.get("COLOR_0").as_usize() {
+                out.colors = self.read_colors(color)?;
+            }
+
+            if let Some(factor) = pbr.get("baseColorFactor").as_f32s::<4>() {
+                out.base_color = Vector4::from_array(factor);
+            }
+
+            if let Some(texture) = base_texture.get("index").as_usize() {
+                out.texture = self.load_image(texture)?;
+            }
+
+            let indices = match primitive.get("indices").as_usize() {
+                Some(indices) => self.read_indices(indices)?,
+                None => (0..out.positions.len() as u32).collect(),
+            };
+
+            out.indices = triangulate(indices, mode);
+
+            if out
+                .indices
+                .iter()
+                .any(|&i| i as usize >= out.positions.len())
+            {
+                return Err(format!("glTF mesh '{name}' has an index out of range"));
+            }
+
+            model.primitives.push(out);
+        }
+
+        Ok(())
+    }
+
+    /// Bytes of a buffer view, with its stride when interleaved
+    fn buffer_view(&self, index: usize) -> Result<(&[u8], Option<usize>), String> {
+        let view = self.json.get("bufferViews").at(index);
+        let buffer = view
+            .get("buffer")
+            .as_usize()
+            .and_then(|b| self.buffers.get(b))
+            .ok_or("Invalid glTF buffer view")?;
+
+        let offset = view.get("byteOffset").as_usize().unwrap_or(0);
+        let length = view
+            .get("byteLength")
+            .as_usize()
+            .ok_or("glTF buffer view without byteLength")?;
+        let bytes = offset
+            .checked_add(length)
+            .and_then(|end| buffer.get(offset..end))
+            .ok_or("glTF buffer view out of range")?;
+
+        Ok((bytes, view.get("byteStride").as_usize()))
+    }
+
+    /// Elements of an accessor converted to floats, normalized
+    /// integers are mapped to `0..1` or `-1..1`
+    fn read_accessor<const N: usize>(&self, index: usize) -> Result<Vec<[f32; N]>, String> {
+        let accessor = self.json.get("accessors").at(index);
+        let count = accessor
+            .get("count")
+            .as_usize()
+            .ok_or("glTF accessor without count")?;
+        let components = component_count(accessor.get("type").as_str().unwrap_or(""));
+        let component_type = accessor.get("componentType").as_usize().unwrap_or(0);
+        let normalized = matches!(accessor.get("normalized"), Json::Bool(true));
+        let size = component_size(component_type)?;
+
+        let Some(view) = accessor.get("bufferView").as_usize() else {
+            if count > MAX_ZEROED_ELEMENTS {
+                return Err("glTF accessor without buffer view is too large".to_string());
+            }
+
+            // Without a buffer view every element is zero
+            return Ok(vec![[0.0; N]; count]);
+        };
+
+        if !accessor.get("sparse").is_null() {
+            warn!("Ignoring sparse values of glTF accessor {}", index);
+        }
+
+        let (bytes, stride) = self.buffer_view(view)?;
+        let offset = accessor.get("byteOffset").as_usize().unwrap_or(0);
+        let stride = stride.unwrap_or(components * size);
+
+        if !fits(bytes.len(), offset, stride, components * size, count) {
+            return Err("glTF accessor out of range".to_string());
+        }
+
+        let mut out = Vec::with_capacity(count);
+
+        for i in 0..count {
+            let mut element = [0.0; N];
+
+            for (c, value) in element.iter_mut().enumerate().take(components) {
+                let at = offset + i * stride + c * size;
+                let raw = bytes
+                    .get(at..at + size)
+                    .ok_or("glTF accessor out of range")?;
+                *value = read_component(raw, component_type, normalized);
+            }
+
+            out.push(element);
+        }
+
+        Ok(out)
+    }
+
+    /// Indices are read as integers, floats would lose precision
+    fn read_indices(&self, index: usize) -> Result<Vec<u32>, String> {
+        let accessor = self.json.get("accessors").at(index);
+        let count = accessor
+            .get("count")
+            .as_usize()
+            .ok_or("glTF accessor without count")?;
+        let component_type = accessor.get("componentType").as_usize().unwrap_or(0);
+        let size = component_size(component_type)?;
+
+        let view = accessor
+            .get("bufferView")
+            .as_usize()
+            .ok_or("glTF indices without buffer view")?;
+        let (bytes, stride) = self.buffer_view(view)?;
+        let offset = accessor.get("byteOffset").as_usize().unwrap_or(0);
+        let stride = stride.unwrap_or(size);
+
+        if !fits(bytes.len(), offset, stride, size, count) {
+            return Err("glTF indices out of range".to_string());
+        }
+
+        (0..count)
+            .map(|i| {
+                let at = offset + i * stride;
+                let raw = bytes
+                    .get(at..at + size)
+                    .ok_or("glTF indices out of range")?;
+
+                Ok(match raw {
+                    [a] => *a as u32,
+                    [a, b] => u16::from_le_bytes([*a, *b]) as u32,
+                    [a, b, c, d] => u32::from_le_bytes([*a, *b, *c, *d]),
+                    _ => unreachable!(),
+                })
+            })
+            .collect()
+    }
+
+    /// `COLOR_0` is either RGB or RGBA
+    fn read_colors(&self, index: usize) -> Result<Vec<Vector4>, String> {
+        let rgb = self.json.get("accessors").at(index).get("type").as_str() == Some("VEC3");
+
+        Ok(self
+            .read_accessor::<4>(index)?
+            .into_iter()
+            .map(|[r, g, b, a]| Vector4::new(r, g, b, if rgb { 1.0 } else { a }))
+            .collect())
+    }
+
+    /// Images are loaded once, even when several textures use them
+    fn load_image(&mut self, texture: usize) -> Result<Option<Handle<Image>>, String> {
+        let Some(source) = self
+            .json
+            .get("textures")
+            .at(texture)
+            .get("source")
+            .as_usize()
+        else {
+            return Ok(None);
+        };
+
+        if let Some(Some(handle)) = self.images.get(source) {
+            return Ok(*handle);
+        }
+
+        let image = self.json.get("images").at(source);
+
+        let bytes = if let Some(uri) = image.get("uri").as_str() {
+            read_uri(uri, self.dir)?
+        } else if let Some(view) = image.get("bufferView").as_usize() {
+            self.buffer_view(view)?.0.to_vec()
+        } else {
+            return Ok(None);
+        };
+
+        let handle = load_texture(self.server, bytes);
+
+        if let Some(slot) = self.images.get_mut(source) {
+            *slot = Some(handle);
+        }
+
+        Ok(handle)
+    }
+}
+
+/// `matrix`, or `translation * rotation * scale`
+fn local_transform(node: &Json) -> Matrix4 {
+    if let Some(matrix) = node.get("matrix").as_f32s::<16>() {
+        let mut cols = [[0.0; 4]; 4];
+
+        for (i, value) in matrix.into_iter().enumerate() {
+            cols[i / 4][i % 4] = value;
+        }
+
+        return Matrix4::from_cols(cols);
+    }
+
+    let translation = node.get("translation").as_f32s::<3>().unwrap_or([0.0; 3]);
+    let rotation = node
+        .get("rotation")
+        .as_f32s::<4>()
+        .unwrap_or([0.0, 0.0, 0.0, 1.0]);
+    let scale = node.get("scale").as_f32s::<3>().unwrap_or([1.0; 3]);
+
+    Matrix4::from_translation(Vector3::from_array(translation))
+        * Matrix4::from_quaternion(Vector4::from_array(rotation))
+        * Matrix4::from_scale(Vector3::from_array(scale))
+}
+
+/// Strips and fans are turned into a list of triangles
+fn triangulate(indices: Vec<u32>, mode: usize) -> Vec<u32> {
+    match mode {
+        MODE_TRIANGLE_STRIP => indices
+            .windows(3)
+            .enumerate()
+            .flat_map(|(i, w)| {
+                if i % 2 == 0 {
+                    [w[0], w[1], w[2]]
+                } else {
+                    [w[1], w[0], w[2]]
+                }
+            })
+            .collect(),
+        MODE_TRIANGLE_FAN => indices
+            .windows(2)
+            .skip(1)
+            .flat_map(|w| [indices[0], w[0], w[1]])
+            .collect(),
+        _ => {
+            let mut indices = indices;
+            indices.truncate(indices.len() / 3 * 3);
+            indices
+        }
+    }
+}
+
+/// Whether `count` elements of `size` bytes, `stride` bytes apart from `offset`,
+/// are all within `length` bytes. Checked before allocating for them, since
+/// the count comes from the file. Elements can't overlap, so the bytes bound the count
+fn fits(length: usize, offset: usize, stride: usize, size: usize, count: usize) -> bool {
+    let Some(last) = count.checked_sub(1) else {
+        return true;
+    };
+
+    if last > 0 && stride < size {
+        return false;
+    }
+
+    last.checked_mul(stride)
+        .and_then(|end| end.checked_add(offset))
+        .and_then(|end| end.checked_add(size))
+        .is_some_and(|end| end <= length)
+}
+
+fn component_count(kind: &str) -> usize {
+    match kind {
+        "SCALAR" => 1,
+        "VEC2" => 2,
+        "VEC3" => 3,
+        "VEC4" | "MAT2" => 4,
+        "MAT3" => 9,
+        "MAT4" => 16,
+        _ => 1,
+    }
+}
+
+fn component_size(component_type: usize) -> Result<usize, String> {
+    match component_type {
+        5120 | 5121 => Ok(1),
+        5122 | 5123 => Ok(2),
+        5125 | 5126 => Ok(4),
+        other => Err(format!("Unknown glTF component type {other}")),
+    }
+}
+
+fn read_component(raw: &[u8], component_type: usize, normalized: bool) -> f32 {
+    match (component_type, raw) {
+        (5120, [a]) if normalized => (*a as i8 as f32 / 127.0).max(-1.0),
+        (5120, [a]) => *a as i8 as f32,
+        (5121, [a]) if normalized => *a as f32 / 255.0,
+        (5121, [a]) => *a as f32,
+        (5122, [a, b]) if normalized => (i16::from_le_bytes([*a, *b]) as f32 / 32767.0).max(-1.0),
+        (5122, [a, b]) => i16::from_le_bytes([*a, *b]) as f32,
+        (5123, [a, b]) if normalized => u16::from_le_bytes([*a, *b]) as f32 / 65535.0,
+        (5123, [a, b]) => u16::from_le_bytes([*a, *b]) as f32,
+        (5125, [a, b, c, d]) => u32::from_le_bytes([*a, *b, *c, *d]) as f32,
+        (5126, [a, b, c, d]) => f32::from_le_bytes([*a, *b, *c, *d]),
+        _ => 0.0,
+    }
+}
@@ -0,0 +1,355 @@
+//! Just enough JSON to read glTF documents
+
+use utils::FastHashMap;
+
+#[derive(Debug, Clone)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(FastHashMap<String, Json>),
+}
+
+static NULL: Json = Json::Null;
+
+/// Deeper arrays and objects are rejected, the parser would run out of stack
+const MAX_NESTING: usize = 128;
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            bytes: text.as_bytes(),
+            pos: 0,
+            depth: 0,
+        };
+
+        let value = parser.value()?;
+        parser.skip_whitespace();
+
+        if parser.pos != parser.bytes.len() {
+            return Err(format!("Trailing characters at {}", parser.pos));
+        }
+
+        Ok(value)
+    }
+
+    /// Member of an object, or `Null` when missing
+    #[inline]
+    pub fn get(&self, key: &str) -> &Json {
+        match self {
+            Self::Object(map) => map.get(key).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+
+    /// Element of an array, or `Null` when missing
+    #[inline]
+    pub fn at(&self, index: usize) -> &Json {
+        match self {
+            Self::Array(items) => items.get(index).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+
+    #[inline]
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    #[inline]
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            Self::Number(n) => Some(*n as f32),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Self::Number(n) if *n >= 0.0 => Some(*n as usize),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Elements of an array, empty for anything else
+    #[inline]
+    pub fn items(&self) -> &[Json] {
+        match self {
+            Self::Array(items) => items,
+            _ => &[],
+        }
+    }
+
+    /// An array of numbers, such as a matrix or a color
+    pub fn as_f32s<const N: usize>(&self) -> Option<[f32; N]> {
+        let items = self.items();
+
+        if items.len() != N {
+            return None;
+        }
+
+        let mut out = [0.0; N];
+
+        for (value, item) in out.iter_mut().zip(items) {
+            *value = item.as_f32()?;
+        }
+
+        Some(out)
+    }
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// Arrays and objects the parser is inside of
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.bytes.get(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' at {}", byte as char, self.pos))
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        if self.bytes[self.pos..].starts_with(word.as_bytes()) {
+            self.pos += word.len();
+            Ok(value)
+        } else {
+            Err(format!("Unexpected token at {}", self.pos))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        match self.peek() {
+            Some(b'{' | b'[') if self.depth >= MAX_NESTING => {
+                Err(format!("Nested too deeply at {}", self.pos))
+            }
+            Some(b'{') => self.nested(Self::object),
+            Some(b'[') => self.nested(Self::array),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(format!("Unexpected character at {}", self.pos)),
+            None => Err("Unexpected end of input".to_string()),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Json, String>) -> Result<Json, String> {
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+
+        value
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect(b'{')?;
+        let mut map = FastHashMap::default();
+
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Json::Object(map));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(b':')?;
+            map.insert(key, self.value()?);
+
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Json::Object(map));
+                }
+                _ => return Err(format!("Expected ',' or '}}' at {}", self.pos)),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Json::Array(items));
+        }
+
+        loop {
+            items.push(self.value()?);
+
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                }
+                _ => return Err(format!("Expected ',' or ']' at {}", self.pos)),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.pos;
+
+        while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.bytes.get(self.pos) {
+            self.pos += 1;
+        }
+
+        std::str::from_utf8(&self.bytes[start..self.pos])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .map(Json::Number)
+            .ok_or_else(|| format!("Invalid number at {start}"))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.bytes.get(self.pos) != Some(&b'"') {
+            return Err(format!("Expected a string at {}", self.pos));
+        }
+
+        self.pos += 1;
+        let mut out = Vec::new();
+
+        loop {
+            let Some(&byte) = self.bytes.get(self.pos) else {
+                return Err("Unterminated string".to_string());
+            };
+
+            self.pos += 1;
+
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let Some(&escape) = self.bytes.get(self.pos) else {
+                        return Err("Unterminated string".to_string());
+                    };
+
+                    self.pos += 1;
+
+                    match escape {
+                        b'n' => out.push(b'\n'),
+                        b't' => out.push(b'\t'),
+                        b'r' => out.push(b'\r'),
+                        b'b' => out.push(0x08),
+                        b'f' => out.push(0x0c),
+                        b'u' => {
+                            let ch = self.unicode_escape()?;
+                            let mut buf = [0; 4];
+                            out.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                        }
+                        other => out.push(other),
+                    }
+                }
+                _ => out.push(byte),
+            }
+        }
+
+        String::from_utf8(out).map_err(|_| "Invalid UTF-8 in string".to_string())
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self
+            .bytes
+            .get(self.pos..self.pos + 4)
+            .and_then(|d| std::str::from_utf8(d).ok())
+            .and_then(|d| u32::from_str_radix(d, 16).ok())
+            .ok_or_else(|| format!("Invalid unicode escape at {}", self.pos))?;
+
+        self.pos += 4;
+        Ok(digits)
+    }
+
+    /// `\uXXXX`, with surrogate pairs for characters outside the BMP
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex4()?;
+
+        let code = if (0xD800..0xDC00).contains(&high) && self.bytes[self.pos..].starts_with(b"\\u")
+        {
+            self.pos += 2;
+            let low = self.hex4()?;
+            0x10000 + ((high - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF)
+        } else {
+            high
+        };
+
+        Ok(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_values() {
+        let json = Json::parse(r#"{"a": [1, -2.5e1, true, null], "b": {"c": "d\u00e9"}}"#).unwrap();
+
+        assert_eq!(json.get("a").at(0).as_usize(), Some(1));
+        assert_eq!(json.get("a").at(1).as_f32(), Some(-25.0));
+        assert!(matches!(json.get("a").at(2), Json::Bool(true)));
+        assert!(json.get("a").at(3).is_null());
+        assert_eq!(json.get("b").get("c").as_str(), Some("dé"));
+        assert!(json.get("missing").is_null());
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        for text in [
+            "",
+            "{",
+            "[1, 2",
+            r#"{"a": "#,
+            r#"{"a": "b"#,
+            "tru",
+            r#""\u00"#,
+        ] {
+            assert!(Json::parse(text).is_err(), "{text:?} should not parse");
+        }
+    }
+
+    #[test]
+    fn rejects_trailing_characters() {
+        assert!(Json::parse("[1] 2").is_err());
+    }
+
+    #[test]
+    fn rejects_deep_nesting() {
+        let deep = "[".repeat(100_000) + &"]".repeat(100_000);
+        assert!(Json::parse(&deep).is_err());
+
+        let allowed = "[".repeat(MAX_NESTING) + &"]".repeat(MAX_NESTING);
+        assert!(Json::parse(&allowed).is_ok());
+    }
+}
@@ -0,0 +1,160 @@
+mod gltf;
+mod json;
+mod obj;
+
+use crate::{AssetServer, Image};
+use logging::warn;
+use math::{Matrix4, Vector2, Vector3, Vector4};
+use std::path::Path;
+use utils::Handle;
+
+/// A 3d model loaded from an OBJ or glTF file, flattened
+/// into primitives that each have a single material
+#[derive(Debug, Clone, Default)]
+pub struct Model {
+    pub primitives: Vec<ModelPrimitive>,
+}
+
+/// Triangles of a model that share a material
+#[derive(Debug, Clone)]
+pub struct ModelPrimitive {
+    /// Name of the glTF mesh or OBJ object it belongs to, may be empty
+    pub name: String,
+    pub positions: Vec<Vector3>,
+    /// Empty when the file has no normals
+    pub normals: Vec<Vector3>,
+    /// Empty when the file has no texture coordinates
+    pub uvs: Vec<Vector2>,
+    /// Empty when the file has no vertex colors
+    pub colors: Vec<Vector4>,
+    pub indices: Vec<u32>,
+    pub base_color: Vector4,
+    /// Base color texture, added to the atlas
+    pub texture: Option<Handle<Image>>,
+    /// Transform of the node the primitive is attached to,
+    /// combined with the transforms of its parents
+    pub transform: Matrix4,
+}
+
+impl ModelPrimitive {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            positions: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            colors: Vec::new(),
+            indices: Vec::new(),
+            base_color: Vector4::new(1.0, 1.0, 1.0, 1.0),
+            texture: None,
+            transform: Matrix4::identity(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFormat {
+    Obj,
+    /// Both `.gltf` and binary `.glb` files
+    Gltf,
+}
+
+impl ModelFormat {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+
+        match extension.as_str() {
+            "obj" => Some(Self::Obj),
+            "gltf" | "glb" => Some(Self::Gltf),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a model, `dir` is where external buffers,
+/// material libraries and textures are looked up
+pub(crate) fn parse(
+    server: &AssetServer,
+    bytes: &[u8],
+    format: ModelFormat,
+    dir: Option<&Path>,
+) -> Result<Model, String> {
+    match format {
+        ModelFormat::Obj => obj::parse(server, bytes, dir),
+        ModelFormat::Gltf => gltf::parse(server, bytes, dir),
+    }
+}
+
+/// Reads a file next to the model
+fn read_relative(dir: Option<&Path>, uri: &str) -> Result<Vec<u8>, String> {
+    let dir = dir.ok_or_else(|| format!("Cannot load '{uri}' from a model loaded from bytes"))?;
+    let path = dir.join(uri.replace("%20", " "));
+
+    std::fs::read(&path).map_err(|e| format!("Failed to read {}: {e}", path.display()))
+}
+
+/// Only PNG is decoded, other textures are skipped and
+/// the primitive keeps its base color
+fn load_texture(server: &AssetServer, bytes: Vec<u8>) -> Option<Handle<Image>> {
+    const PNG_SIGNATURE: &[u8] = &[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+    if !bytes.starts_with(PNG_SIGNATURE) {
+        warn!("Skipping model texture that is not a PNG");
+        return None;
+    }
+
+    Some(server.load_image_bytes(bytes))
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut count = 0;
+
+    for byte in text.bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            b' ' | b'\n' | b'\r' | b'\t' => continue,
+            _ => return Err("Invalid base64 data".to_string()),
+        };
+
+        bits = (bits << 6) | value as u32;
+        count += 6;
+
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decode_base64;
+
+    #[test]
+    fn decodes_base64() {
+        assert_eq!(decode_base64("").unwrap(), b"");
+        assert_eq!(decode_base64("TWFu").unwrap(), b"Man");
+        assert_eq!(decode_base64("TWE=").unwrap(), b"Ma");
+        assert_eq!(decode_base64("TQ==").unwrap(), b"M");
+        assert_eq!(decode_base64("TW\nFu").unwrap(), b"Man");
+    }
+
+    #[test]
+    fn decodes_url_safe_base64() {
+        assert_eq!(decode_base64("-_8").unwrap(), [0xfb, 0xff]);
+    }
+
+    #[test]
+    fn rejects_invalid_base64() {
+        assert!(decode_base64("TW*u").is_err());
+    }
+}
@@ -0,0 +1,306 @@
+//! Wavefront OBJ, with the colors and diffuse textures of its MTL libraries
+
+use super::{ModelPrimitive, load_texture, read_relative};
+use crate::{AssetServer, Image, Model};
+use logging::warn;
+use math::{Vector2, Vector3, Vector4};
+use std::path::Path;
+use utils::{FastHashMap, Handle};
+
+#[derive(Debug, Clone)]
+struct ObjMaterial {
+    color: Vector4,
+    texture: Option<Handle<Image>>,
+}
+
+impl Default for ObjMaterial {
+    fn default() -> Self {
+        Self {
+            color: Vector4::new(1.0, 1.0, 1.0, 1.0),
+            texture: None,
+        }
+    }
+}
+
+/// The primitive being filled, a new one starts
+/// with each object and each material change
+struct Builder {
+    primitive: ModelPrimitive,
+    /// `position/uv/normal` indices of the vertices already added
+    vertices: FastHashMap<(usize, Option<usize>, Option<usize>), u32>,
+}
+
+pub(super) fn parse(
+    server: &AssetServer,
+    bytes: &[u8],
+    dir: Option<&Path>,
+) -> Result<Model, String> {
+    let text = String::from_utf8_lossy(bytes);
+
+    let mut positions: Vec<Vector3> = Vec::new();
+    let mut colors: Vec<Vector4> = Vec::new();
+    let mut uvs: Vec<Vector2> = Vec::new();
+    let mut normals: Vec<Vector3> = Vec::new();
+
+    let mut materials: FastHashMap<String, ObjMaterial> = FastHashMap::default();
+    let mut textures: FastHashMap<String, Option<Handle<Image>>> = FastHashMap::default();
+
+    let mut model = Model::default();
+    let mut name = String::new();
+    let mut material = ObjMaterial::default();
+    let mut builder: Option<Builder> = None;
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut words = line.split_whitespace();
+
+        let Some(keyword) = words.next() else {
+            continue;
+        };
+
+        let error = |what: &str| format!("Invalid {what} on line {} of OBJ", number + 1);
+
+        match keyword {
+            "v" => {
+                let values = parse_floats(words).ok_or_else(|| error("vertex"))?;
+
+                let [x, y, z] = values
+                    .get(..3)
+                    .ok_or_else(|| error("vertex"))?
+                    .try_into()
+                    .unwrap();
+                positions.push(Vector3::new(x, y, z));
+
+                // Vertex colors are a common extension, `v x y z r g b`
+                colors.push(match values.get(3..6) {
+                    Some(&[r, g, b]) => Vector4::new(r, g, b, 1.0),
+                    _ => Vector4::new(1.0, 1.0, 1.0, 1.0),
+                });
+            }
+            "vt" => {
+                let values = parse_floats(words).ok_or_else(|| error("texture coordinate"))?;
+                let u = *values.first().ok_or_else(|| error("texture coordinate"))?;
+                let v = values.get(1).copied().unwrap_or(0.0);
+
+                // OBJ has its origin at the bottom left of the texture
+                uvs.push(Vector2::new(u, 1.0 - v));
+            }
+            "vn" => {
+                let values = parse_floats(words).ok_or_else(|| error("normal"))?;
+                let [x, y, z] = values
+                    .get(..3)
+                    .ok_or_else(|| error("normal"))?
+                    .try_into()
+                    .unwrap();
+                normals.push(Vector3::new(x, y, z));
+            }
+            "f" => {
+                let builder = builder.get_or_insert_with(|| Builder::new(&name, &material));
+
+                let mut face = Vec::new();
+
+                for word in words {
+                    let mut parts = word.split('/');
+                    let position =
+                        resolve(parts.next(), positions.len()).ok_or_else(|| error("face"))?;
+                    let uv = resolve(parts.next(), uvs.len());
+                    let normal = resolve(parts.next(), normals.len());
+
+                    face.push(builder.vertex(
+                        (position, uv, normal),
+                        &positions,
+                        &colors,
+                        &uvs,
+                        &normals,
+                    ));
+                }
+
+                // Polygons are split into a fan, which is fine for convex faces
+                for i in 1..face.len().saturating_sub(1) {
+                    builder
+                        .primitive
+                        .indices
+                        .extend([face[0], face[i], face[i + 1]]);
+                }
+            }
+            "o" | "g" => {
+                finish(&mut builder, &mut model);
+                name = words.collect::<Vec<_>>().join(" ");
+            }
+            "usemtl" => {
+                finish(&mut builder, &mut model);
+
+                let key = words.collect::<Vec<_>>().join(" ");
+                material = materials.get(&key).cloned().unwrap_or_else(|| {
+                    warn!("Unknown OBJ material '{}'", key);
+                    ObjMaterial::default()
+                });
+            }
+            "mtllib" => {
+                for library in words {
+                    match read_relative(dir, library) {
+                        Ok(bytes) => parse_mtl(server, &bytes, dir, &mut materials, &mut textures),
+                        Err(e) => warn!("{}", e),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    finish(&mut builder, &mut model);
+
+    Ok(model)
+}
+
+impl Builder {
+    fn new(name: &str, material: &ObjMaterial) -> Self {
+        let mut primitive = ModelPrimitive::new(name);
+        primitive.base_color = material.color;
+        primitive.texture = material.texture;
+
+        Self {
+            primitive,
+            vertices: FastHashMap::default(),
+        }
+    }
+
+    fn vertex(
+        &mut self,
+        key: (usize, Option<usize>, Option<usize>),
+        positions: &[Vector3],
+        colors: &[Vector4],
+        uvs: &[Vector2],
+        normals: &[Vector3],
+    ) -> u32 {
+        if let Some(&index) = self.vertices.get(&key) {
+            return index;
+        }
+
+        let (position, uv, normal) = key;
+        let primitive = &mut self.primitive;
+        let index = primitive.positions.len() as u32;
+
+        primitive.positions.push(positions[position]);
+        primitive.colors.push(colors[position]);
+        primitive
+            .uvs
+            .push(uv.map_or(Vector2::zeros(), |uv| uvs[uv]));
+        primitive
+            .normals
+            .push(normal.map_or(Vector3::zeros(), |n| normals[n]));
+
+        self.vertices.insert(key, index);
+        index
+    }
+}
+
+/// Moves the primitive being filled to the model, leaving out
+/// the attributes that none of its vertices have
+fn finish(builder: &mut Option<Builder>, model: &mut Model) {
+    let Some(Builder { mut primitive, .. }) = builder.take() else {
+        return;
+    };
+
+    if primitive.indices.is_empty() {
+        return;
+    }
+
+    let white = Vector4::new(1.0, 1.0, 1.0, 1.0);
+
+    if primitive.colors.iter().all(|c| *c == white) {
+        primitive.colors.clear();
+    }
+
+    if primitive.uvs.iter().all(|uv| *uv == Vector2::zeros()) {
+        primitive.uvs.clear();
+    }
+
+    if primitive.normals.iter().all(|n| *n == Vector3::zeros()) {
+        primitive.normals.clear();
+    }
+
+    model.primitives.push(primitive);
+}
+
+/// OBJ indices start at 1, negative ones count from the end
+fn resolve(word: Option<&str>, len: usize) -> Option<usize> {
+    let index: i64 = word.filter(|w| !w.is_empty())?.parse().ok()?;
+
+    let index = if index < 0 {
+        len as i64 + index
+    } else {
+        index - 1
+    };
+
+    (0..len as i64).contains(&index).then_some(index as usize)
+}
+
+fn parse_floats<'a>(words: impl Iterator<Item = &'a str>) -> Option<Vec<f32>> {
+    words.map(|w| w.parse().ok()).collect()
+}
+
+fn parse_mtl(
+    server: &AssetServer,
+    bytes: &[u8],
+    dir: Option<&Path>,
+    materials: &mut FastHashMap<String, ObjMaterial>,
+    textures: &mut FastHashMap<String, Option<Handle<Image>>>,
+) {
+    let text = String::from_utf8_lossy(bytes);
+    let mut current: Option<String> = None;
+
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut words = line.split_whitespace();
+
+        let Some(keyword) = words.next() else {
+            continue;
+        };
+
+        if keyword == "newmtl" {
+            let name = words.collect::<Vec<_>>().join(" ");
+            materials.insert(name.clone(), ObjMaterial::default());
+            current = Some(name);
+            continue;
+        }
+
+        let Some(material) = current.as_ref().and_then(|name| materials.get_mut(name)) else {
+            continue;
+        };
+
+        match keyword {
+            "Kd" => {
+                if let Some(&[r, g, b]) = parse_floats(words).as_deref() {
+                    material.color = Vector4::new(r, g, b, material.color.w);
+                }
+            }
+            "d" => {
+                if let Some(&[d]) = parse_floats(words).as_deref() {
+                    material.color.w = d;
+                }
+            }
+            "Tr" => {
+                if let Some(&[tr]) = parse_floats(words).as_deref() {
+                    material.color.w = 1.0 - tr;
+                }
+            }
+            "map_Kd" => {
+                // Options such as `-s 1 1 1` come before the file name
+                let Some(file) = line.split_whitespace().last() else {
+                    continue;
+                };
+
+                let texture = textures.entry(file.to_string()).or_insert_with(|| {
+                    read_relative(dir, file)
+                        .map_err(|e| warn!("{}", e))
+                        .ok()
+                        .and_then(|bytes| load_texture(server, bytes))
+                });
+
+                material.texture = *texture;
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,477 @@
+use crate::{
+    Context, RenderContext,
+    input::{Input, KeyCode},
+};
+use crossbeam_channel::Receiver;
+use globals::profiling;
+use logging::{LogLevel, error, info, warn};
+use renderer::{Color, Draw, Layer};
+use std::{collections::BTreeMap, fmt::Display, io::BufRead, mem, str::FromStr, thread};
+use utils::Label;
+
+/// Key opening and closing the console
+const TOGGLE_KEY: KeyCode = KeyCode::Backquote;
+
+/// Part of the window covered by the console
+const HEIGHT: f32 = 0.4;
+
+const LINE: f32 = 18.0;
+const PADDING: f32 = 8.0;
+const BACKGROUND: Color = Color::rgba(0.02, 0.02, 0.05, 0.9);
+
+pub type CommandResult = Result<(), String>;
+
+type Command = Box<dyn FnMut(&Args, &mut Context) -> CommandResult + Send>;
+
+/// Arguments given to a console command, split on whitespace.
+/// Double quotes group words into a single argument
+#[derive(Debug, Default, Clone)]
+pub struct Args {
+    args: Vec<String>,
+}
+
+impl Args {
+    /// Splits a line into the command name and its arguments
+    fn parse(line: &str) -> Option<(String, Self)> {
+        let mut words = Vec::new();
+        let mut word = String::new();
+        let mut quoted = false;
+        let mut started = false;
+
+        for ch in line.chars() {
+            match ch {
+                '"' => {
+                    quoted = !quoted;
+                    started = true;
+                }
+                ch if ch.is_whitespace() && !quoted => {
+                    if started {
+                        words.push(mem::take(&mut word));
+                        started = false;
+                    }
+                }
+                ch => {
+                    word.push(ch);
+                    started = true;
+                }
+            }
+        }
+
+        if started {
+            words.push(word);
+        }
+
+        let mut words = words.into_iter();
+        let name = words.next()?;
+
+        Some((
+            name,
+            Self {
+                args: words.collect(),
+            },
+        ))
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.args.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.args.is_empty()
+    }
+
+    #[inline]
+    pub fn raw(&self, index: usize) -> Option<&str> {
+        self.args.get(index).map(String::as_str)
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.args.iter().map(String::as_str)
+    }
+
+    /// Parses the argument at `index`, the error
+    /// is meant to be returned as is by the command
+    pub fn get<T>(&self, index: usize) -> Result<T, String>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let raw = self
+            .raw(index)
+            .ok_or_else(|| format!("Missing argument {}", index + 1))?;
+
+        raw.parse()
+            .map_err(|e| format!("Invalid argument '{raw}': {e}"))
+    }
+
+    /// Like [`Args::get`], but falls back to `default` if the argument is missing
+    pub fn get_or<T>(&self, index: usize, default: T) -> Result<T, String>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match self.raw(index) {
+            Some(_) => self.get(index),
+            None => Ok(default),
+        }
+    }
+}
+
+/// Drop-down developer console, opened with the key under Escape.
+///
+/// Its output is the log buffer, so commands report with the `info!`, `warn!`... macros.
+/// Lines can also be run from code with [`Console::run`],
+/// or from the terminal after calling [`Console::listen_stdin`]
+pub struct Console {
+    commands: BTreeMap<String, Command>,
+    open: bool,
+    input: String,
+    history: Vec<String>,
+    /// Position in the history while browsing it with the arrows
+    browsing: Option<usize>,
+    /// Lines waiting to be executed at the start of the next frame
+    pending: Vec<String>,
+    stdin: Option<Receiver<String>>,
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        let mut console = Self {
+            commands: BTreeMap::new(),
+            open: false,
+            input: String::new(),
+            history: Vec::new(),
+            browsing: None,
+            pending: Vec::new(),
+            stdin: None,
+        };
+
+        console.register_builtins();
+        console
+    }
+}
+
+impl Console {
+    /// Registers a command, replacing any command with the same name
+    ///
+    /// ```ignore
+    /// ctx.console.register("spawn", |args, ctx| {
+    ///     let count: u32 = args.get_or(0, 1)?;
+    ///     // ...
+    ///     Ok(())
+    /// });
+    /// ```
+    pub fn register<F>(&mut self, name: &str, command: F)
+    where
+        F: FnMut(&Args, &mut Context) -> CommandResult + Send + 'static,
+    {
+        self.commands.insert(name.to_string(), Box::new(command));
+    }
+
+    #[inline]
+    pub fn unregister(&mut self, name: &str) {
+        self.commands.remove(name);
+    }
+
+    /// Queues a line, executed at the start of the next frame
+    #[inline]
+    pub fn run<T: Into<String>>(&mut self, line: T) {
+        self.pending.push(line.into());
+    }
+
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    #[inline]
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+    }
+
+    /// Runs every line typed in the terminal, for headless testing.
+    ///
+    /// Only a single window should listen, since the lines are not shared
+    pub fn listen_stdin(&mut self) {
+        if self.stdin.is_some() {
+            return;
+        }
+
+        let (tx, rx) = crossbeam_channel::unbounded();
+
+        thread::spawn(move || {
+            for line in std::io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        self.stdin = Some(rx);
+    }
+
+    fn register_builtins(&mut self) {
+        self.register("help", |_, ctx| {
+            let names = ctx.console.commands.keys().cloned().collect::<Vec<_>>();
+
+            info!("Commands: {}", names.join(", "));
+            Ok(())
+        });
+
+        self.register("clear", |_, _| {
+            globals::logs::get().write().clear();
+            Ok(())
+        });
+
+        self.register("fps", |args, ctx| {
+            match args.get::<u32>(0)? {
+                0 => ctx.time.uncap_fps(),
+                fps => ctx.time.set_target_fps(fps),
+            }
+
+            Ok(())
+        });
+
+        self.register("timescale", |args, ctx| {
+            let scale = args.get::<f32>(0)?;
+
+            if scale < 0.0 {
+                return Err("The time scale can't be negative".to_string());
+            }
+
+            ctx.time.set_scale(scale);
+            Ok(())
+        });
+
+        self.register("scene", |args, ctx| {
+            let label = args.raw(0).ok_or("Missing scene label")?;
+
+            ctx.scenes.request_change(Label::new(label));
+            Ok(())
+        });
+
+        self.register("trace", |args, _| match args.raw(0) {
+            Some("start") => {
+                profiling::start_trace();
+                info!("Tracing started");
+                Ok(())
+            }
+            Some("stop") => {
+                let path = args.raw(1).unwrap_or("trace.json");
+
+                profiling::stop_trace(path).map_err(|e| e.to_string())?;
+                info!("Trace written to {}", path);
+                Ok(())
+            }
+            _ => Err("Usage: trace start | trace stop [path]".to_string()),
+        });
+
+        self.register("wireframe", |_, ctx| {
+            let wireframe = !ctx.scene.wireframe();
+
+            ctx.scene.set_wireframe(wireframe);
+            Ok(())
+        });
+    }
+
+    /// Handles the keyboard while the console is open,
+    /// hiding it from the scene
+    pub(crate) fn update(&mut self, input: &mut Input) {
+        if input.key_pressed(&TOGGLE_KEY) {
+            self.open = !self.open;
+            input.consume_keyboard();
+            return;
+        }
+
+        if let Some(stdin) = &self.stdin {
+            self.pending.extend(stdin.try_iter());
+        }
+
+        if !self.open {
+            return;
+        }
+
+        self.input.push_str(input.text_input());
+
+        if input.key_repeated(&KeyCode::Backspace) {
+            self.input.pop();
+        }
+
+        if input.key_pressed(&KeyCode::Tab) {
+            self.complete();
+        }
+
+        if input.key_repeated(&KeyCode::ArrowUp) {
+            self.browse(true);
+        }
+
+        if input.key_repeated(&KeyCode::ArrowDown) {
+            self.browse(false);
+        }
+
+        if input.key_pressed(&KeyCode::Enter) {
+            self.submit();
+        }
+
+        if input.key_pressed(&KeyCode::Escape) {
+            self.open = false;
+        }
+
+        input.consume_keyboard();
+    }
+
+    /// Takes the lines to execute this frame
+    #[inline]
+    pub(crate) fn take_pending(&mut self) -> Vec<String> {
+        mem::take(&mut self.pending)
+    }
+
+    /// Executes a line, the command is taken out of the console
+    /// while it runs, so that it can access the console itself through `ctx`
+    pub(crate) fn execute(line: &str, ctx: &mut Context) {
+        let Some((name, args)) = Args::parse(line) else {
+            return;
+        };
+
+        let Some(mut command) = ctx.console.commands.remove(&name) else {
+            warn!(
+                "Unknown command '{}', type 'help' to list the commands",
+                name
+            );
+            return;
+        };
+
+        if let Err(e) = command(&args, ctx) {
+            error!("{}: {}", name, e);
+        }
+
+        // Unless the command replaced itself
+        ctx.console.commands.entry(name).or_insert(command);
+    }
+
+    fn submit(&mut self) {
+        let line = mem::take(&mut self.input);
+        let line = line.trim();
+
+        self.browsing = None;
+
+        if line.is_empty() {
+            return;
+        }
+
+        info!("> {}", line);
+
+        if self.history.last().is_none_or(|last| last != line) {
+            self.history.push(line.to_string());
+        }
+
+        self.pending.push(line.to_string());
+    }
+
+    /// Completes the command name, or lists the candidates if there are many
+    fn complete(&mut self) {
+        if self.input.contains(char::is_whitespace) {
+            return;
+        }
+
+        let candidates = self
+            .commands
+            .keys()
+            .filter(|name| name.starts_with(self.input.as_str()))
+            .collect::<Vec<_>>();
+
+        match candidates.as_slice() {
+            [] => {}
+            [name] => self.input = format!("{name} "),
+            [first, rest @ ..] => {
+                let common = rest.iter().fold(first.len(), |len, name| {
+                    first
+                        .chars()
+                        .zip(name.chars())
+                        .take_while(|(a, b)| a == b)
+                        .count()
+                        .min(len)
+                });
+
+                info!(
+                    "{}",
+                    candidates
+                        .iter()
+                        .map(|name| name.as_str())
+                        .collect::<Vec<_>>()
+                        .join("  ")
+                );
+
+                self.input = first.chars().take(common).collect();
+            }
+        }
+    }
+
+    fn browse(&mut self, older: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        let last = self.history.len() - 1;
+
+        self.browsing = match (self.browsing, older) {
+            (None, true) => Some(last),
+            (None, false) => None,
+            (Some(i), true) => Some(i.saturating_sub(1)),
+            (Some(i), false) if i < last => Some(i + 1),
+            (Some(_), false) => None,
+        };
+
+        self.input = match self.browsing {
+            Some(i) => self.history[i].clone(),
+            None => String::new(),
+        };
+    }
+
+    pub(crate) fn render(&self, ctx: &RenderContext, draw: &mut Draw) {
+        if !self.open {
+            return;
+        }
+
+        let previous = (*draw.layer(), *draw.color());
+        let width = ctx.window.width() as f32;
+        let height = (ctx.window.height() as f32 * HEIGHT).max(LINE * 2.0 + PADDING * 2.0);
+
+        draw.set_layer(Layer::Ui);
+        draw.set_color(BACKGROUND);
+        draw.rect(0.0, 0.0, width, height);
+
+        // Newest logs at the bottom, right above the input line
+        let logs = globals::logs::get().read();
+        let input_y = height - PADDING - LINE;
+        let mut y = input_y - LINE;
+
+        for (level, message) in logs.iter().rev() {
+            if y < PADDING {
+                break;
+            }
+
+            draw.set_color(match level {
+                LogLevel::Trace => Color::Cyan,
+                LogLevel::Debug => Color::Purple,
+                LogLevel::Info => Color::White,
+                LogLevel::Warn => Color::Yellow,
+                LogLevel::Error | LogLevel::Fatal => Color::Red,
+            });
+
+            draw.debug_text(message, PADDING, y);
+            y -= LINE;
+        }
+
+        draw.set_color(Color::Green);
+        draw.debug_text(format!("> {}_", self.input), PADDING, input_y);
+
+        draw.set_layer(previous.0);
+        draw.set_color(previous.1);
+    }
+}
@@ -11,10 +11,23 @@ pub struct Input {
     pub(crate) held_keys: FastHashSet<KeyCode>,
     pub(crate) pressed_keys: FastHashSet<KeyCode>,
     pub(crate) released_keys: FastHashSet<KeyCode>,
+    /// Keys that were pressed, or repeated by the OS because they are held
+    pub(crate) repeated_keys: FastHashSet<KeyCode>,
 
+    /// Text typed this frame, following the keyboard layout
+    #[get(ty = &str, name = "text_input")]
+    pub(crate) text: String,
+
+    /// In window pixels, as used by the Ui layer
     #[get]
     pub(crate) mouse_position: Vector2,
 
+    /// In the virtual resolution of the World layer if there is one,
+    /// equal to the mouse position otherwise. A new virtual resolution
+    /// shows from the next callback of the scene on
+    #[get]
+    pub(crate) virtual_mouse_position: Vector2,
+
     #[get]
     pub(crate) mouse_delta: Vector2,
     #[get(copied)]
@@ -30,7 +43,10 @@ impl Default for Input {
             held_keys: FastHashSet::default(),
             pressed_keys: FastHashSet::default(),
             released_keys: FastHashSet::default(),
+            repeated_keys: FastHashSet::default(),
+            text: String::new(),
             mouse_position: Vector2::zeros(),
+            virtual_mouse_position: Vector2::zeros(),
             mouse_delta: Vector2::zeros(),
             wheel_delta: 0.0,
             held_mouse: FastHashSet::default(),
@@ -55,6 +71,13 @@ impl Input {
         self.pressed_keys.contains(key)
     }
 
+    /// Like [`Input::key_pressed`], but also true when the OS repeats the key
+    /// because it is held, useful for text editing and menu navigation
+    #[inline]
+    pub fn key_repeated(&self, key: &KeyCode) -> bool {
+        self.repeated_keys.contains(key)
+    }
+
     #[inline]
     pub fn mouse_held(&self, button: &MouseButton) -> bool {
         self.held_mouse.contains(button)
@@ -65,10 +88,22 @@ impl Input {
         self.pressed_mouse.contains(button)
     }
 
+    /// Hides this frame's key presses and typed text from the scene,
+    /// when something else, like the console, handled them
+    #[inline]
+    pub(crate) fn consume_keyboard(&mut self) {
+        self.pressed_keys.clear();
+        self.released_keys.clear();
+        self.repeated_keys.clear();
+        self.text.clear();
+    }
+
     #[inline]
     pub(crate) fn flush(&mut self) {
         self.pressed_keys.clear();
         self.released_keys.clear();
+        self.repeated_keys.clear();
+        self.text.clear();
         self.pressed_mouse.clear();
         self.mouse_delta.set(0.0, 0.0);
         self.wheel_delta = 0.0;
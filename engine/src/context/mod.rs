@@ -1,3 +1,4 @@
+pub mod console;
 pub mod input;
 pub mod states;
 pub mod sysinfo;
@@ -11,6 +12,7 @@ mod window;
 use crate::{
     AppOwned,
     context::{
+        console::Console,
         input::Input,
         scene_changer::SceneChanger,
         states::{GlobalStates, States},
@@ -18,7 +20,7 @@ use crate::{
     },
 };
 use assets::{AssetServer, AssetServerGuard};
-use globals::profiling::{self, Statistics};
+use globals::profiling::Statistics;
 use renderer::{Draw, Renderer, Scene};
 use std::sync::Arc;
 use winit::{
@@ -50,7 +52,9 @@ pub struct AppContext {
     pub states: States,
     pub globals: GlobalStates,
     pub info: Arc<SystemInfo>,
+    /// Owned by each window, refreshed at the end of its frames
     pub profiling: Statistics,
+    pub console: Console,
 }
 
 unsafe impl Send for AppContext {}
@@ -71,6 +75,7 @@ pub struct Context<'a> {
     pub globals: &'a GlobalStates,
     pub info: &'a SystemInfo,
     pub profiling: &'a Statistics,
+    pub console: &'a mut Console,
 }
 
 /// Basically equal to [`Context`], but will be immutable,
@@ -85,6 +90,7 @@ pub struct RenderContext<'a> {
     pub globals: &'a GlobalStates,
     pub info: &'a SystemInfo,
     pub profiling: &'a Statistics,
+    pub console: &'a Console,
 }
 
 impl AppContext {
@@ -104,12 +110,15 @@ impl AppContext {
             states,
             globals: app_owned.globals,
             info: app_owned.info,
-            profiling: profiling::get_stats(),
+            profiling: Statistics::default(),
+            console: Console::default(),
         }
     }
 
     #[inline]
     pub(crate) fn as_temp_mut(&mut self) -> Context<'_> {
+        self.update_virtual_mouse();
+
         Context {
             window: &mut self.window,
             time: &mut self.time,
@@ -122,11 +131,14 @@ impl AppContext {
             states: &mut self.states,
             info: &self.info,
             profiling: &self.profiling,
+            console: &mut self.console,
         }
     }
 
     #[inline]
     pub(crate) fn as_render_context(&mut self) -> (RenderContext<'_>, Draw<'_>) {
+        self.update_virtual_mouse();
+
         let ctx = RenderContext {
             window: &self.window,
             time: &self.time,
@@ -137,6 +149,7 @@ impl AppContext {
             globals: &self.globals,
             info: &self.info,
             profiling: &self.profiling,
+            console: &self.console,
         };
 
         let draw = Draw::new(&mut self.render, self.assets.guard());
@@ -144,6 +157,13 @@ impl AppContext {
         (ctx, draw)
     }
 
+    /// Follows the virtual resolution, which the scene can change in any of its
+    /// callbacks, so that the next ones see the mouse in the new resolution
+    #[inline]
+    fn update_virtual_mouse(&mut self) {
+        self.input.virtual_mouse_position = self.render.to_virtual(self.input.mouse_position);
+    }
+
     #[inline]
     pub(crate) fn handle_device_event(&mut self, event: DeviceEvent) {
         match event {
@@ -161,26 +181,39 @@ impl AppContext {
         match event {
             WindowEvent::Resized(size) => {
                 self.render.resize(size.into());
+                self.update_virtual_mouse();
             }
 
-            WindowEvent::KeyboardInput { event, .. } => match event.physical_key {
-                PhysicalKey::Code(code) => {
-                    if event.state.is_pressed() {
-                        if !event.repeat {
-                            self.input.pressed_keys.insert(code);
+            WindowEvent::KeyboardInput { event, .. } => {
+                if event.state.is_pressed()
+                    && let Some(text) = &event.text
+                {
+                    self.input
+                        .text
+                        .extend(text.chars().filter(|ch| !ch.is_control()));
+                }
+
+                match event.physical_key {
+                    PhysicalKey::Code(code) => {
+                        if event.state.is_pressed() {
+                            if !event.repeat {
+                                self.input.pressed_keys.insert(code);
+                            }
+                            self.input.held_keys.insert(code);
+                            self.input.repeated_keys.insert(code);
+                        } else {
+                            self.input.held_keys.remove(&code);
+                            self.input.released_keys.insert(code);
                         }
-                        self.input.held_keys.insert(code);
-                    } else {
-                        self.input.held_keys.remove(&code);
-                        self.input.released_keys.insert(code);
                     }
+                    PhysicalKey::Unidentified(_) => {}
                 }
-                PhysicalKey::Unidentified(_) => {}
-            },
+            }
 
             WindowEvent::CursorMoved { position, .. } => {
                 self.input.mouse_position.x = position.x as f32;
                 self.input.mouse_position.y = position.y as f32;
+                self.update_virtual_mouse();
             }
 
             WindowEvent::MouseInput { state, button, .. } => {
@@ -1,4 +1,4 @@
-use globals::{consts, profiling};
+use globals::{consts, profiling::TimeStats};
 use logging::info;
 use macros::{Get, Set};
 use spin_sleep::SpinSleeper;
@@ -52,6 +52,8 @@ pub struct Time {
     fps_uncap: bool,
 
     /// FPS calculation stuff
+    #[get(name = "frame_history")]
+    /// Duration of the most recent frames, oldest first
     frame_times: VecDeque<Duration>,
     frame_times_sum: Duration,
     fps_sample_size: usize,
@@ -148,8 +150,6 @@ impl Time {
             0.0
         };
 
-        profiling::update_time(self.delta_time, self.fps, self.tps);
-
         if self.tick_timer.is_finished() {
             self.tps = self.tick_counter;
             self.tick_counter = 0;
@@ -159,6 +159,20 @@ impl Time {
         self.last_frame = self.this_frame;
     }
 
+    /// Snapshot given to the statistics at the end of the frame
+    #[inline]
+    pub(crate) fn stats(&self) -> TimeStats {
+        let frame_time = self.frame_times.back().copied().unwrap_or_default();
+
+        TimeStats::new(
+            self.elapsed_time.as_secs_f32(),
+            self.delta_time,
+            frame_time.as_secs_f32() * 1000.0,
+            self.fps,
+            self.tps,
+        )
+    }
+
     /// Checks whether the game loop should do a tick or not
     ///
     /// If yes, it returns the instant at which the tick started,
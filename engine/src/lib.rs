@@ -1,16 +1,22 @@
 mod builder;
 mod context;
 mod lifecycle;
+mod overlay;
 mod scene;
 
+pub mod ui;
+
 use crate::{
-    context::{AppContext, WinitWindow, states::GlobalStates, sysinfo::SystemInfo},
+    context::{
+        AppContext, WinitWindow, console::Console, states::GlobalStates, sysinfo::SystemInfo,
+    },
     lifecycle::{LoopState, WindowHandle, WindowMessage},
+    overlay::DebugOverlay,
     scene::SceneManager,
 };
 use assets::AssetServer;
 use crossbeam_channel::Receiver;
-use globals::{TrackingAllocator, profiling};
+use globals::{AllocCategory, TrackingAllocator, alloc_scope};
 use logging::{LogError, LogLevel, error, info, warn};
 use renderer::Renderer;
 use std::{
@@ -27,7 +33,8 @@ use winit::{
 
 // === RE-EXPORTS ===
 pub use builder::{AppBuilder, WindowBuilder};
-pub use context::{Context, Monitor, Monitors, RenderContext, Time, Window, input};
+pub use context::{Context, Monitor, Monitors, RenderContext, Time, Window, console, input};
+pub use globals::{profile_scope, profiling};
 pub use renderer::Draw;
 pub use scene::Scene;
 pub use utils::{Label, label};
@@ -133,13 +140,16 @@ impl App {
         // Must create the surface on the main thread on Windows
         let (surface, config) = Renderer::create_surface(window.inner().clone());
 
-        let handle = thread::spawn(move || {
+        let thread_name = format!("window {label}");
+        let handle = thread::Builder::new().name(thread_name).spawn(move || {
             let _ctx = logging::ctx!("window", label);
             let renderer = Renderer::from_surface(surface, config, &app_owned.assets.guard());
 
             Self::start_loop(scenes, window, renderer, app_owned, rx);
         });
 
+        let handle = handle.expect("Failed to spawn the window thread");
+
         let window_handle = WindowHandle {
             sender: tx,
             thread: handle,
@@ -189,6 +199,7 @@ impl App {
     ) {
         let mut context = AppContext::new(window, renderer, app_owned);
         let mut scenes = SceneManager::new(scenes);
+        let mut overlay = DebugOverlay::default();
 
         scenes.current().load(&mut context.as_temp_mut());
         context.window.request_redraw();
@@ -248,7 +259,7 @@ impl App {
                         context.handle_device_event(event);
                     }
 
-                    Self::frame(&mut context, &mut scenes);
+                    Self::frame(&mut context, &mut scenes, &mut overlay);
                     context.window.request_redraw();
                 }
             }
@@ -256,24 +267,51 @@ impl App {
     }
 
     #[inline]
-    fn frame(context: &mut AppContext, scenes: &mut SceneManager) {
+    fn frame(context: &mut AppContext, scenes: &mut SceneManager, overlay: &mut DebugOverlay) {
         profiling::reset_frame();
+        context.assets.rasterize_requested_fonts();
         context.time.frame_start();
         context.time.update();
 
+        // Before the scene, so that it doesn't see the keys typed in the console
+        context.console.update(&mut context.input);
+
+        for line in context.console.take_pending() {
+            Console::execute(&line, &mut context.as_temp_mut());
+        }
+
+        let game_alloc = alloc_scope(AllocCategory::Game);
+
         while let Some(tick_start) = context.time.next_tick() {
+            profile_scope!("fixed_update");
             scenes.current().fixed_update(&mut context.as_temp_mut());
             context.time.do_tick(tick_start);
         }
 
-        scenes.current().update(&mut context.as_temp_mut());
+        {
+            profile_scope!("update");
+            scenes.current().update(&mut context.as_temp_mut());
+        }
+
+        overlay.update(&context.input);
 
         {
+            profile_scope!("render");
             let (render_context, mut draw) = context.as_render_context();
             scenes.current().render(&render_context, &mut draw);
+            overlay.render(&render_context, &mut draw);
+            render_context.console.render(&render_context, &mut draw);
         }
 
-        context.render.present(&context.assets.guard());
+        drop(game_alloc);
+
+        {
+            let _alloc = alloc_scope(AllocCategory::Renderer);
+
+            context
+                .render
+                .present(&context.assets.guard(), context.time.delta());
+        }
 
         context.time.frame_end();
         context.input.flush();
@@ -289,7 +327,7 @@ impl App {
             scenes.switch_to(new_scene, &mut context.as_temp_mut());
         }
 
-        context.profiling = profiling::get_stats();
+        context.profiling.end_frame(context.time.stats());
         context.time.wait_for_next_frame();
     }
 }
@@ -0,0 +1,290 @@
+use crate::{
+    RenderContext,
+    input::{Input, KeyCode},
+};
+use globals::{
+    AllocCategory,
+    profiling::{self, History},
+};
+use logging::LogLevel;
+use renderer::{Color, Draw, Layer};
+use std::time::Duration;
+use utils::ByteSize;
+
+/// Key toggling the overlay
+const TOGGLE_KEY: KeyCode = KeyCode::F3;
+
+/// Frames kept for the graphs
+const HISTORY: usize = 120;
+
+/// Logs visible at once in the console
+const CONSOLE_LINES: usize = 12;
+
+const LINE: f32 = 18.0;
+const PADDING: f32 = 8.0;
+const WIDTH: f32 = 340.0;
+const GRAPH_HEIGHT: f32 = 40.0;
+
+/// Deepest profiling scopes shown, and how many at most
+const SCOPE_DEPTH: u32 = 2;
+const SCOPE_LINES: usize = 10;
+
+/// Upper bounds of the frame time histogram buckets, in milliseconds
+const BUCKETS: [f32; 8] = [4.0, 8.0, 12.0, 17.0, 25.0, 33.0, 50.0, f32::INFINITY];
+
+const BACKGROUND: Color = Color::rgba(0.0, 0.0, 0.0, 0.75);
+const GRAPH_BACKGROUND: Color = Color::rgba(1.0, 1.0, 1.0, 0.08);
+
+/// Built-in HUD showing the frame timings, the render and memory
+/// statistics and the latest logs, toggled with F3
+#[derive(Default)]
+pub(crate) struct DebugOverlay {
+    visible: bool,
+    /// How many logs the console is scrolled up by
+    scroll: usize,
+}
+
+impl DebugOverlay {
+    pub(crate) fn update(&mut self, input: &Input) {
+        if input.key_pressed(&TOGGLE_KEY) {
+            self.visible = !self.visible;
+        }
+
+        if !self.visible {
+            return;
+        }
+
+        let logs = globals::logs::get().read().len();
+        let max_scroll = logs.saturating_sub(CONSOLE_LINES);
+        let scroll = self.scroll as f32 + input.wheel_delta();
+
+        self.scroll = (scroll.max(0.0) as usize).min(max_scroll);
+    }
+
+    pub(crate) fn render(&self, ctx: &RenderContext, draw: &mut Draw) {
+        if !self.visible {
+            return;
+        }
+
+        let previous = (*draw.layer(), *draw.color());
+
+        draw.set_layer(Layer::Ui);
+
+        self.draw_stats(ctx, draw);
+        self.draw_console(ctx, draw);
+
+        draw.set_layer(previous.0);
+        draw.set_color(previous.1);
+    }
+
+    fn draw_stats(&self, ctx: &RenderContext, draw: &mut Draw) {
+        let time = ctx.time;
+        let render = ctx.profiling.render;
+
+        let memory = ctx.profiling.mem;
+
+        let frames = time
+            .frame_history()
+            .iter()
+            .map(Duration::as_secs_f32)
+            .collect::<Vec<_>>();
+
+        let scopes = profiling::last_frame_scopes()
+            .into_iter()
+            .filter(|scope| scope.depth() <= SCOPE_DEPTH)
+            .take(SCOPE_LINES)
+            .collect::<Vec<_>>();
+
+        let (x, mut y) = (PADDING, PADDING);
+
+        let categories = match cfg!(feature = "track-allocations") {
+            true => AllocCategory::COUNT,
+            false => 0,
+        };
+        let height =
+            LINE * (17 + categories + scopes.len()) as f32 + GRAPH_HEIGHT * 3.0 + PADDING * 6.0;
+
+        draw.set_color(BACKGROUND);
+        draw.rect(0.0, 0.0, WIDTH + PADDING * 2.0, height);
+
+        let last_frame = frames.last().copied().unwrap_or(0.0) * 1000.0;
+
+        draw.set_color(Color::White);
+        let p99 = ctx.profiling.history.frame_time.percentile(99.0);
+
+        draw.debug_text(
+            format!(
+                "FPS {} ({:.2} ms, p99 {:.2} ms)",
+                time.fps(),
+                last_frame,
+                p99.unwrap_or(0.0)
+            ),
+            x,
+            y,
+        );
+        y += LINE;
+
+        Self::graph(draw, &ctx.profiling.history.fps, x, y, Color::Green);
+        y += GRAPH_HEIGHT + PADDING;
+
+        draw.set_color(Color::White);
+        draw.debug_text(format!("TPS {}", time.tps()), x, y);
+        y += LINE;
+
+        Self::graph(draw, &ctx.profiling.history.tps, x, y, Color::Cyan);
+        y += GRAPH_HEIGHT + PADDING;
+
+        draw.set_color(Color::White);
+        draw.debug_text("Frame times (ms)", x, y);
+        y += LINE;
+
+        Self::histogram(draw, &frames, x, y);
+        y += GRAPH_HEIGHT + LINE + PADDING;
+
+        let lines = [
+            format!("Draw calls       {}", render.draw_calls()),
+            format!("Vertices         {}", render.vertices()),
+            format!("Indices          {}", render.indices()),
+            format!("Instance writes  {}", render.instance_writes()),
+            format!("Pipeline switches {}", render.pipeline_switches()),
+            format!(
+                "Geometry buffers {} ({})",
+                render.geometry_buffers(),
+                ByteSize::from_bytes(render.geometry_buffers_size() as u64)
+            ),
+            format!("Present (cpu)    {:.2} ms", render.present_time()),
+            match render.gpu() {
+                gpu if gpu.supported => format!(
+                    "Gpu {:.2} ms (world {:.2}, ui {:.2}, post {:.2})",
+                    gpu.frame, gpu.world, gpu.ui, gpu.post
+                ),
+                _ => "Gpu timings unsupported".to_string(),
+            },
+            String::new(),
+            format!("Memory   {}", ByteSize::from_bytes(memory.current() as u64)),
+            format!("Peak     {}", ByteSize::from_bytes(memory.peak() as u64)),
+            format!(
+                "Freed    {}",
+                ByteSize::from_bytes(memory.deallocated() as u64)
+            ),
+        ];
+
+        // Only tracked with the feature, they would all be zero otherwise
+        let categories = AllocCategory::ALL
+            .iter()
+            .filter(|_| cfg!(feature = "track-allocations"))
+            .map(|&category| {
+                let stats = memory.category(category);
+
+                format!(
+                    "  {:<9}{} ({} allocs)",
+                    category.name(),
+                    ByteSize::from_bytes(stats.current() as u64),
+                    stats.frame_allocations()
+                )
+            });
+
+        draw.set_color(Color::White);
+
+        for line in lines.into_iter().chain(categories) {
+            draw.debug_text(line, x, y);
+            y += LINE;
+        }
+
+        y += LINE;
+
+        for scope in scopes {
+            let indent = "  ".repeat(scope.depth() as usize);
+            let ms = scope.duration().as_secs_f32() * 1000.0;
+
+            draw.debug_text(format!("{indent}{} {ms:.2} ms", scope.name()), x, y);
+            y += LINE;
+        }
+    }
+
+    fn draw_console(&self, ctx: &RenderContext, draw: &mut Draw) {
+        let logs = globals::logs::get().read();
+        let height = CONSOLE_LINES as f32 * LINE + PADDING * 2.0;
+        let width = ctx.window.width() as f32;
+        let mut y = ctx.window.height() as f32 - height;
+
+        draw.set_color(BACKGROUND);
+        draw.rect(0.0, y, width, height);
+
+        y += PADDING;
+
+        let end = logs.len().saturating_sub(self.scroll);
+        let start = end.saturating_sub(CONSOLE_LINES);
+
+        for (level, message) in &logs[start..end] {
+            draw.set_color(match level {
+                LogLevel::Trace => Color::Cyan,
+                LogLevel::Debug => Color::Purple,
+                LogLevel::Info => Color::Green,
+                LogLevel::Warn => Color::Yellow,
+                LogLevel::Error | LogLevel::Fatal => Color::Red,
+            });
+
+            draw.debug_text(message, PADDING, y);
+            y += LINE;
+        }
+    }
+
+    /// Bars of the last values of the history, scaled to their highest value
+    fn graph(draw: &mut Draw, history: &History, x: f32, y: f32, color: Color) {
+        draw.set_color(GRAPH_BACKGROUND);
+        draw.rect(x, y, WIDTH, GRAPH_HEIGHT);
+
+        let values = history.iter().skip(history.len().saturating_sub(HISTORY));
+        let max = values.clone().fold(1.0, f32::max);
+        let bar = WIDTH / HISTORY as f32;
+
+        draw.set_color(color);
+
+        for (i, value) in values.enumerate() {
+            let height = GRAPH_HEIGHT * value / max;
+
+            draw.rect(x + i as f32 * bar, y + GRAPH_HEIGHT - height, bar, height);
+        }
+    }
+
+    /// How many of the recent frames took each range of time
+    fn histogram(draw: &mut Draw, frames: &[f32], x: f32, y: f32) {
+        let mut counts = [0u32; BUCKETS.len()];
+
+        for frame in frames {
+            let ms = frame * 1000.0;
+            let bucket = BUCKETS.iter().position(|&max| ms < max).unwrap_or(0);
+
+            counts[bucket] += 1;
+        }
+
+        let max = counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+        let width = WIDTH / BUCKETS.len() as f32;
+
+        draw.set_color(GRAPH_BACKGROUND);
+        draw.rect(x, y, WIDTH, GRAPH_HEIGHT);
+
+        for (i, count) in counts.iter().enumerate() {
+            let bar_x = x + i as f32 * width;
+            let height = GRAPH_HEIGHT * *count as f32 / max;
+
+            draw.set_color(if BUCKETS[i] <= 17.0 {
+                Color::Green
+            } else if BUCKETS[i] <= 33.0 {
+                Color::Yellow
+            } else {
+                Color::Red
+            });
+            draw.rect(bar_x + 1.0, y + GRAPH_HEIGHT - height, width - 2.0, height);
+
+            let label = match BUCKETS[i] {
+                max if max.is_finite() => format!("<{max}"),
+                _ => "50+".to_string(),
+            };
+
+            draw.set_color(Color::Gray);
+            draw.debug_text(label, bar_x + 2.0, y + GRAPH_HEIGHT + 2.0);
+        }
+    }
+}
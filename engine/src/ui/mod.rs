@@ -0,0 +1,874 @@
+//! Immediate mode widgets, drawn through [`Draw`] on [`Layer::Ui`].
+//!
+//! The [`Ui`] holds what has to survive between frames (which widget is held,
+//! focused, how far areas are scrolled), and is usually kept in the scene.
+//! Widgets are then declared every frame on the [`UiFrame`] returned by [`Ui::begin`]:
+//!
+//! ```ignore
+//! fn render(&mut self, ctx: &RenderContext, draw: &mut Draw) {
+//!     let mut ui = self.ui.begin(ctx, draw);
+//!
+//!     ui.panel("Settings", 20.0, 20.0, 240.0, |ui| {
+//!         ui.checkbox("Fullscreen", &mut self.fullscreen);
+//!         ui.slider("Volume", &mut self.volume, 0.0..=1.0);
+//!
+//!         if ui.button("Quit") {
+//!             self.quit = true;
+//!         }
+//!     });
+//! }
+//! ```
+//!
+//! Widgets are identified by their label, which must be unique inside a panel
+//! or an area. Anything after `##` in a label is part of the id, but not displayed.
+//!
+//! Tab / Shift+Tab (or the arrow keys) move the keyboard focus, Enter and Space
+//! activate the focused widget and Escape clears the focus.
+
+mod style;
+
+use crate::{
+    RenderContext,
+    input::{Input, KeyCode, MouseButton},
+};
+use assets::{Font, FontFace};
+use macros::{Get, Set};
+use math::Vector2;
+use renderer::{Color, Draw, Layer, TextLayout};
+use std::{mem, ops::RangeInclusive};
+use utils::{FastHashMap, Label, Rect};
+
+// === RE-EXPORTS ===
+pub use style::UiStyle;
+
+type Id = u64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Row,
+    Column,
+}
+
+/// Where the next widgets are placed
+struct Scope {
+    direction: Direction,
+    origin: Vector2,
+    cursor: Vector2,
+    /// Width given to the widgets that fill their scope
+    width: f32,
+    /// Extent of the widgets placed so far
+    size: Vector2,
+}
+
+impl Scope {
+    fn new(direction: Direction, origin: Vector2, width: f32) -> Self {
+        Self {
+            direction,
+            origin,
+            cursor: origin,
+            width,
+            size: Vector2::zeros(),
+        }
+    }
+
+    fn allocate(&mut self, width: f32, height: f32, spacing: f32) -> Rect {
+        let rect = Rect {
+            x: self.cursor.x,
+            y: self.cursor.y,
+            width,
+            height,
+        };
+
+        self.size.x = self.size.x.max(rect.x + width - self.origin.x);
+        self.size.y = self.size.y.max(rect.y + height - self.origin.y);
+
+        match self.direction {
+            Direction::Column => self.cursor.y += height + spacing,
+            Direction::Row => self.cursor.x += width + spacing,
+        }
+
+        rect
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Interaction {
+    hovered: bool,
+    held: bool,
+    clicked: bool,
+    focused: bool,
+}
+
+/// State of the widgets, kept between frames
+#[derive(Default)]
+#[derive(Get, Set)]
+pub struct Ui {
+    #[get]
+    #[get(mut)]
+    #[set]
+    style: UiStyle,
+
+    active: Option<Id>,
+    focus: Option<Id>,
+    /// Focusable widgets, in the order they were declared
+    focus_order: Vec<Id>,
+    last_focus_order: Vec<Id>,
+    /// Content size of panels and scroll areas, from the last frame
+    sizes: FastHashMap<Id, Vector2>,
+    scroll: FastHashMap<Id, f32>,
+    /// Byte offset of the caret in the focused text field
+    caret: usize,
+    hovered: bool,
+}
+
+impl Ui {
+    pub fn new(style: UiStyle) -> Self {
+        Self {
+            style,
+            ..Self::default()
+        }
+    }
+
+    /// Whether the mouse was over the ui last frame,
+    /// so that the game can ignore the clicks meant for it
+    #[inline]
+    pub fn wants_mouse(&self) -> bool {
+        self.hovered || self.active.is_some()
+    }
+
+    /// Whether a widget has the keyboard focus
+    #[inline]
+    pub fn wants_keyboard(&self) -> bool {
+        self.focus.is_some()
+    }
+
+    /// Starts declaring the widgets of this frame, they are drawn
+    /// on [`Layer::Ui`] and the state of `draw` is restored when the frame is dropped
+    pub fn begin<'a, 'd>(
+        &'a mut self,
+        ctx: &'a RenderContext<'_>,
+        draw: &'a mut Draw<'d>,
+    ) -> UiFrame<'a, 'd> {
+        let face = self
+            .style
+            .font
+            .unwrap_or_else(|| ctx.assets.debug_font().into());
+        let font = ctx.assets.get_font(face.handle());
+        let px = face.size(font) as f32;
+        let line_height = font
+            .horizontal_line_metrics(px)
+            .map(|metrics| metrics.new_line_size)
+            .unwrap_or(px);
+
+        let restore = (*draw.layer(), *draw.color(), *draw.text_layout());
+
+        draw.set_layer(Layer::Ui);
+        draw.set_text_layout(TextLayout::default());
+
+        self.navigate(ctx.input);
+
+        let root = Scope::new(Direction::Column, Vector2::zeros(), self.style.item_width);
+
+        UiFrame {
+            ui: self,
+            input: ctx.input,
+            face,
+            font,
+            px,
+            line_height,
+            draw,
+            scopes: vec![root],
+            ids: Vec::new(),
+            clips: Vec::new(),
+            hovered: false,
+            restore,
+        }
+    }
+
+    fn navigate(&mut self, input: &Input) {
+        if input.key_pressed(&KeyCode::Escape) {
+            self.focus = None;
+            return;
+        }
+
+        let shift = input.key_held(&KeyCode::ShiftLeft) || input.key_held(&KeyCode::ShiftRight);
+        let tab = input.key_repeated(&KeyCode::Tab);
+
+        let forward = (tab && !shift) || input.key_repeated(&KeyCode::ArrowDown);
+        let backward = (tab && shift) || input.key_repeated(&KeyCode::ArrowUp);
+
+        let len = self.last_focus_order.len();
+
+        if forward == backward || len == 0 {
+            return;
+        }
+
+        let current = self
+            .focus
+            .and_then(|id| self.last_focus_order.iter().position(|&f| f == id));
+
+        let next = match (current, forward) {
+            (None, true) => 0,
+            (None, false) => len - 1,
+            (Some(i), true) => (i + 1) % len,
+            (Some(i), false) => (i + len - 1) % len,
+        };
+
+        self.focus = Some(self.last_focus_order[next]);
+        self.caret = usize::MAX;
+    }
+}
+
+/// Widgets of a single frame, see [`Ui::begin`]
+pub struct UiFrame<'a, 'd> {
+    ui: &'a mut Ui,
+    input: &'a Input,
+    face: FontFace,
+    font: &'a Font,
+    px: f32,
+    line_height: f32,
+    draw: &'a mut Draw<'d>,
+
+    scopes: Vec<Scope>,
+    /// Ids of the panels and areas the widgets are declared in
+    ids: Vec<Id>,
+    /// Visible part of the scroll areas the widgets are declared in
+    clips: Vec<Rect>,
+    hovered: bool,
+    restore: (Layer, Color, TextLayout),
+}
+
+/// Layout
+impl UiFrame<'_, '_> {
+    /// Places the widgets declared in `contents` next to each other
+    #[inline]
+    pub fn row(&mut self, contents: impl FnOnce(&mut Self)) {
+        self.group(Direction::Row, contents);
+    }
+
+    /// Places the widgets declared in `contents` under each other,
+    /// which is the default outside of rows
+    #[inline]
+    pub fn column(&mut self, contents: impl FnOnce(&mut Self)) {
+        self.group(Direction::Column, contents);
+    }
+
+    /// Leaves some empty space
+    #[inline]
+    pub fn space(&mut self, amount: f32) {
+        match self.scope().direction {
+            Direction::Column => self.allocate(0.0, amount),
+            Direction::Row => self.allocate(amount, 0.0),
+        };
+    }
+
+    /// A window-like container at a fixed position, its height follows its content
+    pub fn panel(
+        &mut self,
+        title: &str,
+        x: f32,
+        y: f32,
+        width: f32,
+        contents: impl FnOnce(&mut Self),
+    ) {
+        let id = self.id(title);
+        let style = self.ui.style;
+        let title_height = self.widget_height();
+        let content = self.ui.sizes.get(&id).copied().unwrap_or_default();
+
+        let rect = Rect {
+            x,
+            y,
+            width,
+            height: title_height + content.y + style.padding.y * 2.0,
+        };
+
+        self.fill(&rect, style.background);
+        self.fill(
+            &Rect {
+                height: title_height,
+                ..rect
+            },
+            style.title,
+        );
+        self.text(
+            Self::display(title),
+            x + style.padding.x,
+            y + style.padding.y,
+            style.text,
+        );
+
+        if Self::contains(&rect, self.input.mouse_position()) {
+            self.hovered = true;
+        }
+
+        let origin = Vector2::new(x + style.padding.x, y + title_height + style.padding.y);
+        let scope = self.scoped(
+            id,
+            Scope::new(Direction::Column, origin, width - style.padding.x * 2.0),
+            contents,
+        );
+
+        self.ui.sizes.insert(id, scope.size);
+    }
+
+    /// An area of a fixed height, scrolled with the mouse wheel.
+    ///
+    /// Widgets are cut at its edges
+    pub fn scroll_area(&mut self, label: &str, height: f32, contents: impl FnOnce(&mut Self)) {
+        let id = self.id(label);
+        let style = self.ui.style;
+        let width = self.fill_width();
+        let rect = self.allocate(width, height);
+
+        let content = self.ui.sizes.get(&id).copied().unwrap_or_default();
+        let max_scroll = (content.y + style.padding.y * 2.0 - height).max(0.0);
+        let mut scroll = self.ui.scroll.get(&id).copied().unwrap_or(0.0);
+
+        let hovered = self.pointed(&rect);
+
+        if hovered {
+            self.hovered = true;
+            scroll -= self.input.wheel_delta() * self.line_height;
+        }
+
+        scroll = scroll.clamp(0.0, max_scroll);
+        self.ui.scroll.insert(id, scroll);
+
+        if self.visible(&rect) {
+            self.fill(&rect, style.background);
+        }
+
+        let clip = match self.clips.last() {
+            Some(parent) => Self::intersect(parent, &rect),
+            None => rect,
+        };
+
+        let origin = Vector2::new(rect.x + style.padding.x, rect.y + style.padding.y - scroll);
+        let inner_width = width - style.padding.x * 2.0 - style.scrollbar_width;
+
+        self.clips.push(clip);
+        self.draw.push_clip(clip.x, clip.y, clip.width, clip.height);
+
+        let scope = self.scoped(
+            id,
+            Scope::new(Direction::Column, origin, inner_width),
+            contents,
+        );
+
+        self.draw.pop_clip();
+        self.clips.pop();
+
+        self.ui.sizes.insert(id, scope.size);
+
+        if max_scroll > 0.0 && self.visible(&rect) {
+            let total = max_scroll + height;
+            let thumb = height * height / total;
+
+            self.fill(
+                &Rect {
+                    x: rect.x + rect.width - style.scrollbar_width,
+                    y: rect.y + (height - thumb) * scroll / max_scroll,
+                    width: style.scrollbar_width,
+                    height: thumb,
+                },
+                style.hovered,
+            );
+        }
+    }
+}
+
+/// Widgets
+impl UiFrame<'_, '_> {
+    pub fn label<T: AsRef<str>>(&mut self, text: T) {
+        let text = text.as_ref();
+        let style = self.ui.style;
+        let rect = self.allocate(self.text_width(text), self.widget_height());
+
+        if self.visible(&rect) {
+            self.text(text, rect.x, rect.y + style.padding.y, style.text);
+        }
+    }
+
+    /// Returns true when clicked, or activated with the keyboard
+    pub fn button(&mut self, label: &str) -> bool {
+        let id = self.id(label);
+        let text = Self::display(label);
+        let style = self.ui.style;
+
+        let width = self.text_width(text) + style.padding.x * 2.0;
+        let rect = self.allocate(width, self.widget_height());
+
+        if !self.visible(&rect) {
+            return false;
+        }
+
+        let interaction = self.interact(id, &rect);
+
+        self.fill(&rect, self.widget_color(&interaction));
+        self.focus_ring(&rect, &interaction);
+        self.text(
+            text,
+            rect.x + style.padding.x,
+            rect.y + style.padding.y,
+            style.text,
+        );
+
+        interaction.clicked
+    }
+
+    /// Returns true when `value` was toggled
+    pub fn checkbox(&mut self, label: &str, value: &mut bool) -> bool {
+        let id = self.id(label);
+        let text = Self::display(label);
+        let style = self.ui.style;
+        let size = self.line_height;
+
+        let width = size + style.padding.x + self.text_width(text);
+        let rect = self.allocate(width, self.widget_height());
+
+        if !self.visible(&rect) {
+            return false;
+        }
+
+        let interaction = self.interact(id, &rect);
+
+        if interaction.clicked {
+            *value = !*value;
+        }
+
+        let check = Rect {
+            x: rect.x,
+            y: rect.y + style.padding.y,
+            width: size,
+            height: size,
+        };
+
+        self.fill(&check, self.widget_color(&interaction));
+
+        if *value {
+            let inset = (size * 0.2).floor();
+
+            self.fill(
+                &Rect {
+                    x: check.x + inset,
+                    y: check.y + inset,
+                    width: size - inset * 2.0,
+                    height: size - inset * 2.0,
+                },
+                style.accent,
+            );
+        }
+
+        self.focus_ring(&check, &interaction);
+        self.text(
+            text,
+            rect.x + size + style.padding.x,
+            rect.y + style.padding.y,
+            style.text,
+        );
+
+        interaction.clicked
+    }
+
+    /// Dragged with the mouse, or moved by a hundredth of the range
+    /// with the left and right arrows when focused. Returns true when `value` changed
+    pub fn slider(&mut self, label: &str, value: &mut f32, range: RangeInclusive<f32>) -> bool {
+        let id = self.id(label);
+        let text = Self::display(label);
+        let style = self.ui.style;
+        let (min, max) = (*range.start(), *range.end());
+
+        let rect = self.allocate(self.fill_width(), self.widget_height());
+
+        if !self.visible(&rect) {
+            return false;
+        }
+
+        let interaction = self.interact(id, &rect);
+        let previous = *value;
+
+        if interaction.held {
+            let t = ((self.input.mouse_position().x - rect.x) / rect.width).clamp(0.0, 1.0);
+            *value = min + t * (max - min);
+        }
+
+        if interaction.focused {
+            let step = (max - min) / 100.0;
+
+            if self.input.key_repeated(&KeyCode::ArrowLeft) {
+                *value -= step;
+            }
+
+            if self.input.key_repeated(&KeyCode::ArrowRight) {
+                *value += step;
+            }
+        }
+
+        *value = value.clamp(min, max);
+
+        let t = match max > min {
+            true => (*value - min) / (max - min),
+            false => 0.0,
+        };
+
+        self.fill(&rect, self.widget_color(&interaction));
+        self.fill(
+            &Rect {
+                width: rect.width * t,
+                ..rect
+            },
+            style.accent,
+        );
+        self.focus_ring(&rect, &interaction);
+
+        let text = format!("{text}: {value:.2}");
+        let x = rect.x + (rect.width - self.text_width(&text)) / 2.0;
+
+        self.text(&text, x, rect.y + style.padding.y, style.text);
+
+        *value != previous
+    }
+
+    /// Single line text input, the label is shown while it's empty.
+    /// Returns true when `value` changed
+    pub fn text_field(&mut self, label: &str, value: &mut String) -> bool {
+        let id = self.id(label);
+        let style = self.ui.style;
+
+        let rect = self.allocate(self.fill_width(), self.widget_height());
+
+        if !self.visible(&rect) {
+            return false;
+        }
+
+        let interaction = self.interact(id, &rect);
+        let changed = interaction.focused && self.edit(value);
+
+        let color = match interaction.hovered || interaction.focused {
+            true => style.hovered,
+            false => style.widget,
+        };
+
+        self.fill(&rect, color);
+        self.focus_ring(&rect, &interaction);
+
+        let x = rect.x + style.padding.x;
+        let y = rect.y + style.padding.y;
+
+        if value.is_empty() && !interaction.focused {
+            self.text(Self::display(label), x, y, style.text_weak);
+            return changed;
+        }
+
+        // Only the end of the text is shown when it's too long
+        let inner = rect.width - style.padding.x * 2.0;
+        let mut start = 0;
+
+        while self.text_width(&value[start..]) > inner {
+            start += value[start..].chars().next().map_or(1, char::len_utf8);
+        }
+
+        self.text(&value[start..], x, y, style.text);
+
+        if interaction.focused {
+            let caret = self.ui.caret.max(start);
+            let offset = self.text_width(&value[start..caret]);
+
+            self.fill(
+                &Rect {
+                    x: x + offset,
+                    y,
+                    width: 1.0,
+                    height: self.line_height,
+                },
+                style.accent,
+            );
+        }
+
+        changed
+    }
+}
+
+/// Internals
+impl UiFrame<'_, '_> {
+    fn id(&self, label: &str) -> Id {
+        let parent = self.ids.last().copied().unwrap_or(0);
+
+        (parent.rotate_left(5) ^ Label::hash(label) as u64).wrapping_mul(0x100_0000_01b3)
+    }
+
+    #[inline]
+    fn display(label: &str) -> &str {
+        label.split("##").next().unwrap_or(label)
+    }
+
+    #[inline]
+    fn scope(&mut self) -> &mut Scope {
+        self.scopes
+            .last_mut()
+            .expect("The root scope is never popped")
+    }
+
+    fn group(&mut self, direction: Direction, contents: impl FnOnce(&mut Self)) {
+        let width = self.fill_width();
+        let origin = self.scope().cursor;
+
+        self.scopes.push(Scope::new(direction, origin, width));
+        contents(self);
+
+        let scope = self.scopes.pop().expect("Pushed above");
+
+        self.allocate(scope.size.x, scope.size.y);
+    }
+
+    /// Runs `contents` inside of `scope`, with the ids of the widgets derived from `id`
+    fn scoped(&mut self, id: Id, scope: Scope, contents: impl FnOnce(&mut Self)) -> Scope {
+        self.ids.push(id);
+        self.scopes.push(scope);
+
+        contents(self);
+
+        self.ids.pop();
+        self.scopes.pop().expect("Pushed above")
+    }
+
+    #[inline]
+    fn allocate(&mut self, width: f32, height: f32) -> Rect {
+        let spacing = self.ui.style.spacing;
+
+        self.scope().allocate(width, height, spacing)
+    }
+
+    #[inline]
+    fn fill_width(&self) -> f32 {
+        match self.scopes.last() {
+            Some(scope) if scope.direction == Direction::Column => scope.width,
+            _ => self.ui.style.item_width,
+        }
+    }
+
+    #[inline]
+    fn widget_height(&self) -> f32 {
+        self.line_height + self.ui.style.padding.y * 2.0
+    }
+
+    fn text_width(&self, text: &str) -> f32 {
+        text.chars()
+            .map(|ch| self.font.metrics(ch, self.px).advance_width)
+            .sum()
+    }
+
+    /// Whether some of the rect is inside of the current scroll area
+    fn visible(&self, rect: &Rect) -> bool {
+        self.clips
+            .last()
+            .is_none_or(|clip| rect.y < clip.y + clip.height && rect.y + rect.height > clip.y)
+    }
+
+    /// Whether the mouse is over the visible part of the rect
+    fn pointed(&self, rect: &Rect) -> bool {
+        let mouse = self.input.mouse_position();
+
+        Self::contains(rect, mouse)
+            && self
+                .clips
+                .last()
+                .is_none_or(|clip| Self::contains(clip, mouse))
+    }
+
+    #[inline]
+    fn contains(rect: &Rect, point: &Vector2) -> bool {
+        point.x >= rect.x
+            && point.x < rect.x + rect.width
+            && point.y >= rect.y
+            && point.y < rect.y + rect.height
+    }
+
+    fn intersect(a: &Rect, b: &Rect) -> Rect {
+        let x = a.x.max(b.x);
+        let y = a.y.max(b.y);
+
+        Rect {
+            x,
+            y,
+            width: ((a.x + a.width).min(b.x + b.width) - x).max(0.0),
+            height: ((a.y + a.height).min(b.y + b.height) - y).max(0.0),
+        }
+    }
+
+    /// Mouse and keyboard state of a focusable widget
+    fn interact(&mut self, id: Id, rect: &Rect) -> Interaction {
+        let left = MouseButton::Left;
+
+        self.ui.focus_order.push(id);
+
+        let hovered = self.pointed(rect) && self.ui.active.is_none_or(|active| active == id);
+
+        if hovered {
+            self.hovered = true;
+
+            if self.input.mouse_pressed(&left) {
+                self.ui.active = Some(id);
+
+                if self.ui.focus != Some(id) {
+                    self.ui.focus = Some(id);
+                    self.ui.caret = usize::MAX;
+                }
+            }
+        }
+
+        let active = self.ui.active == Some(id);
+        let released = active && !self.input.mouse_held(&left);
+        let focused = self.ui.focus == Some(id);
+
+        let activated = focused
+            && (self.input.key_pressed(&KeyCode::Enter) || self.input.key_pressed(&KeyCode::Space));
+
+        Interaction {
+            hovered,
+            held: active && !released,
+            clicked: (released && hovered) || activated,
+            focused,
+        }
+    }
+
+    /// Applies the keyboard input to the focused text field
+    fn edit(&mut self, value: &mut String) -> bool {
+        let input = self.input;
+        let before = value.len();
+        let mut changed = false;
+
+        let mut caret = self.ui.caret.min(value.len());
+
+        while !value.is_char_boundary(caret) {
+            caret -= 1;
+        }
+
+        let prev = |value: &str, caret: usize| {
+            value[..caret].chars().next_back().map_or(0, char::len_utf8)
+        };
+        let next =
+            |value: &str, caret: usize| value[caret..].chars().next().map_or(0, char::len_utf8);
+
+        if !input.text_input().is_empty() {
+            value.insert_str(caret, input.text_input());
+            caret += input.text_input().len();
+        }
+
+        if input.key_repeated(&KeyCode::Backspace) && caret > 0 {
+            let len = prev(value, caret);
+
+            caret -= len;
+            value.replace_range(caret..caret + len, "");
+            changed = true;
+        }
+
+        if input.key_repeated(&KeyCode::Delete) && caret < value.len() {
+            let len = next(value, caret);
+
+            value.replace_range(caret..caret + len, "");
+            changed = true;
+        }
+
+        if input.key_repeated(&KeyCode::ArrowLeft) {
+            caret -= prev(value, caret);
+        }
+
+        if input.key_repeated(&KeyCode::ArrowRight) {
+            caret += next(value, caret);
+        }
+
+        if input.key_pressed(&KeyCode::Home) {
+            caret = 0;
+        }
+
+        if input.key_pressed(&KeyCode::End) {
+            caret = value.len();
+        }
+
+        if input.key_pressed(&KeyCode::Enter) {
+            self.ui.focus = None;
+        }
+
+        self.ui.caret = caret;
+
+        changed || value.len() != before
+    }
+
+    #[inline]
+    fn widget_color(&self, interaction: &Interaction) -> Color {
+        let style = &self.ui.style;
+
+        match (interaction.held, interaction.hovered) {
+            (true, _) => style.active,
+            (false, true) => style.hovered,
+            (false, false) => style.widget,
+        }
+    }
+
+    #[inline]
+    fn fill(&mut self, rect: &Rect, color: Color) {
+        self.draw.set_color(color);
+        self.draw.rect(rect.x, rect.y, rect.width, rect.height);
+    }
+
+    fn focus_ring(&mut self, rect: &Rect, interaction: &Interaction) {
+        if !interaction.focused {
+            return;
+        }
+
+        let t = self.ui.style.focus_thickness;
+        let color = self.ui.style.focus;
+        let Rect {
+            x,
+            y,
+            width,
+            height,
+        } = *rect;
+
+        self.draw.set_color(color);
+        self.draw.rect(x - t, y - t, width + t * 2.0, t);
+        self.draw.rect(x - t, y + height, width + t * 2.0, t);
+        self.draw.rect(x - t, y, t, height);
+        self.draw.rect(x + width, y, t, height);
+    }
+
+    #[inline]
+    fn text(&mut self, text: &str, x: f32, y: f32, color: Color) {
+        self.draw.set_color(color);
+        self.draw.text(self.face, text, x, y);
+    }
+}
+
+impl Drop for UiFrame<'_, '_> {
+    fn drop(&mut self) {
+        let left = MouseButton::Left;
+
+        if !self.input.mouse_held(&left) {
+            self.ui.active = None;
+        }
+
+        if self.input.mouse_pressed(&left) && !self.hovered {
+            self.ui.focus = None;
+        }
+
+        // The focused widget was not declared this frame
+        if self
+            .ui
+            .focus
+            .is_some_and(|focus| !self.ui.focus_order.contains(&focus))
+        {
+            self.ui.focus = None;
+        }
+
+        self.ui.hovered = self.hovered;
+        self.ui.last_focus_order = mem::take(&mut self.ui.focus_order);
+
+        let (layer, color, text_layout) = self.restore;
+
+        self.draw.set_layer(layer);
+        self.draw.set_color(color);
+        self.draw.set_text_layout(text_layout);
+    }
+}
@@ -0,0 +1,63 @@
+use assets::FontFace;
+use math::Vector2;
+use renderer::Color;
+
+/// Colors and metrics used by the widgets of a [`super::Ui`]
+#[derive(Debug, Clone, Copy)]
+pub struct UiStyle {
+    /// `None` uses the debug font
+    pub font: Option<FontFace>,
+
+    pub text: Color,
+    /// Text of disabled or secondary elements, like placeholders
+    pub text_weak: Color,
+    /// Panels and scroll areas
+    pub background: Color,
+    pub title: Color,
+    pub widget: Color,
+    pub hovered: Color,
+    pub active: Color,
+    /// Filled part of sliders, checked checkboxes, text caret
+    pub accent: Color,
+    /// Outline of the widget focused with the keyboard
+    pub focus: Color,
+
+    /// Space between the borders of a widget and its content
+    pub padding: Vector2,
+    /// Space between two widgets
+    pub spacing: f32,
+    /// Width of sliders and text fields placed in a row
+    pub item_width: f32,
+    pub scrollbar_width: f32,
+    pub focus_thickness: f32,
+}
+
+impl UiStyle {
+    #[inline]
+    pub fn with_font<F: Into<FontFace>>(mut self, font: F) -> Self {
+        self.font = Some(font.into());
+        self
+    }
+}
+
+impl Default for UiStyle {
+    fn default() -> Self {
+        Self {
+            font: None,
+            text: Color::rgb(0.92, 0.92, 0.94),
+            text_weak: Color::rgb(0.55, 0.55, 0.6),
+            background: Color::rgba(0.08, 0.08, 0.1, 0.92),
+            title: Color::rgb(0.16, 0.16, 0.2),
+            widget: Color::rgb(0.2, 0.2, 0.25),
+            hovered: Color::rgb(0.28, 0.28, 0.35),
+            active: Color::rgb(0.35, 0.35, 0.45),
+            accent: Color::rgb(0.3, 0.55, 0.95),
+            focus: Color::rgb(0.95, 0.75, 0.3),
+            padding: Vector2::new(8.0, 4.0),
+            spacing: 4.0,
+            item_width: 160.0,
+            scrollbar_width: 6.0,
+            focus_thickness: 1.0,
+        }
+    }
+}
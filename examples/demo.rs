@@ -2,8 +2,9 @@ use karna::{
     AppBuilder, Context, Draw, RenderContext, Scene, WindowBuilder,
     assets::{Font, Image},
     input::KeyCode,
+    label,
     math::Vector2,
-    render::Color,
+    render::{Color, Crt, Light2d, Occluder, Vignette},
     utils::Handle,
 };
 use renderer::{Geometry, Material, Mesh, Projection, TextureKind, Transform3d};
@@ -11,6 +12,7 @@ use renderer::{Geometry, Material, Mesh, Projection, TextureKind, Transform3d};
 #[derive(Default)]
 struct Demo {
     cat: Handle<Mesh>,
+    light: Handle<Light2d>,
 }
 
 impl Scene for Demo {
@@ -28,9 +30,39 @@ impl Scene for Demo {
         );
 
         ctx.scene.add_mesh(mesh);
+
+        let post = ctx.scene.post_process_mut();
+
+        // Only custom effects can fail to compile
+        post.add(label!("vignette"), Vignette::default()).unwrap();
+        post.add(label!("crt"), Crt::default()).unwrap();
+
+        // The light follows the mouse, the cat's box casts shadows
+        self.light = ctx.scene.add_light_2d(
+            Light2d::point([200.0, 200.0], 400.0).with_color(Color::rgb(1.0, 0.9, 0.7)),
+        );
+
+        let size = meta.size.to_f32();
+        let corner = Vector2::new(400.0 - size.width / 2.0, 300.0 - size.height / 2.0);
+
+        ctx.scene
+            .add_occluder(Occluder::rect(corner, size.width, size.height));
     }
 
-    fn update(&mut self, ctx: &mut Context) {}
+    fn update(&mut self, ctx: &mut Context) {
+        if ctx.input.key_pressed(&KeyCode::KeyC) {
+            let post = ctx.scene.post_process_mut();
+            let enabled = post.is_enabled(label!("crt"));
+
+            post.set_enabled(label!("crt"), !enabled);
+        }
+
+        let mouse = *ctx.input.mouse_position();
+
+        if let Some(light) = ctx.scene.get_light_2d_mut(self.light) {
+            light.position = mouse;
+        }
+    }
 
     fn render(&mut self, ctx: &RenderContext, draw: &mut Draw) {}
 }
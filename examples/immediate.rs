@@ -1,13 +1,21 @@
-use karna::{AppBuilder, Context, Draw, RenderContext, Scene, WindowBuilder, render::Color};
+use karna::{
+    AppBuilder, Context, Draw, RenderContext, Scene, WindowBuilder,
+    render::{
+        Color, Gradient, ImageOptions, Layer, LineCap, LineJoin, RenderTarget, RenderTargetConfig,
+        Stroke, Vertex,
+    },
+    utils::Handle,
+};
 use math::rng;
 
 #[derive(Default)]
 struct ImmediateDemo {
     lines: Vec<(f32, f32, f32, f32)>,
+    target: Option<Handle<RenderTarget>>,
 }
 
 impl Scene for ImmediateDemo {
-    fn load(&mut self, _ctx: &mut Context) {
+    fn load(&mut self, ctx: &mut Context) {
         let lines: Vec<(f32, f32, f32, f32)> = (0..10)
             .map(|_| {
                 (
@@ -20,11 +28,14 @@ impl Scene for ImmediateDemo {
             .collect();
 
         self.lines = lines;
+        self.target = Some(ctx.scene.create_target(
+            RenderTargetConfig::new(160, 120).with_clear_color(Color::rgba(0.2, 0.2, 0.3, 1.0)),
+        ));
     }
 
     fn update(&mut self, _ctx: &mut Context) {}
 
-    fn render(&mut self, _ctx: &RenderContext, draw: &mut Draw) {
+    fn render(&mut self, ctx: &RenderContext, draw: &mut Draw) {
         draw.set_color(Color::Cyan);
 
         for i in 0..10 {
@@ -45,8 +56,107 @@ impl Scene for ImmediateDemo {
         draw.set_color(Color::Magenta);
         draw.circle(300.0, 100.0, 50.0);
 
+        draw.set_stroke(
+            Stroke::new(6.0)
+                .with_join(LineJoin::Round)
+                .with_cap(LineCap::Round),
+        );
+        draw.set_color(Color::Green);
+        draw.polyline([
+            (200.0, 450.0),
+            (260.0, 380.0),
+            (320.0, 450.0),
+            (380.0, 380.0),
+        ]);
+
+        draw.set_stroke(Stroke::new(3.0).with_dash(12.0, 6.0));
+        draw.stroke_circle(300.0, 100.0, 60.0);
+        draw.stroke_rounded_rect(420.0, 300.0, 120.0, 80.0, 16.0);
+
+        draw.set_stroke(Stroke::default());
+        draw.set_color(Color::Yellow);
+        draw.polygon([
+            (450.0, 450.0),
+            (520.0, 420.0),
+            (490.0, 470.0),
+            (520.0, 520.0),
+        ]);
+        draw.stroke_polygon([
+            (450.0, 450.0),
+            (520.0, 420.0),
+            (490.0, 470.0),
+            (520.0, 520.0),
+        ]);
+
         draw.set_color(Color::White);
         draw.debug_text("Text!!", 10.0, 10.0);
+
+        draw.rect_gradient(
+            20.0,
+            480.0,
+            160.0,
+            40.0,
+            &Gradient::linear([20.0, 0.0], [180.0, 0.0], Color::Red, Color::Blue)
+                .with_stop(0.5, Color::Yellow),
+        );
+        draw.circle_gradient(
+            120.0,
+            560.0,
+            30.0,
+            &Gradient::radial([120.0, 560.0], 30.0, Color::White, Color::Purple),
+        );
+
+        let vertex = |x: f32, y: f32, color: Color| {
+            Vertex::new([x, y, 0.0].into(), color.into(), [0.0, 0.0].into())
+        };
+
+        draw.triangles(
+            &[
+                vertex(220.0, 580.0, Color::Red),
+                vertex(260.0, 500.0, Color::Green),
+                vertex(300.0, 580.0, Color::Blue),
+            ],
+            &[0, 1, 2],
+        );
+
+        // A group spinning around its center, cut by a clip rect
+        let angle = ctx.time.elapsed().as_secs_f32();
+
+        draw.push_clip(600.0, 300.0, 160.0, 120.0);
+        draw.push_transform(680.0, 360.0, angle, 1.0, 1.0);
+        draw.set_color(Color::Blue);
+        draw.rect(-60.0, -60.0, 120.0, 120.0);
+        draw.set_color(Color::Orange);
+        draw.circle(60.0, 0.0, 20.0);
+        draw.set_color(Color::White);
+        draw.debug_text("Spin", -20.0, -8.0);
+        draw.pop_transform();
+        draw.pop_clip();
+
+        let Some(target) = self.target else {
+            return;
+        };
+
+        // Drawn offscreen, then shown twice like an image
+        draw.set_layer(Layer::Target(target));
+        draw.set_color(Color::Yellow);
+        draw.circle(80.0, 60.0, 40.0);
+        draw.set_color(Color::White);
+        draw.debug_text("Target", 50.0, 52.0);
+        draw.set_layer(Layer::World);
+
+        draw.image(target, 600.0, 20.0);
+        draw.image_ex(
+            target,
+            680.0,
+            220.0,
+            ImageOptions::new()
+                .with_size(120.0, 90.0)
+                .centered()
+                .with_rotation(angle)
+                .with_tint(Color::Cyan)
+                .with_flip_x(true),
+        );
     }
 }
 
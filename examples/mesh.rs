@@ -2,7 +2,10 @@ use karna::{
     AppBuilder, Context, Draw, RenderContext, Scene, WindowBuilder,
     input::KeyCode,
     math::{Vector2, Vector3},
-    render::{Color, Geometry, Layer, Material, Mesh, TextureKind, Transform3d},
+    render::{
+        Color, DirectionalLight, Geometry, Layer, Material, Mesh, PointLight, Shading, TextureKind,
+        Transform3d,
+    },
     utils::Handle,
 };
 use renderer::{Projection, Text};
@@ -19,15 +22,23 @@ struct Demo {
 impl Scene for Demo {
     fn load(&mut self, ctx: &mut Context) {
         ctx.time.set_target_fps(175);
+        ctx.scene.set_depth_buffer(true);
 
         let mesh = Mesh::new(
             Geometry::cube(1.0),
-            Material::new_color(Color::Cyan),
+            Material::new_color(Color::Cyan).with_shading(Shading::blinn_phong()),
             Transform3d::default().with_position([0.0, 0.0, 0.0]),
         );
 
         self.mesh = ctx.scene.add_mesh(mesh);
 
+        ctx.scene.set_ambient_light(Color::rgb(0.15, 0.15, 0.2));
+        ctx.scene
+            .add_directional_light(DirectionalLight::new([-0.5, -1.0, -0.3]).with_intensity(0.8));
+        ctx.scene.add_point_light(
+            PointLight::new([2.0, 1.0, 2.0], 6.0).with_color(Color::rgb(1.0, 0.6, 0.3)),
+        );
+
         let mesh = Mesh::new(
             Geometry::circle(25.0, 32),
             Material::new_color(Color::Magenta),
@@ -38,6 +49,26 @@ impl Scene for Demo {
 
         ctx.scene.add_mesh(mesh);
 
+        let torus = Mesh::new(
+            Geometry::torus(0.6, 0.2, 48, 16),
+            Material::new_color(Color::Magenta).with_shading(Shading::blinn_phong()),
+            Transform3d::default().with_position([2.0, 0.0, 0.0]),
+        );
+
+        ctx.scene.add_mesh(torus);
+
+        let model = ctx.assets.load_model("examples/assets/pyramid.obj");
+
+        for primitive in &ctx.assets.get_model(model).primitives {
+            let mesh = Mesh::new(
+                Geometry::from_primitive(primitive),
+                Material::from_primitive(primitive).with_shading(Shading::Lambert),
+                Transform3d::from_matrix(primitive.transform).with_position([-2.0, -0.5, 0.0]),
+            );
+
+            ctx.scene.add_mesh(mesh);
+        }
+
         let cat = ctx
             .assets
             .load_image_bytes(include_bytes!("assets/cat.png").to_vec());
@@ -72,6 +103,15 @@ impl Scene for Demo {
         text.set_rotation([0.0, 0.0, 180.0f32.to_radians()]);
 
         self.text = ctx.scene.add_text(text);
+
+        // Open the console with the key under Escape, or type in the terminal
+        ctx.console.listen_stdin();
+        ctx.console.register("fov", |args, ctx| {
+            let fov = args.get::<f32>(0)?;
+
+            ctx.scene.camera_mut().set_fov(fov);
+            Ok(())
+        });
     }
 
     fn update(&mut self, ctx: &mut Context) {
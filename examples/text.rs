@@ -2,8 +2,8 @@ use karna::{
     AppBuilder, Context, Draw, RenderContext, Scene, WindowBuilder,
     assets::Font,
     input::KeyCode,
-    render::{Color, Text, Transform3d},
-    utils::Handle,
+    render::{Color, HorizontalAlign, Text, TextLayout, Transform3d},
+    utils::{Handle, Label},
 };
 
 #[derive(Default)]
@@ -11,6 +11,7 @@ struct TextDemo {
     font: Handle<Font>,
     text1: Handle<Text>,
     text2: Handle<Text>,
+    dialogue: Handle<Text>,
     color_timer: f32,
     logs_toggle: bool,
 }
@@ -34,6 +35,53 @@ impl Scene for TextDemo {
         text.set_position([300.0, 300.0, 0.0]);
 
         self.text2 = ctx.scene.add_text(text);
+
+        // Same font, rasterized at another size without loading it again
+        let big = ctx.assets.get_font(self.font).at_size(32);
+        let mut text = Text::new(big).with_content("Big JetBrains Mono");
+
+        text.set_position([300.0, 400.0, 0.0]);
+
+        ctx.scene.add_text(text);
+
+        // Rich text, images are referenced by name
+        let cat = ctx
+            .assets
+            .load_image_bytes(include_bytes!("assets/cat.png").to_vec());
+
+        ctx.assets.name_image(Label::new("cat"), cat);
+
+        let mut text = Text::new(self.font)
+            .with_markup(true)
+            .with_content("[color=red]Red[/color], [color=#44ff88]green[/color] and [size=24]bigger[/size] text, with a cat [img=cat]");
+
+        text.set_position([100.0, 500.0, 0.0]);
+
+        ctx.scene.add_text(text);
+
+        // Wrapped and centered in a 200px wide box
+        let mut text = Text::new(self.font)
+            .with_content("This longer text is wrapped to fit in a box, and each line is centered")
+            .with_layout(
+                TextLayout::default()
+                    .with_max_width(200.0)
+                    .with_horizontal_align(HorizontalAlign::Center)
+                    .with_line_spacing(1.2),
+            );
+
+        text.set_position([550.0, 100.0, 0.0]);
+
+        ctx.scene.add_text(text);
+
+        // Dialogue, revealed glyph by glyph
+        let mut text = Text::new(self.font)
+            .with_markup(true)
+            .with_typewriter(20.0)
+            .with_content("Press [color=yellow]Space[/color] to skip. [wave]Waving[/wave], [shake]shaking[/shake] and [rainbow]rainbow[/rainbow] text!");
+
+        text.set_position([100.0, 600.0, 0.0]);
+
+        self.dialogue = ctx.scene.add_text(text);
     }
 
     fn update(&mut self, ctx: &mut Context) {
@@ -43,6 +91,15 @@ impl Scene for TextDemo {
             *font.rotation_z_mut() += 1.0 * ctx.time.delta();
         }
 
+        if ctx.input.key_pressed(&KeyCode::Space)
+            && let Some(text) = ctx.scene.get_text_mut(self.dialogue)
+        {
+            match text.is_revealed() {
+                true => text.restart_typewriter(),
+                false => text.skip_typewriter(),
+            }
+        }
+
         if ctx.input.key_pressed(&KeyCode::KeyL) {
             self.logs_toggle = !self.logs_toggle;
         }
@@ -64,6 +121,17 @@ impl Scene for TextDemo {
 
         draw.debug_text("Press 'L' to toggle logs!", 10.0, 60.0);
 
+        // Centered around a point, using the measured size
+        let label = "Centered label";
+        let bounds = draw.measure(self.font, label);
+
+        draw.text(
+            self.font,
+            label,
+            400.0 - bounds.width / 2.0,
+            250.0 - bounds.height / 2.0,
+        );
+
         if self.logs_toggle {
             draw.debug_logs(10.0, 80.0);
         }
@@ -0,0 +1,71 @@
+use karna::{
+    AppBuilder, Context, Draw, RenderContext, Scene, WindowBuilder, render::Color, ui::Ui,
+};
+
+#[derive(Default)]
+struct UiDemo {
+    ui: Ui,
+    clicks: u32,
+    show_square: bool,
+    size: f32,
+    name: String,
+}
+
+impl Scene for UiDemo {
+    fn load(&mut self, _ctx: &mut Context) {
+        self.size = 50.0;
+    }
+
+    fn update(&mut self, _ctx: &mut Context) {}
+
+    fn render(&mut self, ctx: &RenderContext, draw: &mut Draw) {
+        if self.show_square {
+            draw.set_color(Color::Orange);
+            draw.rect(500.0, 200.0, self.size, self.size);
+        }
+
+        let mut ui = self.ui.begin(ctx, draw);
+
+        ui.panel("Widgets", 20.0, 20.0, 260.0, |ui| {
+            ui.label(format!("Clicked {} times", self.clicks));
+
+            ui.row(|ui| {
+                if ui.button("Click me") {
+                    self.clicks += 1;
+                }
+
+                if ui.button("Reset") {
+                    self.clicks = 0;
+                }
+            });
+
+            ui.checkbox("Show square", &mut self.show_square);
+            ui.slider("Size", &mut self.size, 10.0..=200.0);
+            ui.text_field("Your name", &mut self.name);
+
+            if !self.name.is_empty() {
+                ui.label(format!("Hello, {}!", self.name));
+            }
+        });
+
+        ui.panel("Scrolling", 300.0, 20.0, 200.0, |ui| {
+            ui.scroll_area("items", 200.0, |ui| {
+                for i in 0..30 {
+                    ui.label(format!("Item {i}"));
+                }
+            });
+        });
+    }
+}
+
+fn main() {
+    AppBuilder::new()
+        .with_window(
+            WindowBuilder::new()
+                .with_label("main")
+                .with_title("Ui demo")
+                .with_initial_scene(UiDemo::default()),
+        )
+        .build()
+        .run();
+}
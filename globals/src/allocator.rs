@@ -1,5 +1,5 @@
 use std::{
-    alloc::{GlobalAlloc, System},
+    alloc::{GlobalAlloc, Layout},
     sync::atomic::{AtomicUsize, Ordering},
 };
 
@@ -11,8 +11,12 @@ static PEAK: AtomicUsize = AtomicUsize::new(0);
 pub struct TrackingAllocator;
 
 unsafe impl GlobalAlloc for TrackingAllocator {
-    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
-        let ret = unsafe { System.alloc(layout) };
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        #[cfg(feature = "track-allocations")]
+        let ret = unsafe { categories::alloc(layout) };
+
+        #[cfg(not(feature = "track-allocations"))]
+        let ret = unsafe { std::alloc::System.alloc(layout) };
 
         if !ret.is_null() {
             let current = CURRENT.fetch_add(layout.size(), Ordering::Relaxed);
@@ -24,9 +28,15 @@ unsafe impl GlobalAlloc for TrackingAllocator {
         ret
     }
 
-    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        #[cfg(feature = "track-allocations")]
         unsafe {
-            System.dealloc(ptr, layout);
+            categories::dealloc(ptr, layout);
+        }
+
+        #[cfg(not(feature = "track-allocations"))]
+        unsafe {
+            std::alloc::System.dealloc(ptr, layout);
         }
 
         if !ptr.is_null() {
@@ -56,3 +66,190 @@ pub fn current_bytes() -> usize {
 pub fn peak_bytes() -> usize {
     PEAK.load(Ordering::Relaxed)
 }
+
+/// Subsystem the allocations of a thread are attributed to,
+/// set with [`alloc_scope`]
+#[repr(u8)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AllocCategory {
+    /// Anything outside of a scope
+    #[default]
+    Other,
+    Game,
+    Renderer,
+    Assets,
+}
+
+impl AllocCategory {
+    pub const COUNT: usize = 4;
+    pub const ALL: [Self; Self::COUNT] = [Self::Other, Self::Game, Self::Renderer, Self::Assets];
+
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Other => "other",
+            Self::Game => "game",
+            Self::Renderer => "renderer",
+            Self::Assets => "assets",
+        }
+    }
+}
+
+/// Totals of a category since the start of the process
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CategoryTotals {
+    pub current: usize,
+    pub peak: usize,
+    pub allocations: usize,
+    pub allocated: usize,
+}
+
+/// Restores the previous category when dropped
+pub struct AllocScope {
+    #[cfg(feature = "track-allocations")]
+    previous: AllocCategory,
+}
+
+impl Drop for AllocScope {
+    #[inline]
+    fn drop(&mut self) {
+        #[cfg(feature = "track-allocations")]
+        categories::set_category(self.previous);
+    }
+}
+
+/// Attributes the allocations of the current thread to `category`,
+/// until the returned scope is dropped.
+///
+/// Does nothing unless the `track-allocations` feature is enabled
+#[inline]
+#[must_use]
+pub fn alloc_scope(category: AllocCategory) -> AllocScope {
+    #[cfg(feature = "track-allocations")]
+    {
+        AllocScope {
+            previous: categories::set_category(category),
+        }
+    }
+
+    #[cfg(not(feature = "track-allocations"))]
+    {
+        let _ = category;
+        AllocScope {}
+    }
+}
+
+/// Always zeroed unless the `track-allocations` feature is enabled
+#[inline]
+pub fn category_totals(category: AllocCategory) -> CategoryTotals {
+    #[cfg(feature = "track-allocations")]
+    {
+        categories::totals(category)
+    }
+
+    #[cfg(not(feature = "track-allocations"))]
+    {
+        let _ = category;
+        CategoryTotals::default()
+    }
+}
+
+/// Every allocation is prefixed by a header holding its category,
+/// so that it is freed from the right one, whichever thread frees it
+#[cfg(feature = "track-allocations")]
+mod categories {
+    use super::{AllocCategory, CategoryTotals};
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    type Counters = [AtomicUsize; AllocCategory::COUNT];
+
+    static CURRENT: Counters = [const { AtomicUsize::new(0) }; AllocCategory::COUNT];
+    static PEAK: Counters = [const { AtomicUsize::new(0) }; AllocCategory::COUNT];
+    static ALLOCATIONS: Counters = [const { AtomicUsize::new(0) }; AllocCategory::COUNT];
+    static ALLOCATED: Counters = [const { AtomicUsize::new(0) }; AllocCategory::COUNT];
+
+    thread_local! {
+        static CATEGORY: Cell<AllocCategory> = const { Cell::new(AllocCategory::Other) };
+    }
+
+    pub(super) fn set_category(category: AllocCategory) -> AllocCategory {
+        CATEGORY
+            .try_with(|current| current.replace(category))
+            .unwrap_or_default()
+    }
+
+    pub(super) fn totals(category: AllocCategory) -> CategoryTotals {
+        let i = category as usize;
+
+        CategoryTotals {
+            current: CURRENT[i].load(Ordering::Relaxed),
+            peak: PEAK[i].load(Ordering::Relaxed),
+            allocations: ALLOCATIONS[i].load(Ordering::Relaxed),
+            allocated: ALLOCATED[i].load(Ordering::Relaxed),
+        }
+    }
+
+    /// Size of the header, a multiple of the alignment so that it is kept
+    #[inline]
+    fn header(layout: Layout) -> usize {
+        layout.align().max(size_of::<usize>())
+    }
+
+    #[inline]
+    fn padded(layout: Layout) -> Option<Layout> {
+        let size = layout.size().checked_add(header(layout))?;
+
+        Layout::from_size_align(size, layout.align()).ok()
+    }
+
+    pub(super) unsafe fn alloc(layout: Layout) -> *mut u8 {
+        let Some(padded) = padded(layout) else {
+            return std::ptr::null_mut();
+        };
+
+        let base = unsafe { System.alloc(padded) };
+
+        if base.is_null() {
+            return base;
+        }
+
+        // Dropping a thread local can allocate, in that case it counts as other
+        let category = CATEGORY.try_with(Cell::get).unwrap_or_default();
+        let i = category as usize;
+
+        unsafe {
+            let ptr = base.add(header(layout));
+
+            ptr.sub(1).write(category as u8);
+
+            let current = CURRENT[i].fetch_add(layout.size(), Ordering::Relaxed);
+
+            PEAK[i].fetch_max(current + layout.size(), Ordering::Relaxed);
+            ALLOCATIONS[i].fetch_add(1, Ordering::Relaxed);
+            ALLOCATED[i].fetch_add(layout.size(), Ordering::Relaxed);
+
+            ptr
+        }
+    }
+
+    pub(super) unsafe fn dealloc(ptr: *mut u8, layout: Layout) {
+        if ptr.is_null() {
+            return;
+        }
+
+        unsafe {
+            let i = ptr.sub(1).read() as usize;
+
+            CURRENT[i].fetch_sub(layout.size(), Ordering::Relaxed);
+
+            // Can't fail, the same layout was padded in `alloc`
+            let padded = padded(layout).unwrap_unchecked();
+
+            System.dealloc(ptr.sub(header(layout)), padded);
+        }
+    }
+}
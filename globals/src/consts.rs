@@ -19,3 +19,9 @@ pub const TEXT_INSTANCE_BASE_CAPACITY: usize = 1024;
 
 /// Base size of the texture atlas, can be resized
 pub const TEXTURE_ATLAS_BASE_SIZE: (u32, u32) = (1024, 1024);
+
+/// Directional lights sent to the lit shaders per layer, the others are ignored
+pub const MAX_DIRECTIONAL_LIGHTS: usize = 4;
+
+/// Point lights sent to the lit shaders per layer, the others are ignored
+pub const MAX_POINT_LIGHTS: usize = 32;
@@ -4,4 +4,4 @@ pub mod consts;
 pub mod logs;
 pub mod profiling;
 
-pub use allocator::TrackingAllocator;
+pub use allocator::{AllocCategory, AllocScope, TrackingAllocator, alloc_scope};
@@ -0,0 +1,114 @@
+use std::collections::VecDeque;
+
+/// Frames kept by default in each [`History`]
+pub const HISTORY_FRAMES: usize = 240;
+
+/// Rolling buffer of the last values of a metric, one per frame
+#[derive(Debug, Clone)]
+pub struct History {
+    values: VecDeque<f32>,
+    capacity: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(HISTORY_FRAMES)
+    }
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            values: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    #[inline]
+    pub fn push(&mut self, value: f32) {
+        if self.values.len() == self.capacity {
+            self.values.pop_front();
+        }
+
+        self.values.push_back(value);
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// From the oldest to the newest value
+    #[inline]
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = f32> + ExactSizeIterator + Clone + '_ {
+        self.values.iter().copied()
+    }
+
+    #[inline]
+    pub fn latest(&self) -> Option<f32> {
+        self.values.back().copied()
+    }
+
+    pub fn min(&self) -> Option<f32> {
+        self.iter().reduce(f32::min)
+    }
+
+    pub fn max(&self) -> Option<f32> {
+        self.iter().reduce(f32::max)
+    }
+
+    pub fn average(&self) -> Option<f32> {
+        match self.values.is_empty() {
+            true => None,
+            false => Some(self.iter().sum::<f32>() / self.len() as f32),
+        }
+    }
+
+    /// Value under which `percentile`% of the values are,
+    /// `percentile(99.0)` of the frame times gives the 1% lows
+    pub fn percentile(&self, percentile: f32) -> Option<f32> {
+        if self.values.is_empty() {
+            return None;
+        }
+
+        let mut sorted = self.iter().collect::<Vec<_>>();
+        sorted.sort_by(f32::total_cmp);
+
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0) * (sorted.len() - 1) as f32;
+
+        Some(sorted[rank.round() as usize])
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+}
+
+/// Histories of the main metrics of [`super::Statistics`]
+#[derive(Debug, Clone, Default)]
+pub struct StatsHistory {
+    /// In milliseconds, unaffected by the time scale
+    pub frame_time: History,
+    pub fps: History,
+    pub tps: History,
+    pub draw_calls: History,
+    pub triangles: History,
+    pub instance_writes: History,
+    /// Cpu time of `Renderer::present`, in milliseconds
+    pub present_time: History,
+    /// In milliseconds, stays empty without gpu timestamps
+    pub gpu_time: History,
+    /// Memory in use, in bytes
+    pub memory: History,
+}
@@ -1,6 +1,9 @@
 use macros::Get;
 
-use crate::allocator::{allocated_bytes, current_bytes, deallocated_bytes, peak_bytes};
+use crate::allocator::{
+    AllocCategory, CategoryTotals, allocated_bytes, category_totals, current_bytes,
+    deallocated_bytes, peak_bytes,
+};
 
 #[derive(Debug, Clone, Copy)]
 #[derive(Default)]
@@ -17,9 +20,39 @@ pub struct MemoryStats {
 
     #[get(copied)]
     pub(crate) peak: usize,
+
+    categories: [CategoryStats; AllocCategory::COUNT],
+    /// Totals at the previous update, to know what happened since
+    totals: [CategoryTotals; AllocCategory::COUNT],
+}
+
+/// Memory of a single [`AllocCategory`], only tracked
+/// with the `track-allocations` feature
+#[derive(Debug, Clone, Copy)]
+#[derive(Default)]
+#[derive(Get)]
+pub struct CategoryStats {
+    #[get(copied)]
+    pub(crate) current: usize,
+
+    #[get(copied)]
+    pub(crate) peak: usize,
+
+    /// Allocations made since the previous update, usually the previous frame
+    #[get(copied)]
+    pub(crate) frame_allocations: usize,
+
+    /// Bytes allocated since the previous update
+    #[get(copied)]
+    pub(crate) frame_allocated: usize,
 }
 
 impl MemoryStats {
+    #[inline]
+    pub fn category(&self, category: AllocCategory) -> CategoryStats {
+        self.categories[category as usize]
+    }
+
     #[inline]
     #[doc(hidden)]
     pub fn update(&mut self) {
@@ -27,5 +60,19 @@ impl MemoryStats {
         self.deallocated = deallocated_bytes();
         self.current = current_bytes();
         self.peak = peak_bytes();
+
+        for category in AllocCategory::ALL {
+            let i = category as usize;
+            let totals = category_totals(category);
+            let previous = self.totals[i];
+
+            self.categories[i] = CategoryStats {
+                current: totals.current,
+                peak: totals.peak,
+                frame_allocations: totals.allocations - previous.allocations,
+                frame_allocated: totals.allocated - previous.allocated,
+            };
+            self.totals[i] = totals;
+        }
     }
 }
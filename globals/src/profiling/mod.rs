@@ -1,35 +1,48 @@
+mod history;
 mod memory;
 mod render;
+mod scope;
 mod time;
 
-use crate::profiling::{memory::MemoryStats, time::TimeStats};
-use std::cell::RefCell;
-
+pub use history::{HISTORY_FRAMES, History, StatsHistory};
+pub use memory::{CategoryStats, MemoryStats};
 pub use render::*;
+pub use scope::{ScopeGuard, ScopeRecord, is_tracing, last_frame_scopes, start_trace, stop_trace};
+pub use time::TimeStats;
 
-thread_local! {
-    static STATS: RefCell<Statistics> = RefCell::new(Statistics::default());
-}
-
-#[inline]
-pub fn get_stats() -> Statistics {
-    STATS.with(|stats| *stats.borrow())
-}
-
-// === Time Stats ===
-
-pub fn update_time(delta: f32, fps: f32, tps: u32) {
-    STATS.with(|stats| {
-        let mut stats = stats.borrow_mut();
-
-        stats.time.update(delta, fps, tps);
-    });
-}
-
-#[derive(Default)]
-#[derive(Debug, Clone, Copy)]
+/// Statistics of a single window, refreshed at the end of each of its frames
+#[derive(Debug, Clone, Default)]
 pub struct Statistics {
     pub render: RenderStats,
     pub time: TimeStats,
     pub mem: MemoryStats,
+    pub history: StatsHistory,
+}
+
+impl Statistics {
+    /// Collects the render counters recorded on this thread during the frame,
+    /// refreshes the memory usage and pushes everything to the histories
+    #[doc(hidden)]
+    pub fn end_frame(&mut self, time: TimeStats) {
+        self.time = time;
+        self.render = render::current();
+        self.mem.update();
+
+        let history = &mut self.history;
+
+        history.frame_time.push(time.frame_time);
+        history.fps.push(time.fps);
+        history.tps.push(time.tps as f32);
+        history.draw_calls.push(self.render.draw_calls as f32);
+        history.triangles.push(self.render.triangles as f32);
+        history
+            .instance_writes
+            .push(self.render.instance_writes as f32);
+        history.present_time.push(self.render.present_time);
+        history.memory.push(self.mem.current as f32);
+
+        if self.render.gpu.supported {
+            history.gpu_time.push(self.render.gpu.frame);
+        }
+    }
 }
@@ -1,6 +1,11 @@
 use macros::Get;
+use std::cell::RefCell;
 
-use crate::profiling::STATS;
+thread_local! {
+    /// Counters of the frame being rendered on this thread, the renderer of a window
+    /// only runs on its own thread, so they are never mixed with another window's
+    static RENDER: RefCell<RenderStats> = RefCell::new(RenderStats::default());
+}
 
 #[derive(Debug, Clone, Copy)]
 #[derive(Default)]
@@ -35,6 +40,38 @@ pub struct RenderStats {
 
     #[get(copied)]
     pub(crate) pipeline_switches: u32,
+
+    /// Time spent on the cpu recording and submitting the frame, in milliseconds
+    #[get(copied)]
+    pub(crate) present_time: f32,
+
+    /// Resolved a few frames late, since the gpu runs behind the cpu
+    #[get(copied)]
+    pub(crate) gpu: GpuTimings,
+}
+
+/// Time the gpu spent on each part of a frame, in milliseconds.
+///
+/// Layers and sub-renderers are only measured when the adapter
+/// supports `TIMESTAMP_QUERY_INSIDE_PASSES`, and nothing is
+/// when it doesn't support `TIMESTAMP_QUERY`
+#[derive(Debug, Clone, Copy)]
+#[derive(Default)]
+pub struct GpuTimings {
+    pub supported: bool,
+    pub frame: f32,
+    pub world: f32,
+    pub ui: f32,
+    /// All the user layers together
+    pub custom: f32,
+    /// All the layers drawn into render targets together
+    pub target: f32,
+    /// Summed over every layer
+    pub immediate: f32,
+    pub retained: f32,
+    pub text: f32,
+    /// All the post-processing effects together
+    pub post: f32,
 }
 
 impl RenderStats {
@@ -45,6 +82,7 @@ impl RenderStats {
         *self = Self {
             geometry_buffers: self.geometry_buffers,
             geometry_buffers_size: self.geometry_buffers_size,
+            gpu: self.gpu,
             ..Self::default()
         }
     }
@@ -52,65 +90,91 @@ impl RenderStats {
 
 #[inline]
 pub fn record_draw_call(vertices_n: u32, indices_n: u32) {
-    STATS.with(|stats| {
+    RENDER.with(|stats| {
         let mut stats = stats.borrow_mut();
 
-        stats.render.draw_calls += 1;
-        stats.render.indices += indices_n;
-        stats.render.vertices += vertices_n;
+        stats.draw_calls += 1;
+        stats.indices += indices_n;
+        stats.vertices += vertices_n;
     });
 }
 
 #[inline]
 pub fn record_instance_writes(count: u32) {
-    STATS.with(|stats| {
+    RENDER.with(|stats| {
         let mut stats = stats.borrow_mut();
 
-        stats.render.instance_writes += count;
+        stats.instance_writes += count;
     });
 }
 
 #[inline]
 pub fn record_geometry_buffer(count: u32) {
-    STATS.with(|stats| {
+    RENDER.with(|stats| {
         let mut stats = stats.borrow_mut();
 
-        stats.render.geometry_buffers += count;
+        stats.geometry_buffers += count;
     });
 }
 
 #[inline]
 pub fn record_geometry_buffers_size(size: u32) {
-    STATS.with(|stats| {
+    RENDER.with(|stats| {
         let mut stats = stats.borrow_mut();
 
-        stats.render.geometry_buffers_size += size;
+        stats.geometry_buffers_size += size;
     });
 }
 
 #[inline]
 pub fn record_triangles(index_n: u32) {
-    STATS.with(|stats| {
+    RENDER.with(|stats| {
         let mut stats = stats.borrow_mut();
 
-        stats.render.triangles += index_n / 3;
+        stats.triangles += index_n / 3;
     });
 }
 
 #[inline]
 pub fn record_pipeline_switches(count: u32) {
-    STATS.with(|stats| {
+    RENDER.with(|stats| {
         let mut stats = stats.borrow_mut();
 
-        stats.render.pipeline_switches += count;
+        stats.pipeline_switches += count;
     });
 }
 
+#[inline]
+pub fn record_present_time(ms: f32) {
+    RENDER.with(|stats| {
+        let mut stats = stats.borrow_mut();
+
+        stats.present_time = ms;
+    });
+}
+
+#[inline]
+pub fn record_gpu_timings(timings: GpuTimings) {
+    RENDER.with(|stats| {
+        let mut stats = stats.borrow_mut();
+
+        stats.gpu = timings;
+    });
+}
+
+/// Counters recorded so far on this thread
+#[inline]
+pub(crate) fn current() -> RenderStats {
+    RENDER.with(|stats| *stats.borrow())
+}
+
 #[inline]
 pub fn reset_frame() {
-    STATS.with(|stats| {
+    RENDER.with(|stats| {
         let mut stats = stats.borrow_mut();
 
-        stats.render.reset_frame();
+        stats.reset_frame();
     });
+
+    super::scope::end_frame();
 }
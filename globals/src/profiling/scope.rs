@@ -0,0 +1,256 @@
+use macros::Get;
+use parking_lot::Mutex;
+use std::{
+    cell::RefCell,
+    fmt::Write as _,
+    fs, io, mem,
+    path::Path,
+    sync::{
+        LazyLock,
+        atomic::{AtomicBool, AtomicU32, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Every timestamp is relative to this, so that scopes
+/// of different threads can be put on the same timeline
+static EPOCH: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+static NEXT_THREAD_ID: AtomicU32 = AtomicU32::new(1);
+
+static TRACING: AtomicBool = AtomicBool::new(false);
+static TRACE: Mutex<Vec<TraceEvent>> = Mutex::new(Vec::new());
+static THREAD_NAMES: Mutex<Vec<(u32, String)>> = Mutex::new(Vec::new());
+
+thread_local! {
+    static RECORDER: RefCell<ScopeRecorder> = RefCell::new(ScopeRecorder::new());
+}
+
+/// Times the rest of the enclosing block, nested scopes
+/// are recorded as children of the enclosing ones
+///
+/// ```ignore
+/// fn update(&mut self, ctx: &mut Context) {
+///     profile_scope!("physics");
+///     // ...
+/// }
+/// ```
+#[macro_export]
+macro_rules! profile_scope {
+    ($name:expr) => {
+        let _profile_scope = $crate::profiling::ScopeGuard::new($name);
+    };
+}
+
+/// A scope measured during the last complete frame of the current thread
+#[derive(Debug, Clone, Copy)]
+#[derive(Get)]
+pub struct ScopeRecord {
+    #[get(copied)]
+    pub(crate) name: &'static str,
+
+    /// How many scopes enclose this one
+    #[get(copied)]
+    pub(crate) depth: u32,
+
+    /// Since the start of the process
+    #[get(copied)]
+    pub(crate) start: Duration,
+
+    #[get(copied)]
+    pub(crate) duration: Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TraceEvent {
+    name: &'static str,
+    thread: u32,
+    start: Duration,
+    duration: Duration,
+}
+
+struct ScopeRecorder {
+    thread: u32,
+    thread_name: String,
+    depth: u32,
+    /// Scopes of the frame being recorded, in the order they end
+    current: Vec<ScopeRecord>,
+    last: Vec<ScopeRecord>,
+}
+
+impl ScopeRecorder {
+    fn new() -> Self {
+        let thread = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+        let thread_name = thread::current()
+            .name()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("thread {thread}"));
+
+        Self {
+            thread,
+            thread_name,
+            depth: 0,
+            current: Vec::new(),
+            last: Vec::new(),
+        }
+    }
+}
+
+/// Created by [`profile_scope!`], records the scope when dropped
+pub struct ScopeGuard {
+    name: &'static str,
+    start: Instant,
+}
+
+impl ScopeGuard {
+    #[inline]
+    #[doc(hidden)]
+    pub fn new(name: &'static str) -> Self {
+        LazyLock::force(&EPOCH);
+        RECORDER.with(|recorder| recorder.borrow_mut().depth += 1);
+
+        Self {
+            name,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        let duration = self.start.elapsed();
+        let start = self.start.saturating_duration_since(*EPOCH);
+
+        RECORDER.with(|recorder| {
+            let mut recorder = recorder.borrow_mut();
+
+            recorder.depth -= 1;
+
+            let record = ScopeRecord {
+                name: self.name,
+                depth: recorder.depth,
+                start,
+                duration,
+            };
+
+            recorder.current.push(record);
+
+            if TRACING.load(Ordering::Relaxed) {
+                if recorder.depth == 0 {
+                    register_thread(&recorder);
+                }
+
+                TRACE.lock().push(TraceEvent {
+                    name: self.name,
+                    thread: recorder.thread,
+                    start,
+                    duration,
+                });
+            }
+        });
+    }
+}
+
+/// Closes the frame of the current thread, its scopes
+/// become available through [`last_frame_scopes`]
+#[inline]
+pub(crate) fn end_frame() {
+    RECORDER.with(|recorder| {
+        let mut recorder = recorder.borrow_mut();
+        let current = mem::take(&mut recorder.current);
+
+        recorder.last = current;
+        recorder.last.sort_by_key(|record| record.start);
+    });
+}
+
+/// Scopes of the last frame of the current thread,
+/// sorted by start time, so that children follow their parent
+#[inline]
+pub fn last_frame_scopes() -> Vec<ScopeRecord> {
+    RECORDER.with(|recorder| recorder.borrow().last.clone())
+}
+
+/// Starts keeping the scopes of every thread, until [`stop_trace`]
+pub fn start_trace() {
+    TRACE.lock().clear();
+    THREAD_NAMES.lock().clear();
+    TRACING.store(true, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn is_tracing() -> bool {
+    TRACING.load(Ordering::Relaxed)
+}
+
+/// Stops tracing and writes the scopes in the Chrome trace format,
+/// which can be opened in `chrome://tracing` or Perfetto
+pub fn stop_trace<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    TRACING.store(false, Ordering::Relaxed);
+
+    let events = mem::take(&mut *TRACE.lock());
+    let threads = mem::take(&mut *THREAD_NAMES.lock());
+
+    fs::write(path, chrome_trace(&events, &threads))
+}
+
+fn register_thread(recorder: &ScopeRecorder) {
+    let mut names = THREAD_NAMES.lock();
+
+    if !names.iter().any(|(id, _)| *id == recorder.thread) {
+        names.push((recorder.thread, recorder.thread_name.clone()));
+    }
+}
+
+fn chrome_trace(events: &[TraceEvent], threads: &[(u32, String)]) -> String {
+    let mut json = String::from("{\"traceEvents\":[");
+    let mut first = true;
+
+    let mut separator = |json: &mut String| {
+        if !mem::take(&mut first) {
+            json.push(',');
+        }
+    };
+
+    for (id, name) in threads {
+        separator(&mut json);
+        let _ = write!(
+            json,
+            "{{\"ph\":\"M\",\"pid\":1,\"tid\":{id},\"name\":\"thread_name\",\"args\":{{\"name\":\"{}\"}}}}",
+            escape(name)
+        );
+    }
+
+    for event in events {
+        separator(&mut json);
+        let _ = write!(
+            json,
+            "{{\"ph\":\"X\",\"pid\":1,\"tid\":{},\"name\":\"{}\",\"ts\":{:.3},\"dur\":{:.3}}}",
+            event.thread,
+            escape(event.name),
+            event.start.as_secs_f64() * 1_000_000.0,
+            event.duration.as_secs_f64() * 1_000_000.0,
+        );
+    }
+
+    json.push_str("]}");
+    json
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for ch in text.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            ch if ch.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", ch as u32);
+            }
+            ch => escaped.push(ch),
+        }
+    }
+
+    escaped
+}
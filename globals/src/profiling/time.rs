@@ -4,12 +4,18 @@ use macros::Get;
 #[derive(Default)]
 #[derive(Get)]
 pub struct TimeStats {
+    /// Since the window was created, in seconds
     #[get(copied)]
     pub(crate) elapsed: f32,
 
+    /// Smoothed and scaled, like `Time::delta`
     #[get(copied)]
     pub(crate) delta: f32,
 
+    /// Real duration of the last frame, in milliseconds
+    #[get(copied)]
+    pub(crate) frame_time: f32,
+
     #[get(copied)]
     pub(crate) fps: f32,
 
@@ -20,10 +26,13 @@ pub struct TimeStats {
 impl TimeStats {
     #[inline]
     #[doc(hidden)]
-    pub fn update(&mut self, delta: f32, fps: f32, tps: u32) {
-        self.elapsed += delta;
-        self.delta = delta;
-        self.fps = fps;
-        self.tps = tps;
+    pub fn new(elapsed: f32, delta: f32, frame_time: f32, fps: f32, tps: u32) -> Self {
+        Self {
+            elapsed,
+            delta,
+            frame_time,
+            fps,
+            tps,
+        }
     }
 }
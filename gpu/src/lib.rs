@@ -35,6 +35,24 @@ pub fn queue() -> &'static wgpu::Queue {
     &get().queue
 }
 
+/// Runs `create`, returning the validation errors of what it creates
+/// instead of panicking in the handler of uncaught errors
+pub fn validated<T>(create: impl FnOnce() -> T) -> Result<T, String> {
+    let scope = device().push_error_scope(wgpu::ErrorFilter::Validation);
+    let value = create();
+
+    match pollster::block_on(scope.pop()) {
+        Some(error) => Err(error.to_string()),
+        None => Ok(value),
+    }
+}
+
+/// Whether the device was created with all the `features`
+#[inline]
+pub fn has_features(features: wgpu::Features) -> bool {
+    get().device.features().contains(features)
+}
+
 #[derive(Debug)]
 #[derive(Get)]
 pub struct GpuState {
@@ -67,12 +85,18 @@ impl GpuState {
             .await
             .expect("Failed to request adapter");
 
+        // Only used when available, like the gpu timings
+        let optional_features = adapter.features().intersection(
+            wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES,
+        );
+
         let (device, queue) = adapter
             .request_device(&wgpu::DeviceDescriptor {
                 required_limits: wgpu::Limits::defaults(),
                 label: Some("device"),
                 required_features: wgpu::Features::default()
-                    .union(wgpu::Features::POLYGON_MODE_LINE),
+                    .union(wgpu::Features::POLYGON_MODE_LINE)
+                    .union(optional_features),
                 ..Default::default()
             })
             .await
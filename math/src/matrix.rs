@@ -699,6 +699,33 @@ impl Matrix4 {
         ])
     }
 
+    /// Rotation of a unit quaternion stored as `(x, y, z, w)`
+    pub fn from_quaternion(q: Vector4) -> Self {
+        let (x, y, z, w) = (q[0], q[1], q[2], q[3]);
+
+        Self([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y + z * w),
+                2.0 * (x * z - y * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * y - z * w),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z + x * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * z + y * w),
+                2.0 * (y * z - x * w),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
     pub fn perspective(fov_y_radians: f32, aspect: f32, z_near: f32, z_far: f32) -> Self {
         let f = 1.0 / (fov_y_radians / 2.0).tan();
         let range = z_near - z_far;
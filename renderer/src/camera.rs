@@ -1,7 +1,7 @@
 use gpu::core::{GpuBuffer, GpuBufferBuilder};
 use logging::warn;
 use macros::{Get, Set, track_dirty};
-use math::{Matrix4, Size, Vector3};
+use math::{Matrix4, Size, Vector2, Vector3, Vector4};
 
 #[derive(Debug, Clone, Copy)]
 pub enum Projection {
@@ -68,6 +68,81 @@ impl Projection {
     }
 }
 
+/// How the projection of a layer follows the size of what it is rendered into,
+/// the sizes being the resolution the game is designed for
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ScaleMode {
+    /// One unit per pixel, a bigger window shows more of the world
+    #[default]
+    Window,
+    /// Always shows exactly the design size, distorted if the aspect ratios differ
+    Stretch(Size<f32>),
+    /// Shows the whole design size without distortion, with bars on the sides
+    Fit(Size<f32>),
+    /// Covers the window without distortion, cropping the edges of the design size
+    Fill(Size<f32>),
+    /// Shows the whole design size without distortion,
+    /// and more of the world on the sides instead of bars
+    Expand(Size<f32>),
+    /// The projection is left as it was set, whatever the window size
+    Fixed,
+}
+
+impl ScaleMode {
+    /// Design sizes of at least one unit, an empty one would divide by zero
+    fn clamped(self) -> Self {
+        let clamp = |design: Size<f32>| Size::new(design.width.max(1.0), design.height.max(1.0));
+
+        match self {
+            Self::Stretch(design) => Self::Stretch(clamp(design)),
+            Self::Fit(design) => Self::Fit(clamp(design)),
+            Self::Fill(design) => Self::Fill(clamp(design)),
+            Self::Expand(design) => Self::Expand(clamp(design)),
+            Self::Window | Self::Fixed => self,
+        }
+    }
+
+    /// Top left corner and size of the visible area, in world units
+    fn area(&self, view: Size<f32>) -> (Vector2, Size<f32>) {
+        match *self {
+            Self::Window | Self::Fixed => (Vector2::zeros(), view),
+            Self::Stretch(design) | Self::Fit(design) => (Vector2::zeros(), design),
+            Self::Fill(design) | Self::Expand(design) => {
+                let (x, y) = (view.width / design.width, view.height / design.height);
+                let scale = match self {
+                    Self::Fill(_) => x.max(y),
+                    _ => x.min(y),
+                };
+
+                let visible = Size::new(view.width / scale, view.height / scale);
+                let origin = Vector2::new(
+                    (design.width - visible.width) / 2.0,
+                    (design.height - visible.height) / 2.0,
+                );
+
+                (origin, visible)
+            }
+        }
+    }
+
+    /// Part of the window the layer is drawn in, only smaller than it when fitting
+    pub(crate) fn viewport(&self, view: Size<u32>) -> Option<(Vector2, Size<f32>)> {
+        let Self::Fit(design) = *self else {
+            return None;
+        };
+
+        let view = view.to_f32();
+        let scale = (view.width / design.width).min(view.height / design.height);
+        let size = Size::new(design.width * scale, design.height * scale);
+        let offset = Vector2::new(
+            (view.width - size.width) / 2.0,
+            (view.height - size.height) / 2.0,
+        );
+
+        Some((offset, size))
+    }
+}
+
 #[track_dirty(u16)]
 #[derive(Debug)]
 #[derive(Get, Set)]
@@ -84,6 +159,9 @@ pub struct Camera {
     // Maths
     projection: Projection,
 
+    #[get(copied)]
+    scale_mode: ScaleMode,
+
     #[get]
     #[get(copied, prop = "x", ty = f32)]
     #[get(copied, prop = "y", ty = f32)]
@@ -164,6 +242,7 @@ impl Camera {
             bg,
             bgl,
             projection,
+            scale_mode: ScaleMode::default(),
             position: Vector3::new(0.0, 0.0, -5.0),
             target: Vector3::z(),
             up: Vector3::y(),
@@ -181,6 +260,33 @@ impl Camera {
         }
     }
 
+    #[inline]
+    pub(crate) fn view_projection(&self) -> Matrix4 {
+        self.projection.matrix() * self.view_matrix()
+    }
+
+    /// How far a point is along the view direction, greater is farther
+    #[inline]
+    pub(crate) fn view_depth(&self, point: Vector3) -> f32 {
+        match self.projection {
+            // Looking down the z axis, a greater z is closer
+            Projection::Orthographic { .. } => -point.z,
+            Projection::Perspective { .. } => {
+                (point - self.position).dot(&(self.target - self.position).normalized())
+            }
+        }
+    }
+
+    /// Position of the camera, or with a w of 0 the direction towards it
+    /// when it is infinitely far away, for the specular highlights
+    #[inline]
+    pub(crate) fn eye(&self) -> Vector4 {
+        match self.projection {
+            Projection::Orthographic { .. } => Vector4::new(0.0, 0.0, 1.0, 0.0),
+            Projection::Perspective { .. } => self.position.extend(1.0),
+        }
+    }
+
     #[inline]
     pub(crate) fn queue_resize(&mut self) {
         self.tracker |= Self::projection_f()
@@ -192,22 +298,58 @@ impl Camera {
             return;
         }
 
-        match &mut self.projection {
-            Projection::Orthographic { right, bottom, .. } => {
-                *right = view.width as f32;
-                *bottom = view.height as f32;
+        let view = view.to_f32();
+
+        match (&mut self.projection, self.scale_mode) {
+            (_, ScaleMode::Fixed) => {}
+            (
+                Projection::Orthographic {
+                    left,
+                    right,
+                    bottom,
+                    top,
+                    ..
+                },
+                ScaleMode::Window,
+            ) => {
+                *left = 0.0;
+                *top = 0.0;
+                *right = view.width;
+                *bottom = view.height;
+            }
+            (
+                Projection::Orthographic {
+                    left,
+                    right,
+                    bottom,
+                    top,
+                    ..
+                },
+                mode,
+            ) => {
+                let (origin, size) = mode.area(view);
+
+                *left = origin.x;
+                *right = origin.x + size.width;
+                *top = origin.y;
+                *bottom = origin.y + size.height;
             }
-            Projection::Perspective { aspect_ratio, .. } => {
-                *aspect_ratio = view.to_f32().aspect_ratio();
+            (Projection::Perspective { aspect_ratio, .. }, mode) => {
+                *aspect_ratio = mode.area(view).1.aspect_ratio();
             }
         }
 
-        let vp = self.projection.matrix() * self.view_matrix();
-
-        self.uniform_buffer.write(0, &[vp]);
+        self.uniform_buffer.write(0, &[self.view_projection()]);
         self.clear_all_dirty();
     }
 
+    /// Design sizes smaller than one unit are made one unit large
+    #[inline]
+    pub fn set_scale_mode(&mut self, scale_mode: ScaleMode) {
+        self.scale_mode = scale_mode.clamped();
+        self.tracker |= Self::scale_mode_f();
+    }
+
     #[inline]
     pub fn set_projection(&mut self, projection: Projection) {
         self.projection = projection;
@@ -51,6 +51,68 @@ impl Color {
         Self { r, g, b, a }
     }
 
+    /// Parses a hex color in the `#rrggbb` or `#rrggbbaa` form
+    /// (the leading `#` is optional)
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+
+        if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+            return None;
+        }
+
+        let channel = |i: usize| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .ok()
+                .map(|c| c as f32 / 255.0)
+        };
+
+        let a = if hex.len() == 8 { channel(6)? } else { 1.0 };
+
+        Some(Self::rgba(channel(0)?, channel(2)?, channel(4)?, a))
+    }
+
+    /// Creates a color from a hue in turns (`0.0..1.0`, wrapping),
+    /// a saturation and a value in `0.0..=1.0`
+    pub fn hsv(h: f32, s: f32, v: f32) -> Self {
+        let h = h.rem_euclid(1.0) * 6.0;
+        let c = v * s;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let m = v - c;
+
+        let (r, g, b) = match h as u32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+
+        Self::rgb(r + m, g + m, b + m)
+    }
+
+    /// Parses either a named color (`"red"`, `"cyan"`, ...) or a hex color
+    pub fn parse(s: &str) -> Option<Self> {
+        let color = match s.to_ascii_lowercase().as_str() {
+            "red" => Self::Red,
+            "green" => Self::Green,
+            "blue" => Self::Blue,
+            "white" => Self::White,
+            "black" => Self::Black,
+            "yellow" => Self::Yellow,
+            "cyan" => Self::Cyan,
+            "magenta" => Self::Magenta,
+            "gray" | "grey" => Self::Gray,
+            "orange" => Self::Orange,
+            "purple" => Self::Purple,
+            "brown" => Self::Brown,
+            "pink" => Self::Pink,
+            _ => return Self::from_hex(s),
+        };
+
+        Some(color)
+    }
+
     #[inline]
     pub fn random() -> Self {
         let r = rng(0.0..=1.0);
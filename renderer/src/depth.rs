@@ -0,0 +1,47 @@
+use math::Size;
+
+/// Depth texture of a pass, cleared every frame and never sampled
+#[derive(Debug)]
+pub(crate) struct DepthBuffer {
+    view: wgpu::TextureView,
+}
+
+impl DepthBuffer {
+    pub(crate) const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+    pub(crate) fn new(size: Size<u32>) -> Self {
+        let size = Size::new(size.width.max(1), size.height.max(1));
+
+        let texture = gpu::device().create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth Buffer"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+        Self {
+            view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        }
+    }
+
+    /// Cleared to the far plane, nothing is kept after the pass
+    #[inline]
+    pub(crate) fn attachment(&self) -> wgpu::RenderPassDepthStencilAttachment<'_> {
+        wgpu::RenderPassDepthStencilAttachment {
+            view: &self.view,
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Clear(1.0),
+                store: wgpu::StoreOp::Discard,
+            }),
+            stencil_ops: None,
+        }
+    }
+}
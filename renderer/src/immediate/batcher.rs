@@ -1,13 +1,30 @@
 use globals::{consts, profiling};
 use gpu::core::{GpuBuffer, GpuBufferBuilder};
+use math::{Matrix4, Size, Vector2, Vector3};
+use std::ops::Range;
+use utils::Rect;
+
+/// What the indices of a batch are drawn with, besides their pipeline
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DrawState {
+    pub clip: Option<Rect>,
+    pub z_index: i32,
+}
 
 #[derive(Debug)]
 pub struct Batcher<V> {
     pub vertices: Vec<V>,
     pub indices: Vec<u32>,
+    /// Vertices before this one already went through the transform they were drawn with
+    transformed: usize,
+    /// First index each state applies to, in increasing order.
+    /// The indices before the first one have the default state
+    states: Vec<(u32, DrawState)>,
     vertex_buffer: GpuBuffer<V>,
     index_buffer: GpuBuffer<u32>,
     pipeline: wgpu::RenderPipeline,
+    /// Whether the vertices were already counted by the profiler this frame
+    counted: bool,
 }
 
 impl<V> Batcher<V> {
@@ -29,49 +46,219 @@ impl<V> Batcher<V> {
         Self {
             vertices: Vec::with_capacity(consts::IMMEDIATE_VERTEX_BASE_CAPACITY),
             indices: Vec::with_capacity(consts::IMMEDIATE_INDEX_BASE_CAPACITY),
+            transformed: 0,
+            states: Vec::new(),
             vertex_buffer,
             index_buffer,
             pipeline,
+            counted: false,
+        }
+    }
+
+    #[inline]
+    pub fn set_pipeline(&mut self, pipeline: wgpu::RenderPipeline) {
+        self.pipeline = pipeline;
+    }
+
+    /// Vertices added since the last call
+    #[inline]
+    pub fn untransformed(&mut self) -> &mut [V] {
+        let start = self.transformed;
+        self.transformed = self.vertices.len();
+
+        &mut self.vertices[start..]
+    }
+
+    /// Draws the indices added from now on with `state`
+    pub fn set_state(&mut self, state: DrawState) {
+        let start = self.indices.len() as u32;
+
+        match self.states.last_mut() {
+            Some((_, last)) if *last == state => {}
+            Some((from, last)) if *from == start => *last = state,
+            None if state == DrawState::default() => {}
+            _ => self.states.push((start, state)),
         }
     }
 
     #[inline]
-    fn clear(&mut self) {
+    pub fn clear(&mut self) {
         self.vertices.clear();
         self.indices.clear();
+        self.transformed = 0;
+        self.states.clear();
+        self.counted = false;
     }
 
-    #[inline]
-    pub fn present<'a>(&'a mut self, render_pass: &mut wgpu::RenderPass<'a>) {
+    /// Uploads the batch, before any of it is drawn this frame
+    pub fn upload(&mut self) {
         if self.vertices.is_empty() {
             return;
         }
 
-        let vertex_count = self.vertices.len() as u32;
-        let index_count = self.indices.len() as u32;
+        let vertex_count = self.vertices.len();
+        let index_count = self.indices.len();
 
-        if vertex_count > self.vertex_buffer.capacity() as u32 {
-            let new_capacity = self.vertex_buffer.capacity() * 2;
-            self.vertex_buffer.resize(new_capacity);
+        if vertex_count > self.vertex_buffer.capacity() {
+            self.vertex_buffer.resize(vertex_count.next_power_of_two());
         }
 
-        if index_count > self.index_buffer.capacity() as u32 {
-            let new_capacity = self.index_buffer.capacity() * 2;
-            self.index_buffer.resize(new_capacity);
+        if index_count > self.index_buffer.capacity() {
+            self.index_buffer.resize(index_count.next_power_of_two());
         }
 
         self.vertex_buffer.write(0, &self.vertices);
         self.index_buffer.write(0, &self.indices);
+    }
+
+    /// Ranges of indices that are not empty, with the state they are drawn with
+    fn ranges(&self) -> impl Iterator<Item = (Range<u32>, DrawState)> + '_ {
+        let end = self.indices.len() as u32;
+        let first = self.states.first().map_or(end, |(from, _)| *from);
+
+        let ranges = self
+            .states
+            .iter()
+            .enumerate()
+            .map(move |(i, &(from, state))| {
+                let to = self.states.get(i + 1).map_or(end, |(next, _)| *next);
+                (from..to, state)
+            });
+
+        std::iter::once((0..first, DrawState::default()))
+            .chain(ranges)
+            .filter(|(range, _)| !range.is_empty())
+    }
+
+    /// Z indices of what is in the batch, in no particular order
+    #[inline]
+    pub fn z_indices(&self) -> impl Iterator<Item = i32> + '_ {
+        self.ranges().map(|(_, state)| state.z_index)
+    }
+
+    /// Draws the indices of the batch at `z_index`. With `runs`, only the indices in a run
+    /// are drawn, with the texture of the run bound at group 1.
+    ///
+    /// `scissor` is only given when something was clipped this frame
+    pub fn draw(
+        &mut self,
+        render_pass: &mut wgpu::RenderPass,
+        z_index: i32,
+        runs: Option<&[(&wgpu::BindGroup, Range<u32>)]>,
+        scissor: Option<&Scissor>,
+    ) {
+        let ranges: Vec<_> = self
+            .ranges()
+            .filter(|(_, state)| state.z_index == z_index)
+            .collect();
+
+        let mut bound = false;
+
+        for (range, state) in ranges {
+            if let Some(scissor) = scissor {
+                let Some((x, y, width, height)) = scissor.rect(state.clip.as_ref()) else {
+                    continue;
+                };
+
+                render_pass.set_scissor_rect(x, y, width, height);
+            }
 
-        render_pass.set_pipeline(&self.pipeline);
-        profiling::record_pipeline_switches(1);
+            if !bound {
+                render_pass.set_pipeline(&self.pipeline);
+                profiling::record_pipeline_switches(1);
+
+                render_pass.set_vertex_buffer(0, self.vertex_buffer.slice_all());
+                render_pass
+                    .set_index_buffer(self.index_buffer.slice_all(), wgpu::IndexFormat::Uint32);
+
+                bound = true;
+            }
+
+            let Some(runs) = runs else {
+                self.draw_indexed(render_pass, range);
+                continue;
+            };
+
+            for (bind_group, run) in runs {
+                let start = range.start.max(run.start);
+                let end = range.end.min(run.end);
+
+                if start < end {
+                    render_pass.set_bind_group(1, *bind_group, &[]);
+                    self.draw_indexed(render_pass, start..end);
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn draw_indexed(&mut self, render_pass: &mut wgpu::RenderPass, indices: Range<u32>) {
+        // The vertices are shared by all the draws of the batch, they are counted once
+        let vertex_count = match self.counted {
+            true => 0,
+            false => self.vertices.len() as u32,
+        };
+
+        self.counted = true;
+
+        profiling::record_draw_call(vertex_count, indices.len() as u32);
+        render_pass.draw_indexed(indices, 0, 0..1);
+    }
+}
+
+/// Turns clip rects in layer coordinates into scissor rects in pixels
+pub struct Scissor {
+    view_projection: Matrix4,
+    viewport: (Vector2, Size<f32>),
+    view: Size<u32>,
+}
+
+impl Scissor {
+    pub fn new(view_projection: Matrix4, viewport: (Vector2, Size<f32>), view: Size<u32>) -> Self {
+        Self {
+            view_projection,
+            viewport,
+            view,
+        }
+    }
+
+    /// The whole pass without a clip rect, `None` when nothing of the rect is visible
+    pub fn rect(&self, clip: Option<&Rect>) -> Option<(u32, u32, u32, u32)> {
+        let Some(clip) = clip else {
+            return Some((0, 0, self.view.width, self.view.height));
+        };
+
+        let (offset, size) = self.viewport;
+        let mut min = Vector2::new(f32::MAX, f32::MAX);
+        let mut max = Vector2::new(f32::MIN, f32::MIN);
+
+        for (x, y) in [
+            (clip.x, clip.y),
+            (clip.x + clip.width, clip.y),
+            (clip.x, clip.y + clip.height),
+            (clip.x + clip.width, clip.y + clip.height),
+        ] {
+            let ndc = self
+                .view_projection
+                .transform_point(Vector3::new(x, y, 0.0));
+
+            // Normalized device coordinates have y going up, pixels have it going down
+            let pixel = Vector2::new(
+                offset.x + (ndc.x + 1.0) * 0.5 * size.width,
+                offset.y + (1.0 - ndc.y) * 0.5 * size.height,
+            );
+
+            min = Vector2::new(min.x.min(pixel.x), min.y.min(pixel.y));
+            max = Vector2::new(max.x.max(pixel.x), max.y.max(pixel.y));
+        }
 
-        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice_all());
-        render_pass.set_index_buffer(self.index_buffer.slice_all(), wgpu::IndexFormat::Uint32);
+        let view = self.view.to_f32();
 
-        render_pass.draw_indexed(0..index_count, 0, 0..1);
-        profiling::record_draw_call(vertex_count, index_count);
+        let x = min.x.round().clamp(0.0, view.width) as u32;
+        let y = min.y.round().clamp(0.0, view.height) as u32;
+        let right = max.x.round().clamp(0.0, view.width) as u32;
+        let bottom = max.y.round().clamp(0.0, view.height) as u32;
 
-        self.clear();
+        (right > x && bottom > y).then_some((x, y, right - x, bottom - y))
     }
 }
@@ -0,0 +1,123 @@
+use crate::{
+    color::Color,
+    tessellate::{self, Tessellation},
+};
+use math::{Vector2, Vector4};
+
+/// Colors blended across a filled shape, in the same coordinates as the shape
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    kind: GradientKind,
+    /// Offsets from 0 to 1 along the gradient, in increasing order
+    stops: Vec<(f32, Color)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GradientKind {
+    Linear { start: Vector2, end: Vector2 },
+    Radial { center: Vector2, radius: f32 },
+}
+
+impl Gradient {
+    /// Goes from `from` at `start` to `to` at `end`, the
+    /// colors stay the same past both ends
+    pub fn linear<V, C>(start: V, end: V, from: C, to: C) -> Self
+    where
+        V: Into<Vector2>,
+        C: Into<Color>,
+    {
+        Self {
+            kind: GradientKind::Linear {
+                start: start.into(),
+                end: end.into(),
+            },
+            stops: vec![(0.0, from.into()), (1.0, to.into())],
+        }
+    }
+
+    /// Goes from `inner` at `center` to `outer` at `radius` from it
+    pub fn radial<V, C>(center: V, radius: f32, inner: C, outer: C) -> Self
+    where
+        V: Into<Vector2>,
+        C: Into<Color>,
+    {
+        Self {
+            kind: GradientKind::Radial {
+                center: center.into(),
+                radius,
+            },
+            stops: vec![(0.0, inner.into()), (1.0, outer.into())],
+        }
+    }
+
+    /// Adds a color at `offset`, from 0 at the start of the gradient to 1 at its end
+    pub fn with_stop<C: Into<Color>>(mut self, offset: f32, color: C) -> Self {
+        let offset = offset.clamp(0.0, 1.0);
+        let index = self.stops.partition_point(|(o, _)| *o <= offset);
+
+        self.stops.insert(index, (offset, color.into()));
+        self
+    }
+
+    /// How far `p` is along the gradient, from 0 at its start to 1 at its end
+    fn offset(&self, p: Vector2) -> f32 {
+        match self.kind {
+            GradientKind::Linear { start, end } => {
+                let direction = end - start;
+                let length = direction.length_squared();
+
+                if length <= f32::EPSILON {
+                    return 0.0;
+                }
+
+                (p - start).dot(&direction) / length
+            }
+            GradientKind::Radial { center, radius } => {
+                (p - center).length() / radius.abs().max(f32::EPSILON)
+            }
+        }
+    }
+
+    pub(crate) fn color_at(&self, p: Vector2) -> Vector4 {
+        let offset = self.offset(p);
+        let next = self.stops.partition_point(|(o, _)| *o <= offset);
+
+        let (from, to) = match (next.checked_sub(1), self.stops.get(next)) {
+            (Some(i), Some(&to)) => (self.stops[i], to),
+            (Some(i), None) => return self.stops[i].1.into(),
+            (None, Some(&(_, color))) => return color.into(),
+            (None, None) => return Color::White.into(),
+        };
+
+        let t = (offset - from.0) / (to.0 - from.0).max(f32::EPSILON);
+        let from: Vector4 = from.1.into();
+        let to: Vector4 = to.1.into();
+
+        from + (to - from) * t
+    }
+
+    /// Splits the triangles of `shape` so that the colors interpolated between
+    /// their vertices follow the gradient.
+    ///
+    /// Linear gradients are cut along their stops, radial
+    /// ones are split in triangles small enough to look round
+    pub(crate) fn refine(&self, shape: &Tessellation) -> Tessellation {
+        match self.kind {
+            GradientKind::Linear { .. } => {
+                let cuts: Vec<f32> = self.stops.iter().map(|(offset, _)| *offset).collect();
+
+                tessellate::split_bands(shape, |p| self.offset(p), &cuts)
+            }
+            GradientKind::Radial { radius, .. } => {
+                tessellate::subdivide(shape, radius.abs() / RADIAL_STEPS, RADIAL_TRIANGLES)
+            }
+        }
+    }
+}
+
+/// Triangles across the radius of a radial gradient
+const RADIAL_STEPS: f32 = 16.0;
+
+/// Most triangles a shape filled with a radial gradient is split in,
+/// large shapes get coarser steps rather than more of them
+const RADIAL_TRIANGLES: u32 = 1024;
@@ -1,11 +1,19 @@
 use std::borrow::Borrow;
 
-use crate::{Layer, Renderer, color::Color, retained::SceneView};
-use assets::{AssetServerGuard, Font, Image};
+use crate::{
+    Layer, Renderer,
+    color::Color,
+    immediate::{Gradient, ImageOptions},
+    retained::SceneView,
+    target::ImageSource,
+    tessellate::Stroke,
+    text_layout::{TextBounds, TextLayout},
+    vertex::Vertex,
+};
+use assets::{AssetServerGuard, FontFace};
 use logging::LogLevel;
 use macros::{Get, Set};
-use math::Vector2;
-use utils::Handle;
+use math::{Matrix3, Vector2};
 
 #[derive(Get, Set)]
 pub struct Draw<'a> {
@@ -53,6 +61,104 @@ impl<'a> Draw<'a> {
         layer.immediate.draw_color = color.into()
     }
 
+    /// How lines and outlines are drawn on the active layer,
+    /// one pixel wide hairlines until a stroke with a width is set
+    #[inline]
+    pub fn stroke(&self) -> &Stroke {
+        let layer = self.renderer.layer(self.renderer.active_layer);
+
+        &layer.immediate.stroke
+    }
+
+    #[inline]
+    pub fn stroke_mut(&mut self) -> &mut Stroke {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        &mut layer.immediate.stroke
+    }
+
+    #[inline]
+    pub fn set_stroke(&mut self, stroke: Stroke) {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer.immediate.stroke = stroke
+    }
+
+    /// Layout used by [`Draw::text`] and [`Draw::measure`] on the active layer
+    #[inline]
+    pub fn text_layout(&self) -> &TextLayout {
+        let layer = self.renderer.layer(self.renderer.active_layer);
+
+        &layer.immediate.text_layout
+    }
+
+    #[inline]
+    pub fn text_layout_mut(&mut self) -> &mut TextLayout {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        &mut layer.immediate.text_layout
+    }
+
+    #[inline]
+    pub fn set_text_layout(&mut self, layout: TextLayout) {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer.immediate.text_layout = layout
+    }
+
+    /// Scales, rotates by `angle` radians then moves what is drawn on the active
+    /// layer, inside of the transforms already pushed, until [`Draw::pop_transform`]
+    #[inline]
+    pub fn push_transform(&mut self, x: f32, y: f32, angle: f32, scale_x: f32, scale_y: f32) {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+        let transform =
+            Matrix3::from_scale_angle_translation([scale_x, scale_y].into(), angle, [x, y].into());
+
+        layer.immediate.push_transform(transform);
+    }
+
+    #[inline]
+    pub fn pop_transform(&mut self) {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer.immediate.pop_transform();
+    }
+
+    /// Only draws inside of the rect on the active layer until [`Draw::pop_clip`].
+    ///
+    /// The rect goes through the current transform, a rotated rect clips to its bounding box,
+    /// and nested clip rects only draw where they overlap
+    #[inline]
+    pub fn push_clip(&mut self, x: f32, y: f32, w: f32, h: f32) {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer.immediate.push_clip([x, y].into(), [w, h].into());
+    }
+
+    #[inline]
+    pub fn pop_clip(&mut self) {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer.immediate.pop_clip();
+    }
+
+    #[inline]
+    pub fn z_index(&self) -> i32 {
+        self.renderer
+            .layer(self.renderer.active_layer)
+            .immediate
+            .z_index()
+    }
+
+    /// Draws what comes next on the active layer over the shapes, meshes and
+    /// texts with a lower z index. It stays set over frames, 0 by default
+    #[inline]
+    pub fn set_z_index(&mut self, z_index: i32) {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer.immediate.set_z_index(z_index);
+    }
+
     #[inline]
     pub fn point(&mut self, x: f32, y: f32) {
         let layer = self.renderer.layer_mut(self.renderer.active_layer);
@@ -64,7 +170,9 @@ impl<'a> Draw<'a> {
     pub fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32) {
         let layer = self.renderer.layer_mut(self.renderer.active_layer);
 
-        layer.immediate.draw_line([x1, y1].into(), [x2, y2].into());
+        layer
+            .immediate
+            .draw_line([x1, y1].into(), [x2, y2].into(), &self.assets);
     }
 
     #[inline]
@@ -75,10 +183,50 @@ impl<'a> Draw<'a> {
     {
         let layer = self.renderer.layer_mut(self.renderer.active_layer);
 
-        layer.immediate.draw_lines(points.into_iter().map(|item| {
+        let lines = points.into_iter().map(|item| {
             let &(x1, y1, x2, y2) = item.borrow();
             (Vector2::new(x1, y1), Vector2::new(x2, y2))
-        }));
+        });
+
+        layer.immediate.draw_lines(lines, &self.assets);
+    }
+
+    /// Draws a line through all the points, with the joins of the stroke
+    #[inline]
+    pub fn polyline<I>(&mut self, points: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<(f32, f32)>,
+    {
+        let points = collect_points(points);
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer.immediate.draw_polyline(&points, false, &self.assets);
+    }
+
+    /// Fills a polygon, convex or concave, as long as its edges don't cross
+    #[inline]
+    pub fn polygon<I>(&mut self, points: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<(f32, f32)>,
+    {
+        let points = collect_points(points);
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer.immediate.fill_polygon(&points, &self.assets);
+    }
+
+    #[inline]
+    pub fn stroke_polygon<I>(&mut self, points: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<(f32, f32)>,
+    {
+        let points = collect_points(points);
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer.immediate.draw_polyline(&points, true, &self.assets);
     }
 
     #[inline]
@@ -88,6 +236,34 @@ impl<'a> Draw<'a> {
         layer.immediate.fill_rect([x, y].into(), w, h, &self.assets);
     }
 
+    /// Outlines are centered on the edges, half of the stroke is inside the rect
+    #[inline]
+    pub fn stroke_rect(&mut self, x: f32, y: f32, w: f32, h: f32) {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer
+            .immediate
+            .stroke_rect([x, y].into(), [w, h].into(), &self.assets);
+    }
+
+    #[inline]
+    pub fn rounded_rect(&mut self, x: f32, y: f32, w: f32, h: f32, radius: f32) {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer
+            .immediate
+            .fill_rounded_rect([x, y].into(), [w, h].into(), radius, &self.assets);
+    }
+
+    #[inline]
+    pub fn stroke_rounded_rect(&mut self, x: f32, y: f32, w: f32, h: f32, radius: f32) {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer
+            .immediate
+            .stroke_rounded_rect([x, y].into(), [w, h].into(), radius, &self.assets);
+    }
+
     #[inline]
     pub fn circle(&mut self, cx: f32, cy: f32, r: f32) {
         let layer = self.renderer.layer_mut(self.renderer.active_layer);
@@ -96,30 +272,158 @@ impl<'a> Draw<'a> {
     }
 
     #[inline]
-    pub fn image(&mut self, image: Handle<Image>, x: f32, y: f32) {
+    pub fn stroke_circle(&mut self, cx: f32, cy: f32, r: f32) {
         let layer = self.renderer.layer_mut(self.renderer.active_layer);
 
         layer
             .immediate
-            .draw_image(image, [x, y].into(), &self.assets);
+            .stroke_circle([cx, cy].into(), r, &self.assets);
     }
 
     #[inline]
-    pub fn text<T: AsRef<str>>(&mut self, font: Handle<Font>, text: T, x: f32, y: f32) {
+    pub fn rect_gradient(&mut self, x: f32, y: f32, w: f32, h: f32, gradient: &Gradient) {
         let layer = self.renderer.layer_mut(self.renderer.active_layer);
 
         layer
             .immediate
-            .draw_text(font, text.as_ref(), x, y, &self.assets);
+            .fill_rect_gradient([x, y].into(), [w, h].into(), gradient, &self.assets);
     }
 
     #[inline]
-    pub fn debug_text<T: AsRef<str>>(&mut self, text: T, x: f32, y: f32) {
+    pub fn circle_gradient(&mut self, cx: f32, cy: f32, r: f32, gradient: &Gradient) {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer
+            .immediate
+            .fill_circle_gradient([cx, cy].into(), r, gradient, &self.assets);
+    }
+
+    #[inline]
+    pub fn polygon_gradient<I>(&mut self, points: I, gradient: &Gradient)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<(f32, f32)>,
+    {
+        let points = collect_points(points);
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer
+            .immediate
+            .fill_polygon_gradient(&points, gradient, &self.assets);
+    }
+
+    /// Draws triangles with the colors of their vertices, every three
+    /// indices make a triangle. The texture coordinates are not used
+    #[inline]
+    pub fn triangles(&mut self, vertices: &[Vertex], indices: &[u32]) {
         let layer = self.renderer.layer_mut(self.renderer.active_layer);
 
         layer
             .immediate
-            .draw_text(self.assets.debug_font(), text.as_ref(), x, y, &self.assets);
+            .draw_triangles(vertices, indices, &self.assets);
+    }
+
+    /// Draws triangles sampling an image or the texture of a render target, the texture
+    /// coordinates go from `(0, 0)` at its top left corner to `(1, 1)` at its bottom right one
+    pub fn textured_triangles<I: Into<ImageSource>>(
+        &mut self,
+        image: I,
+        vertices: &[Vertex],
+        indices: &[u32],
+    ) {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        match image.into() {
+            ImageSource::Image(image) => {
+                layer
+                    .immediate
+                    .draw_image_triangles(image, vertices, indices, &self.assets);
+            }
+            ImageSource::Target(target) => {
+                layer
+                    .immediate
+                    .draw_target_triangles(target, vertices, indices);
+            }
+        }
+    }
+
+    /// Draws an image or the texture of a render target at its size,
+    /// a target shows what was rendered into it this frame
+    #[inline]
+    pub fn image<I: Into<ImageSource>>(&mut self, image: I, x: f32, y: f32) {
+        self.image_ex(image, x, y, ImageOptions::default());
+    }
+
+    /// Draws a part of an image or of the texture of a render target,
+    /// stretched, rotated, tinted or flipped as the options say
+    pub fn image_ex<I: Into<ImageSource>>(
+        &mut self,
+        image: I,
+        x: f32,
+        y: f32,
+        options: ImageOptions,
+    ) {
+        match image.into() {
+            ImageSource::Image(image) => {
+                let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+                layer
+                    .immediate
+                    .draw_image(image, [x, y].into(), &options, &self.assets);
+            }
+            ImageSource::Target(target) => {
+                let Some(size) = self.renderer.targets.get(target).map(|t| t.size()) else {
+                    return;
+                };
+
+                let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+                layer
+                    .immediate
+                    .draw_target(target, [x, y].into(), size.to_f32().into(), &options);
+            }
+        }
+    }
+
+    #[inline]
+    pub fn text<F, T>(&mut self, font: F, text: T, x: f32, y: f32)
+    where
+        F: Into<FontFace>,
+        T: AsRef<str>,
+    {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer
+            .immediate
+            .draw_text(font.into(), text.as_ref(), x, y, &self.assets);
+    }
+
+    /// Returns the bounding box `text` would have if drawn
+    /// at `(0, 0)` with the current text layout
+    #[inline]
+    pub fn measure<F, T>(&self, font: F, text: T) -> TextBounds
+    where
+        F: Into<FontFace>,
+        T: AsRef<str>,
+    {
+        let face = font.into();
+        let font = self.assets.get_font(face.handle());
+
+        self.text_layout()
+            .measure(text.as_ref(), font, face.size(font))
+    }
+
+    #[inline]
+    pub fn debug_text<T: AsRef<str>>(&mut self, text: T, x: f32, y: f32) {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer.immediate.draw_text(
+            self.assets.debug_font().into(),
+            text.as_ref(),
+            x,
+            y,
+            &self.assets,
+        );
     }
 
     #[inline]
@@ -151,3 +455,18 @@ impl<'a> Draw<'a> {
         self.set_color(prev_color);
     }
 }
+
+#[inline]
+fn collect_points<I>(points: I) -> Vec<Vector2>
+where
+    I: IntoIterator,
+    I::Item: Borrow<(f32, f32)>,
+{
+    points
+        .into_iter()
+        .map(|item| {
+            let &(x, y) = item.borrow();
+            Vector2::new(x, y)
+        })
+        .collect()
+}
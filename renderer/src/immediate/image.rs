@@ -0,0 +1,121 @@
+use crate::{color::Color, vertex::Vertex};
+use macros::With;
+use math::{Matrix3, Vector2, Vector3, Vector4};
+
+/// How [`crate::Draw::image_ex`] draws an image
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(With)]
+pub struct ImageOptions {
+    /// Size the image is stretched to, the size of the source rect when `None`
+    pub size: Option<Vector2>,
+
+    /// Part of the image that is drawn, `(x, y, width, height)` in pixels
+    /// from its top left corner, the whole image when `None`
+    pub source: Option<(f32, f32, f32, f32)>,
+
+    /// Point of the image drawn at the position and rotated around,
+    /// from `(0, 0)` at its top left corner to `(1, 1)` at its bottom right one
+    pub origin: Vector2,
+
+    /// In radians, around the origin
+    #[with]
+    pub rotation: f32,
+
+    /// Multiplied with the colors of the image
+    #[with(into)]
+    pub tint: Color,
+
+    #[with]
+    pub flip_x: bool,
+
+    #[with]
+    pub flip_y: bool,
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self {
+            size: None,
+            source: None,
+            origin: Vector2::zeros(),
+            rotation: 0.0,
+            tint: Color::White,
+            flip_x: false,
+            flip_y: false,
+        }
+    }
+}
+
+impl ImageOptions {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn with_size(mut self, width: f32, height: f32) -> Self {
+        self.size = Some(Vector2::new(width, height));
+        self
+    }
+
+    #[inline]
+    pub fn with_source(mut self, x: f32, y: f32, width: f32, height: f32) -> Self {
+        self.source = Some((x, y, width, height));
+        self
+    }
+
+    #[inline]
+    pub fn with_origin(mut self, x: f32, y: f32) -> Self {
+        self.origin = Vector2::new(x, y);
+        self
+    }
+
+    /// Rotates and places the image around its center
+    #[inline]
+    pub fn centered(self) -> Self {
+        self.with_origin(0.5, 0.5)
+    }
+
+    /// Corners of the image drawn at `pos`, clockwise from the top left one.
+    ///
+    /// The image is `native` pixels large and covers `uv` in its texture
+    pub(crate) fn quad(
+        &self,
+        pos: Vector2,
+        native: Vector2,
+        uv: (f32, f32, f32, f32),
+    ) -> [Vertex; 4] {
+        let (x, y, w, h) = self.source.unwrap_or((0.0, 0.0, native.x, native.y));
+        let (uv_x, uv_y, uv_w, uv_h) = uv;
+
+        let mut left = uv_x + x / native.x * uv_w;
+        let mut top = uv_y + y / native.y * uv_h;
+        let mut right = left + w / native.x * uv_w;
+        let mut bottom = top + h / native.y * uv_h;
+
+        if self.flip_x {
+            std::mem::swap(&mut left, &mut right);
+        }
+
+        if self.flip_y {
+            std::mem::swap(&mut top, &mut bottom);
+        }
+
+        let size = self.size.unwrap_or(Vector2::new(w, h));
+        let origin = Vector2::new(self.origin.x * size.x, self.origin.y * size.y);
+        let transform = Matrix3::from_scale_angle_translation(Vector2::ones(), self.rotation, pos);
+        let color: Vector4 = self.tint.into();
+
+        let corner = |x: f32, y: f32, uv: Vector2| {
+            let p = transform * (Vector2::new(x, y) - origin).extend(1.0);
+            Vertex::new(Vector3::new(p.x, p.y, 0.0), color, uv)
+        };
+
+        [
+            corner(0.0, 0.0, Vector2::new(left, top)),
+            corner(size.x, 0.0, Vector2::new(right, top)),
+            corner(size.x, size.y, Vector2::new(right, bottom)),
+            corner(0.0, size.y, Vector2::new(left, bottom)),
+        ]
+    }
+}
@@ -1,127 +1,320 @@
 mod batcher;
+mod gradient;
 mod handle;
+mod image;
 
-use std::borrow::Borrow;
+use std::{borrow::Borrow, ops::Range};
 
 use crate::{
     Camera,
     color::Color,
-    immediate::batcher::Batcher,
+    immediate::batcher::{Batcher, DrawState, Scissor},
     immediate_circle_shader, immediate_shader,
+    layer::LayerFrame,
+    shader::Shader,
+    target::RenderTarget,
+    tessellate::{self, Stroke, Tessellation},
+    text_layout::TextLayout,
     traits::LayoutDescriptor,
     vertex::{CircleVertex, Vertex},
 };
-use assets::{AssetServerGuard, Font, Image};
+use assets::{AssetServerGuard, FontFace, Image};
 use fontdue::layout::{CoordinateSystem, Layout, TextStyle};
 use macros::{Get, Set};
-use math::{Vector2, Vector3, Vector4};
-use utils::{FastHashMap, Handle, label};
+use math::{Matrix3, Size, Vector2, Vector3, Vector4};
+use utils::{FastHashMap, Handle, Rect, label};
 
+pub use gradient::Gradient;
 pub use handle::*;
+pub use image::ImageOptions;
 
 #[derive(Get, Set)]
 pub struct ImmediateRenderer {
     point_batcher: Batcher<Vertex>,
     linelist_batcher: Batcher<Vertex>,
-    linestrip_batcher: Batcher<Vertex>,
     triangle_batcher: Batcher<Vertex>,
     circle_batcher: Batcher<CircleVertex>,
+    /// Quads sampling render targets, drawn in runs of the same target
+    target_batcher: Batcher<Vertex>,
+    target_runs: Vec<(Handle<RenderTarget>, Range<u32>)>,
+
+    /// Each transform is combined with the ones pushed before it
+    transforms: Vec<Matrix3>,
+    /// Bounds of the clip rects in layer coordinates, each inside of the ones pushed before it
+    clips: Vec<Rect>,
+    /// Clip rect and z index of what is drawn now
+    state: DrawState,
+    /// Set when a clip rect was pushed this frame, until the layer is presented
+    scissor: Option<Scissor>,
+    clipped: bool,
 
     pub(crate) draw_color: Color,
-    text_layout: Layout,
-    char_cache: FastHashMap<u32, FastHashMap<char, (Vec<Vertex>, Vec<u32>)>>,
+    pub(crate) stroke: Stroke,
+    pub(crate) text_layout: TextLayout,
+    glyph_layout: Layout,
+    char_cache: CharCache,
 }
 
+/// Vertices and indices of each character at the origin, by font handle and size
+type CharCache = FastHashMap<(u32, u8), FastHashMap<char, (Vec<Vertex>, Vec<u32>)>>;
+
 impl ImmediateRenderer {
     pub(crate) fn new(
         surface_format: wgpu::TextureFormat,
         camera: &Camera,
-        assets: &AssetServerGuard<'_>,
+        texture_bgl: &wgpu::BindGroupLayout,
+        depth: Option<wgpu::TextureFormat>,
     ) -> Self {
-        let point_pipeline = immediate_shader()
-            .pipeline_builder()
-            .label("Immediate Pixel pipeline")
-            .vertex_entry("vs_main")
-            .fragment_entry("fs_main")
-            .topology(wgpu::PrimitiveTopology::PointList)
-            .blend_state(Some(wgpu::BlendState::ALPHA_BLENDING))
-            .build(
-                surface_format,
-                &[camera.bgl(), assets.atlas_bgl()],
-                &[Vertex::desc()],
-            );
-
-        let linelist_pipeline = immediate_shader()
-            .pipeline_builder()
-            .label("Immediate Pixel pipeline")
-            .vertex_entry("vs_main")
-            .fragment_entry("fs_main")
-            .topology(wgpu::PrimitiveTopology::LineList)
-            .blend_state(Some(wgpu::BlendState::ALPHA_BLENDING))
-            .build(
-                surface_format,
-                &[camera.bgl(), assets.atlas_bgl()],
-                &[Vertex::desc()],
-            );
-
-        let linestrip_pipeline = immediate_shader()
-            .pipeline_builder()
-            .label("Immediate Pixel pipeline")
-            .vertex_entry("vs_main")
-            .fragment_entry("fs_main")
-            .topology(wgpu::PrimitiveTopology::LineStrip)
-            .blend_state(Some(wgpu::BlendState::ALPHA_BLENDING))
-            .build(
-                surface_format,
-                &[camera.bgl(), assets.atlas_bgl()],
-                &[Vertex::desc()],
-            );
-
-        let triangle_pipeline = immediate_shader()
-            .pipeline_builder()
-            .label("Immediate Triangle pipeline")
-            .vertex_entry("vs_main")
-            .fragment_entry("fs_main")
-            .topology(wgpu::PrimitiveTopology::TriangleList)
-            .blend_state(Some(wgpu::BlendState::ALPHA_BLENDING))
-            .build(
-                surface_format,
-                &[camera.bgl(), assets.atlas_bgl()],
-                &[Vertex::desc()],
-            );
-
-        let circle_pipeline = immediate_circle_shader()
-            .pipeline_builder()
-            .label("Immediate Circle pipeline")
-            .vertex_entry("vs_main")
-            .fragment_entry("fs_main")
-            .topology(wgpu::PrimitiveTopology::TriangleList)
-            .blend_state(Some(wgpu::BlendState::ALPHA_BLENDING))
-            .build(
-                surface_format,
-                &[camera.bgl(), assets.atlas_bgl()],
-                &[CircleVertex::desc()],
-            );
+        let [
+            point_pipeline,
+            linelist_pipeline,
+            triangle_pipeline,
+            circle_pipeline,
+        ] = Self::pipelines(surface_format, camera, texture_bgl, depth);
 
         let point_batcher = Batcher::new(point_pipeline);
         let linelist_batcher = Batcher::new(linelist_pipeline);
-        let linestrip_batcher = Batcher::new(linestrip_pipeline);
+        let target_batcher = Batcher::new(triangle_pipeline.clone());
         let triangle_batcher = Batcher::new(triangle_pipeline);
 
         let circle_batcher = Batcher::new(circle_pipeline);
 
         Self {
             draw_color: Color::White,
+            stroke: Stroke::default(),
             point_batcher,
             linelist_batcher,
-            linestrip_batcher,
             triangle_batcher,
             circle_batcher,
-            text_layout: Layout::new(CoordinateSystem::PositiveYDown),
+            target_batcher,
+            target_runs: Vec::new(),
+            transforms: Vec::new(),
+            clips: Vec::new(),
+            state: DrawState::default(),
+            scissor: None,
+            clipped: false,
+            text_layout: TextLayout::default(),
+            glyph_layout: Layout::new(CoordinateSystem::PositiveYDown),
             char_cache: FastHashMap::default(),
         }
     }
 
+    /// Points, line lists, triangles and circles, in that order.
+    ///
+    /// Immediate shapes are drawn in order on top of what came before them,
+    /// whatever the depth buffer
+    fn pipelines(
+        surface_format: wgpu::TextureFormat,
+        camera: &Camera,
+        texture_bgl: &wgpu::BindGroupLayout,
+        depth: Option<wgpu::TextureFormat>,
+    ) -> [wgpu::RenderPipeline; 4] {
+        let bind_group_layouts = [camera.bgl(), texture_bgl];
+
+        let build = |shader: &Shader, label, topology, vertex_buffer| {
+            shader
+                .pipeline_builder()
+                .label(label)
+                .vertex_entry("vs_main")
+                .fragment_entry("fs_main")
+                .topology(topology)
+                .blend_state(Some(wgpu::BlendState::ALPHA_BLENDING))
+                .depth_format(depth)
+                .depth_compare(wgpu::CompareFunction::Always)
+                .depth_write(false)
+                .build(surface_format, &bind_group_layouts, &[vertex_buffer])
+        };
+
+        [
+            build(
+                immediate_shader(),
+                "Immediate Pixel pipeline",
+                wgpu::PrimitiveTopology::PointList,
+                Vertex::desc(),
+            ),
+            build(
+                immediate_shader(),
+                "Immediate Pixel pipeline",
+                wgpu::PrimitiveTopology::LineList,
+                Vertex::desc(),
+            ),
+            build(
+                immediate_shader(),
+                "Immediate Triangle pipeline",
+                wgpu::PrimitiveTopology::TriangleList,
+                Vertex::desc(),
+            ),
+            build(
+                immediate_circle_shader(),
+                "Immediate Circle pipeline",
+                wgpu::PrimitiveTopology::TriangleList,
+                CircleVertex::desc(),
+            ),
+        ]
+    }
+
+    /// Rebuilds the pipelines for passes with or without a depth buffer
+    pub(crate) fn set_depth(
+        &mut self,
+        surface_format: wgpu::TextureFormat,
+        camera: &Camera,
+        texture_bgl: &wgpu::BindGroupLayout,
+        depth: Option<wgpu::TextureFormat>,
+    ) {
+        let [point, linelist, triangle, circle] =
+            Self::pipelines(surface_format, camera, texture_bgl, depth);
+
+        self.point_batcher.set_pipeline(point);
+        self.linelist_batcher.set_pipeline(linelist);
+        self.target_batcher.set_pipeline(triangle.clone());
+        self.triangle_batcher.set_pipeline(triangle);
+        self.circle_batcher.set_pipeline(circle);
+    }
+
+    /// Transform applied to what is drawn, identity when none was pushed
+    #[inline]
+    fn transform(&self) -> Matrix3 {
+        self.transforms
+            .last()
+            .copied()
+            .unwrap_or_else(Matrix3::identity)
+    }
+
+    /// Combines `transform` with the current one for what is drawn until it is popped
+    pub fn push_transform(&mut self, transform: Matrix3) {
+        self.apply_transform();
+
+        let transform = self.transform() * transform;
+        self.transforms.push(transform);
+    }
+
+    pub fn pop_transform(&mut self) {
+        self.apply_transform();
+        self.transforms.pop();
+    }
+
+    /// Moves the vertices drawn since the transform last changed.
+    ///
+    /// Circles stay round, their radius is scaled by the average scale of the transform
+    fn apply_transform(&mut self) {
+        let transform = self.transforms.last().copied();
+
+        let point = |p: Vector2, transform: &Matrix3| {
+            let p = *transform * p.extend(1.0);
+            Vector2::new(p.x, p.y)
+        };
+
+        for batcher in [
+            &mut self.point_batcher,
+            &mut self.linelist_batcher,
+            &mut self.triangle_batcher,
+            &mut self.target_batcher,
+        ] {
+            let vertices = batcher.untransformed();
+
+            let Some(transform) = &transform else {
+                continue;
+            };
+
+            for vertex in vertices {
+                let p = point(
+                    Vector2::new(vertex.position.x, vertex.position.y),
+                    transform,
+                );
+                vertex.position = p.extend(vertex.position.z);
+            }
+        }
+
+        let vertices = self.circle_batcher.untransformed();
+
+        let Some(transform) = &transform else {
+            return;
+        };
+
+        let scale = transform.to_matrix2().determinant().abs().sqrt();
+
+        for vertex in vertices {
+            let p = point(
+                Vector2::new(vertex.position.x, vertex.position.y),
+                transform,
+            );
+            vertex.position = p.extend(vertex.position.z);
+            vertex.center = point(vertex.center, transform);
+            vertex.radius *= scale;
+        }
+    }
+
+    /// Only draws inside of the rect until it is popped, the rect
+    /// is moved by the current transform and clipped by the current clip
+    pub fn push_clip(&mut self, pos: Vector2, size: Vector2) {
+        let transform = self.transform();
+        let mut min = Vector2::new(f32::MAX, f32::MAX);
+        let mut max = Vector2::new(f32::MIN, f32::MIN);
+
+        // Rotated rects are clipped to their bounding box
+        for corner in [
+            pos,
+            Vector2::new(pos.x + size.x, pos.y),
+            pos + size,
+            Vector2::new(pos.x, pos.y + size.y),
+        ] {
+            let p = transform * corner.extend(1.0);
+
+            min = Vector2::new(min.x.min(p.x), min.y.min(p.y));
+            max = Vector2::new(max.x.max(p.x), max.y.max(p.y));
+        }
+
+        if let Some(parent) = self.clips.last() {
+            min = Vector2::new(min.x.max(parent.x), min.y.max(parent.y));
+            max = Vector2::new(
+                max.x.min(parent.x + parent.width),
+                max.y.min(parent.y + parent.height),
+            );
+        }
+
+        let clip = Rect {
+            x: min.x,
+            y: min.y,
+            width: (max.x - min.x).max(0.0),
+            height: (max.y - min.y).max(0.0),
+        };
+
+        self.clips.push(clip);
+        self.clipped = true;
+
+        self.state.clip = Some(clip);
+        self.set_state();
+    }
+
+    pub fn pop_clip(&mut self) {
+        self.clips.pop();
+
+        self.state.clip = self.clips.last().copied();
+        self.set_state();
+    }
+
+    #[inline]
+    pub fn z_index(&self) -> i32 {
+        self.state.z_index
+    }
+
+    /// Draws what comes next over what has a lower z index in the layer,
+    /// including meshes and texts. It stays the same over frames
+    pub fn set_z_index(&mut self, z_index: i32) {
+        self.state.z_index = z_index;
+        self.set_state();
+    }
+
+    fn set_state(&mut self) {
+        let state = self.state;
+
+        self.point_batcher.set_state(state);
+        self.linelist_batcher.set_state(state);
+        self.triangle_batcher.set_state(state);
+        self.circle_batcher.set_state(state);
+        self.target_batcher.set_state(state);
+    }
+
     #[inline]
     pub fn draw_point(&mut self, pos: Vector2) {
         let color: Vector4 = self.draw_color.into();
@@ -135,54 +328,275 @@ impl ImmediateRenderer {
     }
 
     #[inline]
-    pub fn draw_line(&mut self, p1: Vector2, p2: Vector2) {
-        let color: Vector4 = self.draw_color.into();
-        let base = self.linelist_batcher.vertices.len() as u32;
-
-        self.linelist_batcher.vertices.extend_from_slice(&[
-            Vertex::new(p1.extend(0.0), color, Vector2::zeros()),
-            Vertex::new(p2.extend(0.0), color, Vector2::zeros()),
-        ]);
-
-        self.linelist_batcher
-            .indices
-            .extend_from_slice(&[base, base + 1]);
+    pub fn draw_line(&mut self, p1: Vector2, p2: Vector2, assets: &AssetServerGuard<'_>) {
+        self.draw_polyline(&[p1, p2], false, assets);
     }
 
     #[inline]
-    pub fn draw_lines<I>(&mut self, points: I)
+    pub fn draw_lines<I>(&mut self, lines: I, assets: &AssetServerGuard<'_>)
     where
         I: IntoIterator,
         I::Item: Borrow<(Vector2, Vector2)>,
     {
-        let color: Vector4 = self.draw_color.into();
-        let base = self.linestrip_batcher.vertices.len() as u32;
+        for item in lines {
+            let (p1, p2) = *item.borrow();
+            self.draw_polyline(&[p1, p2], false, assets);
+        }
+    }
 
-        let mut vertex_count = 0;
+    /// Draws a line through `points` with the current stroke,
+    /// linking the last point to the first one when `closed`
+    pub fn draw_polyline(
+        &mut self,
+        points: &[Vector2],
+        closed: bool,
+        assets: &AssetServerGuard<'_>,
+    ) {
+        if !self.stroke.is_hairline() {
+            let shape = tessellate::stroke(points, closed, &self.stroke);
+            self.push_shape(&shape, assets);
+            return;
+        }
 
-        for item in points {
-            let (p1, p2) = *item.borrow();
+        let color: Vector4 = self.draw_color.into();
 
-            self.linestrip_batcher.vertices.extend_from_slice(&[
-                Vertex::new(p1.extend(0.0), color, Vector2::zeros()),
-                Vertex::new(p2.extend(0.0), color, Vector2::zeros()),
-            ]);
+        for (piece, closed) in tessellate::pieces(points, closed, &self.stroke) {
+            let count = piece.len() as u32;
+            let segments = if closed && count > 2 {
+                count
+            } else {
+                count - 1
+            };
+            let base = self.linelist_batcher.vertices.len() as u32;
+
+            self.linelist_batcher.vertices.extend(
+                piece
+                    .iter()
+                    .map(|p| Vertex::new(p.extend(0.0), color, Vector2::zeros())),
+            );
 
-            vertex_count += 2;
+            for i in 0..segments {
+                self.linelist_batcher
+                    .indices
+                    .extend_from_slice(&[base + i, base + (i + 1) % count]);
+            }
         }
+    }
+
+    /// Adds triangles in the draw color, sampling the white pixel of the atlas
+    fn push_shape(&mut self, shape: &Tessellation, assets: &AssetServerGuard<'_>) {
+        let color: Vector4 = self.draw_color.into();
+        let uv = white_uv(assets);
+        let base = self.triangle_batcher.vertices.len() as u32;
+
+        self.triangle_batcher.vertices.extend(
+            shape
+                .positions
+                .iter()
+                .map(|p| Vertex::new(p.extend(0.0), color, uv)),
+        );
+
+        self.triangle_batcher
+            .indices
+            .extend(shape.indices.iter().map(|i| base + i));
+    }
+
+    /// Adds triangles colored by `gradient` at each of their vertices
+    fn push_gradient(
+        &mut self,
+        shape: &Tessellation,
+        gradient: &Gradient,
+        assets: &AssetServerGuard<'_>,
+    ) {
+        let shape = gradient.refine(shape);
+        let uv = white_uv(assets);
+        let base = self.triangle_batcher.vertices.len() as u32;
+
+        self.triangle_batcher.vertices.extend(
+            shape
+                .positions
+                .iter()
+                .map(|p| Vertex::new(p.extend(0.0), gradient.color_at(*p), uv)),
+        );
+
+        self.triangle_batcher
+            .indices
+            .extend(shape.indices.iter().map(|i| base + i));
+    }
+
+    #[inline]
+    pub fn fill_rect_gradient(
+        &mut self,
+        pos: Vector2,
+        size: Vector2,
+        gradient: &Gradient,
+        assets: &AssetServerGuard<'_>,
+    ) {
+        let mut shape = Tessellation::default();
+        shape.quad(
+            pos,
+            Vector2::new(pos.x + size.x, pos.y),
+            pos + size,
+            Vector2::new(pos.x, pos.y + size.y),
+        );
+
+        self.push_gradient(&shape, gradient, assets);
+    }
+
+    /// Gradient circles are made of triangles, without the smooth edges of [`Self::fill_circle`]
+    #[inline]
+    pub fn fill_circle_gradient(
+        &mut self,
+        center: Vector2,
+        radius: f32,
+        gradient: &Gradient,
+        assets: &AssetServerGuard<'_>,
+    ) {
+        let mut shape = Tessellation::default();
+        shape.fan(center, radius, 0.0, std::f32::consts::TAU);
+
+        self.push_gradient(&shape, gradient, assets);
+    }
+
+    #[inline]
+    pub fn fill_polygon_gradient(
+        &mut self,
+        points: &[Vector2],
+        gradient: &Gradient,
+        assets: &AssetServerGuard<'_>,
+    ) {
+        let shape = Tessellation {
+            positions: points.to_vec(),
+            indices: tessellate::triangulate(points),
+        };
+
+        self.push_gradient(&shape, gradient, assets);
+    }
+
+    /// Adds triangles as they are, sampling the white pixel of the atlas
+    #[inline]
+    pub fn draw_triangles(
+        &mut self,
+        vertices: &[Vertex],
+        indices: &[u32],
+        assets: &AssetServerGuard<'_>,
+    ) {
+        let uv = white_uv(assets);
+
+        self.push_triangles(&map_uv(vertices, |_| uv), indices);
+    }
+
+    /// Adds triangles with texture coordinates from `(0, 0)` at the
+    /// top left corner of the image to `(1, 1)` at its bottom right one
+    #[inline]
+    pub fn draw_image_triangles(
+        &mut self,
+        image: Handle<Image>,
+        vertices: &[Vertex],
+        indices: &[u32],
+        assets: &AssetServerGuard<'_>,
+    ) {
+        let (uv_x, uv_y, uv_w, uv_h, _, _) = assets.get_texture_uv(image);
+        let vertices = map_uv(vertices, |uv| {
+            Vector2::new(uv_x + uv.x * uv_w, uv_y + uv.y * uv_h)
+        });
 
-        self.linestrip_batcher
+        self.push_triangles(&vertices, indices);
+    }
+
+    fn push_triangles(&mut self, vertices: &[Vertex], indices: &[u32]) {
+        let base = self.triangle_batcher.vertices.len() as u32;
+
+        self.triangle_batcher.vertices.extend_from_slice(vertices);
+        self.triangle_batcher
             .indices
-            .extend(base..base + vertex_count);
+            .extend(valid_indices(vertices, indices).map(|i| base + i));
+    }
+
+    /// Adds triangles sampling the texture of a render target,
+    /// from `(0, 0)` at its top left corner to `(1, 1)` at its bottom right one
+    pub fn draw_target_triangles(
+        &mut self,
+        target: Handle<RenderTarget>,
+        vertices: &[Vertex],
+        indices: &[u32],
+    ) {
+        let base = self.target_batcher.vertices.len() as u32;
+        let start = self.target_batcher.indices.len() as u32;
+
+        self.target_batcher.vertices.extend_from_slice(vertices);
+        self.target_batcher
+            .indices
+            .extend(valid_indices(vertices, indices).map(|i| base + i));
+
+        let end = self.target_batcher.indices.len() as u32;
+
+        match self.target_runs.last_mut() {
+            Some((last, run)) if *last == target => run.end = end,
+            _ => self.target_runs.push((target, start..end)),
+        }
+    }
+
+    /// Fills a polygon, convex or concave, that doesn't cross itself
+    #[inline]
+    pub fn fill_polygon(&mut self, points: &[Vector2], assets: &AssetServerGuard<'_>) {
+        let shape = Tessellation {
+            positions: points.to_vec(),
+            indices: tessellate::triangulate(points),
+        };
+
+        self.push_shape(&shape, assets);
+    }
+
+    #[inline]
+    pub fn fill_rounded_rect(
+        &mut self,
+        pos: Vector2,
+        size: Vector2,
+        radius: f32,
+        assets: &AssetServerGuard<'_>,
+    ) {
+        let outline = rounded_rect_outline(pos, size, radius);
+        self.fill_polygon(&outline, assets);
+    }
+
+    /// Outlines are centered on the edges of the shape
+    #[inline]
+    pub fn stroke_rect(&mut self, pos: Vector2, size: Vector2, assets: &AssetServerGuard<'_>) {
+        let corners = [
+            pos,
+            Vector2::new(pos.x + size.x, pos.y),
+            pos + size,
+            Vector2::new(pos.x, pos.y + size.y),
+        ];
+
+        self.draw_polyline(&corners, true, assets);
+    }
+
+    #[inline]
+    pub fn stroke_circle(&mut self, center: Vector2, radius: f32, assets: &AssetServerGuard<'_>) {
+        let segments = tessellate::circle_segments(radius);
+        let outline = tessellate::circle_outline(center, radius, segments);
+
+        self.draw_polyline(&outline, true, assets);
+    }
+
+    #[inline]
+    pub fn stroke_rounded_rect(
+        &mut self,
+        pos: Vector2,
+        size: Vector2,
+        radius: f32,
+        assets: &AssetServerGuard<'_>,
+    ) {
+        let outline = rounded_rect_outline(pos, size, radius);
+        self.draw_polyline(&outline, true, assets);
     }
 
     #[inline]
     pub fn fill_rect(&mut self, pos: Vector2, w: f32, h: f32, assets: &AssetServerGuard<'_>) {
         let color: Vector4 = self.draw_color.into();
-
-        // Get white pixel UV coords for solid color rendering
-        let (uv_x, uv_y, uv_w, uv_h, _, _) = assets.get_white_uv_coords();
-        let uv_center: Vector2 = [uv_x + uv_w * 0.5, uv_y + uv_h * 0.5].into();
+        let uv_center = white_uv(assets);
 
         let base = self.triangle_batcher.vertices.len() as u32;
 
@@ -240,31 +654,42 @@ impl ImmediateRenderer {
         &mut self,
         image: Handle<Image>,
         pos: Vector2,
+        options: &ImageOptions,
         assets: &AssetServerGuard<'_>,
     ) {
-        let color: Vector4 = Color::White.into();
-
         let (uv_x, uv_y, uv_w, uv_h, w, h) = assets.get_texture_uv(image);
-
-        let uv_top_left: Vector2 = [uv_x, uv_y].into();
-        let uv_top_right: Vector2 = [uv_x + uv_w, uv_y].into();
-        let uv_bottom_right: Vector2 = [uv_x + uv_w, uv_y + uv_h].into();
-        let uv_bottom_left: Vector2 = [uv_x, uv_y + uv_h].into();
+        let quad = options.quad(pos, Vector2::new(w, h), (uv_x, uv_y, uv_w, uv_h));
 
         let base = self.triangle_batcher.vertices.len() as u32;
 
-        self.triangle_batcher.vertices.extend_from_slice(&[
-            Vertex::new(pos.extend(0.0), color, uv_top_left),
-            Vertex::new(Vector3::new(pos.x + w, pos.y, 0.0), color, uv_top_right),
-            Vertex::new(
-                Vector3::new(pos.x + w, pos.y + h, 0.0),
-                color,
-                uv_bottom_right,
-            ),
-            Vertex::new(Vector3::new(pos.x, pos.y + h, 0.0), color, uv_bottom_left),
+        self.triangle_batcher.vertices.extend_from_slice(&quad);
+        self.triangle_batcher.indices.extend_from_slice(&[
+            base,
+            base + 1,
+            base + 2,
+            base,
+            base + 2,
+            base + 3,
         ]);
+    }
 
-        self.triangle_batcher.indices.extend_from_slice(&[
+    /// Draws the texture of a render target, as it was at the end of the last frame
+    /// if the target is rendered after this layer
+    #[inline]
+    pub fn draw_target(
+        &mut self,
+        target: Handle<RenderTarget>,
+        pos: Vector2,
+        size: Vector2,
+        options: &ImageOptions,
+    ) {
+        let quad = options.quad(pos, size, (0.0, 0.0, 1.0, 1.0));
+
+        let base = self.target_batcher.vertices.len() as u32;
+        let start = self.target_batcher.indices.len() as u32;
+
+        self.target_batcher.vertices.extend_from_slice(&quad);
+        self.target_batcher.indices.extend_from_slice(&[
             base,
             base + 1,
             base + 2,
@@ -272,6 +697,13 @@ impl ImmediateRenderer {
             base + 2,
             base + 3,
         ]);
+
+        let end = start + 6;
+
+        match self.target_runs.last_mut() {
+            Some((last, run)) if *last == target => run.end = end,
+            _ => self.target_runs.push((target, start..end)),
+        }
     }
 
     #[inline]
@@ -311,26 +743,29 @@ impl ImmediateRenderer {
     #[inline]
     pub fn draw_text(
         &mut self,
-        handle: Handle<Font>,
+        face: FontFace,
         text: &str,
         x: f32,
         y: f32,
         assets: &AssetServerGuard<'_>,
     ) {
+        if !assets.font_face_ready(face) {
+            return;
+        }
+
         let color: Vector4 = self.draw_color.into();
-        let font = assets.get_font(handle);
+        let font = assets.get_font(face.handle());
+        let size = face.size(font);
 
-        self.text_layout.clear();
-        self.text_layout.append(
-            &[font.inner()],
-            &TextStyle::new(text, font.size() as f32, 0),
-        );
+        self.glyph_layout.reset(&self.text_layout.settings());
+        self.glyph_layout
+            .append(&[font.inner()], &TextStyle::new(text, size as f32, 0));
 
-        let glyphs = self.text_layout.glyphs();
+        let glyphs = self.glyph_layout.glyphs();
         let cache = self
             .char_cache
-            .entry(handle.index())
-            .or_insert_with(FastHashMap::default);
+            .entry((face.handle().index(), size))
+            .or_default();
 
         for glyph in glyphs {
             if glyph.width == 0 || glyph.height == 0 {
@@ -355,7 +790,7 @@ impl ImmediateRenderer {
                 }
             } else {
                 // Create new geometry and cache it
-                let (uv_x, uv_y, uv_w, uv_h, _, _) = assets.get_glyph_uv(handle, ch);
+                let (uv_x, uv_y, uv_w, uv_h, _, _) = assets.get_glyph_uv(face, ch);
 
                 let screen_x = x + glyph.x;
                 let screen_y = y + glyph.y;
@@ -411,12 +846,139 @@ impl ImmediateRenderer {
         }
     }
 
-    #[inline]
-    pub fn present<'a>(&'a mut self, render_pass: &mut wgpu::RenderPass<'a>) {
-        self.point_batcher.present(render_pass);
-        self.linelist_batcher.present(render_pass);
-        self.linestrip_batcher.present(render_pass);
-        self.triangle_batcher.present(render_pass);
-        self.circle_batcher.present(render_pass);
+    /// Uploads what was drawn this frame, before it is drawn in parts by [`Self::draw`].
+    ///
+    /// The transforms and clip rects still pushed are dropped at the end of the frame
+    pub(crate) fn prepare(
+        &mut self,
+        camera: &Camera,
+        viewport: (Vector2, Size<f32>),
+        view: Size<u32>,
+    ) {
+        self.apply_transform();
+        self.transforms.clear();
+        self.clips.clear();
+
+        self.scissor = self
+            .clipped
+            .then(|| Scissor::new(camera.view_projection(), viewport, view));
+
+        self.point_batcher.upload();
+        self.linelist_batcher.upload();
+        self.triangle_batcher.upload();
+        self.circle_batcher.upload();
+        self.target_batcher.upload();
+    }
+
+    /// Z indices of what was drawn this frame, in no particular order
+    pub(crate) fn z_indices(&self) -> impl Iterator<Item = i32> + '_ {
+        self.point_batcher
+            .z_indices()
+            .chain(self.linelist_batcher.z_indices())
+            .chain(self.triangle_batcher.z_indices())
+            .chain(self.circle_batcher.z_indices())
+            .chain(self.target_batcher.z_indices())
+    }
+
+    /// Draws what has `z_index`, points first, then lines,
+    /// triangles, circles and render targets
+    pub(crate) fn draw(
+        &mut self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        z_index: i32,
+        view: Size<u32>,
+        frame: &LayerFrame<'_, '_>,
+    ) {
+        let assets = frame.assets;
+        let scissor = self.scissor.as_ref();
+
+        // Retained meshes drawn before may have bound a render target
+        render_pass.set_bind_group(1, assets.atlas_bg(), &[]);
+
+        self.point_batcher.draw(render_pass, z_index, None, scissor);
+        self.linelist_batcher
+            .draw(render_pass, z_index, None, scissor);
+        self.triangle_batcher
+            .draw(render_pass, z_index, None, scissor);
+        self.circle_batcher
+            .draw(render_pass, z_index, None, scissor);
+
+        if !self.target_runs.is_empty() {
+            // Runs of removed targets, or of the target being rendered, are skipped
+            let runs: Vec<_> = self
+                .target_runs
+                .iter()
+                .filter_map(|(target, indices)| {
+                    frame
+                        .target(*target)
+                        .map(|target| (target.bind_group(), indices.clone()))
+                })
+                .collect();
+
+            self.target_batcher
+                .draw(render_pass, z_index, Some(&runs), scissor);
+            render_pass.set_bind_group(1, assets.atlas_bg(), &[]);
+        }
+
+        // What is drawn after the immediate shapes is not clipped
+        if scissor.is_some() {
+            render_pass.set_scissor_rect(0, 0, view.width, view.height);
+        }
+    }
+
+    /// Empties the batches once everything was drawn
+    pub(crate) fn finish(&mut self) {
+        self.point_batcher.clear();
+        self.linelist_batcher.clear();
+        self.triangle_batcher.clear();
+        self.circle_batcher.clear();
+        self.target_batcher.clear();
+        self.target_runs.clear();
+
+        self.scissor = None;
+        self.clipped = false;
+
+        // The z index carries over to the next frame, the clip rects don't
+        self.state.clip = None;
+        self.set_state();
     }
 }
+
+#[inline]
+fn map_uv(vertices: &[Vertex], uv: impl Fn(Vector2) -> Vector2) -> Vec<Vertex> {
+    vertices
+        .iter()
+        .map(|vertex| Vertex {
+            uv: uv(vertex.uv),
+            ..*vertex
+        })
+        .collect()
+}
+
+/// Indices of the whole triangles that only refer to existing vertices
+fn valid_indices<'a>(vertices: &[Vertex], indices: &'a [u32]) -> impl Iterator<Item = u32> + 'a {
+    let count = vertices.len() as u32;
+
+    indices
+        .chunks_exact(3)
+        .filter(move |triangle| triangle.iter().all(|&i| i < count))
+        .flatten()
+        .copied()
+}
+
+/// Center of the white pixel of the atlas, for solid color rendering
+#[inline]
+fn white_uv(assets: &AssetServerGuard<'_>) -> Vector2 {
+    let (uv_x, uv_y, uv_w, uv_h, _, _) = assets.get_white_uv_coords();
+
+    Vector2::new(uv_x + uv_w * 0.5, uv_y + uv_h * 0.5)
+}
+
+/// Outline of a rounded rect from its top left corner, with
+/// enough points per corner for the size of the rounding
+fn rounded_rect_outline(pos: Vector2, size: Vector2, radius: f32) -> Vec<Vector2> {
+    let half = size / 2.0;
+    let segments = (tessellate::circle_segments(radius) / 4).max(1);
+
+    tessellate::rounded_rect_outline(pos + half, half, radius, segments)
+}
@@ -1,10 +1,14 @@
 use crate::{
     camera::Camera,
     immediate::ImmediateRenderer,
+    light::Lights,
     retained::{RetainedRenderer, TextRenderer},
+    target::RenderTarget,
+    timing::{GpuTimer, Section},
 };
 use assets::AssetServerGuard;
-use math::Size;
+use math::{Size, Vector2};
+use utils::{Handle, SlotMap};
 
 #[derive(Default)]
 #[derive(Debug, Clone, Copy)]
@@ -13,6 +17,29 @@ pub enum Layer {
     World,
     Ui,
     Custom(usize),
+    /// Rendered into the texture of a [`RenderTarget`] before the window
+    Target(Handle<RenderTarget>),
+}
+
+/// What every layer presented in a frame shares
+pub(crate) struct LayerFrame<'a, 'b> {
+    pub(crate) assets: &'a AssetServerGuard<'b>,
+    pub(crate) targets: &'a SlotMap<RenderTarget>,
+    pub(crate) delta: f32,
+    /// Target the layer is rendered into, if any
+    pub(crate) rendering: Option<Handle<RenderTarget>>,
+}
+
+impl LayerFrame<'_, '_> {
+    /// The target `handle` can be sampled unless it was removed,
+    /// or it is the one rendered into since a pass can't read what it writes
+    #[inline]
+    pub(crate) fn target(&self, handle: Handle<RenderTarget>) -> Option<&RenderTarget> {
+        match self.rendering {
+            Some(rendering) if rendering == handle => None,
+            _ => self.targets.get(handle),
+        }
+    }
 }
 
 pub struct RenderLayer {
@@ -21,42 +48,112 @@ pub struct RenderLayer {
     pub(crate) retained: RetainedRenderer,
     pub(crate) immediate: ImmediateRenderer,
     pub(crate) text: TextRenderer,
+    pub(crate) lights: Lights,
+
+    /// Whether the layer goes through the [`crate::PostProcess`] effects
+    pub(crate) post_processed: bool,
 }
 
 impl RenderLayer {
     pub(crate) fn new(
-        config: &wgpu::SurfaceConfiguration,
-        assets: &AssetServerGuard<'_>,
+        format: wgpu::TextureFormat,
+        texture_bgl: &wgpu::BindGroupLayout,
         camera: Camera,
+        depth: Option<wgpu::TextureFormat>,
     ) -> Self {
-        let immediate = ImmediateRenderer::new(config.format, &camera, &assets);
-        let retained = RetainedRenderer::new(config.format, &camera, &assets);
-        let text = TextRenderer::new(config.format, &camera, &assets);
+        let lights = Lights::new();
+        let immediate = ImmediateRenderer::new(format, &camera, texture_bgl, depth);
+        let retained = RetainedRenderer::new(format, &camera, texture_bgl, lights.bgl(), depth);
+        let text = TextRenderer::new(format, &camera, texture_bgl, depth);
 
         Self {
             camera,
             retained,
             immediate,
             text,
+            lights,
+            post_processed: true,
         }
     }
 
+    /// The pipelines must match the depth buffer of the passes the layer is drawn in
+    pub(crate) fn set_depth(
+        &mut self,
+        format: wgpu::TextureFormat,
+        texture_bgl: &wgpu::BindGroupLayout,
+        depth: Option<wgpu::TextureFormat>,
+    ) {
+        let camera = &self.camera;
+
+        self.immediate.set_depth(format, camera, texture_bgl, depth);
+        let lights = self.lights.bgl();
+
+        self.retained
+            .set_depth(format, camera, texture_bgl, lights, depth);
+        self.text.set_depth(format, camera, texture_bgl, depth);
+    }
+
     #[inline]
     pub fn queue_resize(&mut self) {
         self.camera.queue_resize();
     }
 
+    /// `viewport` is the part of the pass of size `view` the layer is drawn in.
+    ///
+    /// Everything is drawn by increasing z index, and at the same z index
+    /// the immediate shapes first, then the meshes and the texts
     #[inline]
-    pub fn present<'a>(
-        &'a mut self,
+    pub(crate) fn present(
+        &mut self,
         view: Size<u32>,
-        render_pass: &mut wgpu::RenderPass<'a>,
-        assets: &AssetServerGuard<'_>,
+        viewport: (Vector2, Size<f32>),
+        render_pass: &mut wgpu::RenderPass<'_>,
+        frame: &LayerFrame<'_, '_>,
+        timer: &mut GpuTimer,
+        section: Section,
     ) {
         self.camera.update(view);
+        self.lights.update(self.camera.eye());
+
+        self.immediate.prepare(&self.camera, viewport, view);
+        self.retained.prepare(&self.camera, frame.assets);
+        self.text.prepare(frame.assets, frame.delta);
+
+        let mut z_indices: Vec<i32> = self
+            .immediate
+            .z_indices()
+            .chain(self.retained.z_indices())
+            .chain(self.text.z_indices())
+            .collect();
+
+        z_indices.sort_unstable();
+        z_indices.dedup();
+
+        let layer = timer.begin(render_pass, section);
+
+        for z_index in z_indices {
+            if self.immediate.z_indices().any(|z| z == z_index) {
+                let token = timer.begin(render_pass, Section::Immediate);
+                self.immediate.draw(render_pass, z_index, view, frame);
+                timer.end(render_pass, token);
+            }
+
+            if self.retained.z_indices().any(|z| z == z_index) {
+                let token = timer.begin(render_pass, Section::Retained);
+                self.retained
+                    .draw(render_pass, z_index, &self.lights, frame);
+                timer.end(render_pass, token);
+            }
+
+            if self.text.z_indices().any(|z| z == z_index) {
+                let token = timer.begin(render_pass, Section::Text);
+                self.text.draw(render_pass, z_index, frame.assets);
+                timer.end(render_pass, token);
+            }
+        }
+
+        timer.end(render_pass, layer);
 
-        self.immediate.present(render_pass);
-        self.retained.present(render_pass, assets);
-        self.text.present(render_pass, assets);
+        self.immediate.finish();
     }
 }
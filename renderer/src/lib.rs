@@ -1,29 +1,59 @@
 mod camera;
 mod color;
+mod depth;
 mod immediate;
 mod layer;
+mod light;
+mod light_2d;
+mod pixel;
+mod post;
 mod retained;
 mod shader;
+mod target;
+mod tessellate;
+mod text_layout;
+mod timing;
 mod traits;
 mod vertex;
 
 use assets::AssetServerGuard;
+use globals::{profile_scope, profiling};
 use logging::info;
 use macros::{Get, Set};
-use math::Size;
-use std::sync::{Arc, OnceLock};
+use math::{Size, Vector2};
+use std::{
+    sync::{Arc, OnceLock},
+    time::Instant,
+};
+use utils::{Handle, SlotMap};
 use winit::window::Window;
 
 // === RE-EXPORTS ===
-use crate::shader::Shader;
-pub use camera::{Camera, Projection};
+use crate::{
+    depth::DepthBuffer,
+    layer::LayerFrame,
+    light_2d::Lighting2d,
+    pixel::PixelPerfect,
+    shader::Shader,
+    timing::{GpuTimer, Section},
+};
+pub use camera::{Camera, Projection, ScaleMode};
 pub use color::Color;
-pub use immediate::Draw;
+pub use immediate::{Draw, Gradient, ImageOptions};
 pub use layer::{Layer, RenderLayer};
+pub use light::{DirectionalLight, PointLight};
+pub use light_2d::{Light2d, Occluder};
+pub use post::{
+    Bloom, ChromaticAberration, ColorGrading, Crt, CustomEffect, Effect, PostProcess, Vignette,
+};
 pub use retained::{
-    Scene, SceneView, Text,
-    mesh::{Geometry, Material, Mesh, TextureKind, Transform3d},
+    GlyphEffect, Scene, SceneView, Text,
+    mesh::{AlphaMode, Geometry, Material, Mesh, Shading, TextureKind, Transform3d},
 };
+pub use target::{ImageSource, RenderTarget, RenderTargetConfig};
+pub use tessellate::{LineCap, LineJoin, Stroke};
+pub use text_layout::{HorizontalAlign, TextBounds, TextLayout, VerticalAlign, WrapStyle};
+pub use vertex::Vertex;
 
 /// FIXME: Try to find a better solution to this shit
 #[derive(Debug)]
@@ -32,6 +62,9 @@ struct Shaders {
     text: Shader,
     immediate: Shader,
     immediate_circle: Shader,
+    post: Shader,
+    light_2d: Shader,
+    light_2d_composite: Shader,
 }
 
 static SHADERS: OnceLock<Shaders> = OnceLock::new();
@@ -52,6 +85,18 @@ pub(crate) fn immediate_circle_shader() -> &'static Shader {
     &SHADERS.get().unwrap().immediate_circle
 }
 
+pub(crate) fn post_shader() -> &'static Shader {
+    &SHADERS.get().unwrap().post
+}
+
+pub(crate) fn light_2d_shader() -> &'static Shader {
+    &SHADERS.get().unwrap().light_2d
+}
+
+pub(crate) fn light_2d_composite_shader() -> &'static Shader {
+    &SHADERS.get().unwrap().light_2d_composite
+}
+
 pub fn init() {
     let retained_shader = Shader::from_wgsl_file(
         include_str!("../../shaders/basic_2d.wgsl"),
@@ -71,12 +116,33 @@ pub fn init() {
         Some("Immediate Circle shader"),
     );
 
+    let post_shader = Shader::from_wgsl_file(
+        concat!(
+            include_str!("../../shaders/post_common.wgsl"),
+            include_str!("../../shaders/post.wgsl")
+        ),
+        Some("Post Process shader"),
+    );
+
+    let light_2d_shader = Shader::from_wgsl_file(
+        include_str!("../../shaders/light_2d.wgsl"),
+        Some("Light 2D shader"),
+    );
+
+    let light_2d_composite_shader = Shader::from_wgsl_file(
+        include_str!("../../shaders/light_2d_composite.wgsl"),
+        Some("Light 2D Composite shader"),
+    );
+
     SHADERS
         .set(Shaders {
             retained: retained_shader,
             text: text_shader,
             immediate: immediate_shader,
             immediate_circle: immediate_circle_shader,
+            post: post_shader,
+            light_2d: light_2d_shader,
+            light_2d_composite: light_2d_composite_shader,
         })
         .unwrap();
 
@@ -94,9 +160,24 @@ pub struct Renderer {
     world: RenderLayer,
     ui: RenderLayer,
     user_layers: Vec<RenderLayer>,
+    targets: SlotMap<RenderTarget>,
+    /// Rendered in creation order, apart from the targets
+    /// so that a layer can sample the other ones
+    target_layers: Vec<(Handle<RenderTarget>, RenderLayer)>,
+    /// Layout of the atlas, which the targets share to be sampled the same way
+    texture_bgl: wgpu::BindGroupLayout,
     active_layer: Layer,
     /// Cached viewport size
     view: Size<u32>,
+    timer: GpuTimer,
+    post: PostProcess,
+    /// Set with a virtual resolution
+    pixel_perfect: Option<PixelPerfect>,
+    /// Lights and shadows of the World layer
+    lighting: Lighting2d,
+    /// Shared by the passes drawn at the size of the window,
+    /// targets and the virtual resolution have their own
+    depth: Option<DepthBuffer>,
 }
 
 impl Renderer {
@@ -166,8 +247,15 @@ impl Renderer {
             far: 1.0,
         });
 
-        let world = RenderLayer::new(&surface_config, assets, world_camera);
-        let ui = RenderLayer::new(&surface_config, assets, ui_camera);
+        let texture_bgl = assets.atlas_bgl().clone();
+        let lighting = Lighting2d::new(surface_config.format, &world_camera, &texture_bgl, None);
+        let world = RenderLayer::new(surface_config.format, &texture_bgl, world_camera, None);
+        let mut ui = RenderLayer::new(surface_config.format, &texture_bgl, ui_camera, None);
+
+        // The interface stays readable whatever the effects
+        ui.post_processed = false;
+
+        let post = PostProcess::new(surface_config.format, &texture_bgl);
 
         Self {
             surface,
@@ -176,8 +264,16 @@ impl Renderer {
             world,
             ui,
             user_layers: Vec::new(),
+            targets: SlotMap::new(),
+            target_layers: Vec::new(),
+            texture_bgl,
             active_layer: Layer::default(),
             view,
+            timer: GpuTimer::new(),
+            post,
+            pixel_perfect: None,
+            lighting,
+            depth: None,
         }
     }
 
@@ -194,6 +290,49 @@ impl Renderer {
         self.config.height = view.height;
         self.surface.configure(gpu::device(), &self.config);
         self.view = view;
+
+        if let Some(depth) = &mut self.depth {
+            *depth = DepthBuffer::new(view);
+        }
+    }
+
+    /// Depth tests the retained meshes of every layer, layers drawn later
+    /// still cover the earlier ones. Off by default, 2D is drawn in order
+    #[doc(hidden)]
+    pub fn set_depth_buffer(&mut self, enabled: bool) {
+        if enabled == self.depth.is_some() {
+            return;
+        }
+
+        self.depth = enabled.then(|| DepthBuffer::new(self.view));
+
+        let depth = enabled.then_some(DepthBuffer::FORMAT);
+        let (format, texture_bgl) = (self.config.format, &self.texture_bgl);
+
+        self.world.set_depth(format, texture_bgl, depth);
+        self.ui.set_depth(format, texture_bgl, depth);
+        self.user_layers
+            .iter_mut()
+            .for_each(|layer| layer.set_depth(format, texture_bgl, depth));
+
+        for (handle, layer) in &mut self.target_layers {
+            if let Some(target) = self.targets.get_mut(*handle) {
+                target.set_depth(enabled);
+                layer.set_depth(target.format(), texture_bgl, depth);
+            }
+        }
+
+        if let Some(pixel) = &mut self.pixel_perfect {
+            pixel.set_depth(depth);
+        }
+
+        self.lighting.set_depth(depth);
+    }
+
+    #[inline]
+    #[doc(hidden)]
+    pub fn depth_buffer(&self) -> bool {
+        self.depth.is_some()
     }
 
     #[inline]
@@ -202,21 +341,117 @@ impl Renderer {
             Layer::World => &self.world,
             Layer::Ui => &self.ui,
             Layer::Custom(i) => &self.user_layers[i],
+            Layer::Target(target) => self.target_layer(target),
         }
     }
 
+    #[inline]
+    fn target_layer(&self, target: Handle<RenderTarget>) -> &RenderLayer {
+        self.target_layers
+            .iter()
+            .find_map(|(handle, layer)| (*handle == target).then_some(layer))
+            .expect("Invalid render target handle")
+    }
+
     #[inline]
     fn layer_mut(&mut self, id: Layer) -> &mut RenderLayer {
         match id {
             Layer::World => &mut self.world,
             Layer::Ui => &mut self.ui,
             Layer::Custom(i) => &mut self.user_layers[i],
+            Layer::Target(target) => self
+                .target_layers
+                .iter_mut()
+                .find_map(|(handle, layer)| (*handle == target).then_some(layer))
+                .expect("Invalid render target handle"),
         }
     }
 
+    /// Renders the World layer at a fixed resolution, upscaled by a whole factor
+    /// and letterboxed in the window, or at the window's resolution with `None`
+    #[doc(hidden)]
+    pub fn set_virtual_resolution(&mut self, size: Option<Size<u32>>) {
+        self.pixel_perfect = size.map(|size| {
+            PixelPerfect::new(
+                Size::new(size.width.max(1), size.height.max(1)),
+                self.config.format,
+                &self.texture_bgl,
+                self.depth.is_some().then_some(DepthBuffer::FORMAT),
+            )
+        });
+
+        self.world.queue_resize();
+    }
+
     #[inline]
     #[doc(hidden)]
-    pub fn present(&mut self, assets: &AssetServerGuard<'_>) {
+    pub fn virtual_resolution(&self) -> Option<Size<u32>> {
+        self.pixel_perfect.as_ref().map(PixelPerfect::size)
+    }
+
+    /// Converts a position in the window to the virtual resolution,
+    /// returns it as is without one
+    #[inline]
+    #[doc(hidden)]
+    pub fn to_virtual(&self, position: Vector2) -> Vector2 {
+        match &self.pixel_perfect {
+            Some(pixel) => pixel.to_virtual(self.view, position),
+            None => position,
+        }
+    }
+
+    pub(crate) fn create_target(&mut self, config: RenderTargetConfig) -> Handle<RenderTarget> {
+        let format = config.format().unwrap_or(self.config.format);
+        let depth = self.depth.is_some();
+        let target = RenderTarget::new(&config, format, &self.texture_bgl, depth);
+        let camera = Camera::new(Projection::standard_2d(target.size()));
+        let depth = depth.then_some(DepthBuffer::FORMAT);
+        let mut layer = RenderLayer::new(format, &self.texture_bgl, camera, depth);
+        let handle = self.targets.insert(target);
+
+        // The projection is only written once dirty
+        layer.queue_resize();
+
+        self.target_layers.push((handle, layer));
+
+        handle
+    }
+
+    /// Recreates the texture of the target, what was drawn with it
+    /// keeps sampling the whole of the new one
+    pub(crate) fn resize_target(&mut self, target: Handle<RenderTarget>, size: Size<u32>) {
+        let Some(current) = self.targets.get_mut(target) else {
+            return;
+        };
+
+        let config = RenderTargetConfig::new(size.width, size.height)
+            .with_format(current.format())
+            .with_filter(current.filter())
+            .with_clear_color(current.clear_color());
+
+        let depth = self.depth.is_some();
+        *current = RenderTarget::new(&config, current.format(), &self.texture_bgl, depth);
+
+        if let Some((_, layer)) = self.target_layers.iter_mut().find(|(h, _)| *h == target) {
+            layer.queue_resize();
+        }
+    }
+
+    pub(crate) fn remove_target(&mut self, target: Handle<RenderTarget>) {
+        self.targets.remove(target);
+        self.target_layers.retain(|(handle, _)| *handle != target);
+
+        if matches!(self.active_layer, Layer::Target(active) if active == target) {
+            self.active_layer = Layer::World;
+        }
+    }
+
+    #[inline]
+    #[doc(hidden)]
+    pub fn present(&mut self, assets: &AssetServerGuard<'_>, delta: f32) {
+        profile_scope!("Renderer::present");
+
+        let start = Instant::now();
         let gpu = gpu::get();
         let output = self.surface.get_current_texture().expect("Ouch");
         let view = output
@@ -229,11 +464,79 @@ impl Renderer {
                 label: Some("Render Encoder"),
             });
 
-        {
+        self.timer.collect();
+        self.timer.begin_frame();
+
+        let frame = LayerFrame {
+            assets,
+            targets: &self.targets,
+            delta,
+            rendering: None,
+        };
+
+        let timer = &mut self.timer;
+
+        // Targets first, so that the window can sample them this frame
+        for (handle, layer) in &mut self.target_layers {
+            let Some(target) = frame.targets.get(*handle) else {
+                continue;
+            };
+
+            let frame = LayerFrame {
+                rendering: Some(*handle),
+                ..frame
+            };
+
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
+                label: Some("Render Target Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view: target.view(),
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(target.clear_color().into()),
+                        store: wgpu::StoreOp::Store,
+                    },
+                    depth_slice: None,
+                })],
+                depth_stencil_attachment: target.depth_attachment(),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+                multiview_mask: None,
+            });
+
+            render_pass.set_bind_group(0, layer.camera.bg(), &[]);
+            render_pass.set_bind_group(1, assets.atlas_bg(), &[]);
+
+            layer.present(
+                target.size(),
+                (Vector2::zeros(), target.size().to_f32()),
+                &mut render_pass,
+                &frame,
+                timer,
+                Section::Target,
+            );
+        }
+
+        // The light map is drawn with the pass of the World layer, and the same viewport
+        if self.lighting.is_active() {
+            let camera = &self.world.camera;
+            let (size, viewport) = match &self.pixel_perfect {
+                Some(pixel) => (pixel.size(), None),
+                None => (self.view, camera.scale_mode().viewport(self.view)),
+            };
+
+            let viewport = viewport.unwrap_or((Vector2::zeros(), size.to_f32()));
+
+            self.lighting.render(&mut encoder, camera, size, viewport);
+        }
+
+        let lighting = self.lighting.is_active().then_some(&self.lighting);
+
+        if let Some(pixel) = &self.pixel_perfect {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Pixel Perfect Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: pixel.view(),
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(self.clear_color.into()),
@@ -241,7 +544,7 @@ impl Renderer {
                     },
                     depth_slice: None,
                 })],
-                depth_stencil_attachment: None,
+                depth_stencil_attachment: pixel.depth_attachment(),
                 timestamp_writes: None,
                 occlusion_query_set: None,
                 multiview_mask: None,
@@ -250,19 +553,174 @@ impl Renderer {
             render_pass.set_bind_group(0, self.world.camera.bg(), &[]);
             render_pass.set_bind_group(1, assets.atlas_bg(), &[]);
 
-            self.world.present(self.view, &mut render_pass, assets);
+            self.world.present(
+                pixel.size(),
+                (Vector2::zeros(), pixel.size().to_f32()),
+                &mut render_pass,
+                &frame,
+                timer,
+                Section::World,
+            );
+
+            if let Some(lighting) = lighting {
+                lighting.composite(&mut render_pass);
+            }
+        }
+
+        // Letterboxed around the pixel perfect World layer
+        let clear_color = match self.pixel_perfect {
+            Some(_) => Color::Black,
+            None => self.clear_color,
+        };
+
+        // Post-processed layers are rendered offscreen, then the effects write
+        // to the window, and the other layers are drawn on top of them
+        // Preparing can disable a custom effect that fails to build
+        let offscreen = match self.post.is_active() {
+            true => self.post.prepare(self.view, delta, assets),
+            false => None,
+        };
+        let post = offscreen.is_some();
+        let main_view = offscreen.unwrap_or(&view);
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: main_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(clear_color.into()),
+                        store: wgpu::StoreOp::Store,
+                    },
+                    depth_slice: None,
+                })],
+                depth_stencil_attachment: self.depth.as_ref().map(DepthBuffer::attachment),
+                timestamp_writes: timer.frame_writes(true, !post),
+                occlusion_query_set: None,
+                multiview_mask: None,
+            });
+
+            let layers = screen_layers(&mut self.world, &mut self.ui, &mut self.user_layers)
+                .filter(|(layer, _)| !post || layer.post_processed);
+
+            let pixel = self.pixel_perfect.as_ref();
 
-            render_pass.set_bind_group(0, self.ui.camera.bg(), &[]);
+            present_layers(
+                layers,
+                self.view,
+                &mut render_pass,
+                &frame,
+                timer,
+                pixel,
+                lighting,
+            );
+        }
 
-            self.ui.present(self.view, &mut render_pass, assets);
+        if post {
+            self.post.apply(&mut encoder, &view, assets, timer);
 
-            self.user_layers.iter_mut().for_each(|l| {
-                render_pass.set_bind_group(0, l.camera.bg(), &[]);
-                l.present(self.view, &mut render_pass, assets);
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Overlay Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                    depth_slice: None,
+                })],
+                depth_stencil_attachment: self.depth.as_ref().map(DepthBuffer::attachment),
+                timestamp_writes: timer.frame_writes(false, true),
+                occlusion_query_set: None,
+                multiview_mask: None,
             });
+
+            let layers = screen_layers(&mut self.world, &mut self.ui, &mut self.user_layers)
+                .filter(|(layer, _)| !layer.post_processed);
+
+            let pixel = self.pixel_perfect.as_ref();
+
+            present_layers(
+                layers,
+                self.view,
+                &mut render_pass,
+                &frame,
+                timer,
+                pixel,
+                lighting,
+            );
         }
 
+        self.timer.resolve(&mut encoder);
         gpu.queue().submit([encoder.finish()]);
+        self.timer.after_submit();
         output.present();
+
+        profiling::record_present_time(start.elapsed().as_secs_f32() * 1000.0);
+    }
+}
+
+/// Layers rendered in the window, in order
+fn screen_layers<'a>(
+    world: &'a mut RenderLayer,
+    ui: &'a mut RenderLayer,
+    user_layers: &'a mut [RenderLayer],
+) -> impl Iterator<Item = (&'a mut RenderLayer, Section)> {
+    [(world, Section::World), (ui, Section::Ui)]
+        .into_iter()
+        .chain(user_layers.iter_mut().map(|layer| (layer, Section::Custom)))
+}
+
+/// With a virtual resolution, the World layer was already rendered
+/// and is only composited. The 2D lights are multiplied over the World layer
+fn present_layers<'a, I>(
+    layers: I,
+    view: Size<u32>,
+    render_pass: &mut wgpu::RenderPass<'_>,
+    frame: &LayerFrame<'_, '_>,
+    timer: &mut GpuTimer,
+    pixel: Option<&PixelPerfect>,
+    lighting: Option<&Lighting2d>,
+) where
+    I: Iterator<Item = (&'a mut RenderLayer, Section)>,
+{
+    let layers: Vec<_> = layers.collect();
+    let count = layers.len() as f32;
+
+    for (i, (layer, section)) in layers.into_iter().enumerate() {
+        if let (Section::World, Some(pixel)) = (section, pixel) {
+            pixel.present(view, render_pass);
+            continue;
+        }
+
+        // Later layers get a closer slice of the depth range, so that they stay on top
+        let max_depth = 1.0 - i as f32 / count;
+        let min_depth = max_depth - 1.0 / count;
+
+        let viewport = layer
+            .camera
+            .scale_mode()
+            .viewport(view)
+            .unwrap_or((Vector2::zeros(), view.to_f32()));
+        let (offset, size) = viewport;
+
+        render_pass.set_viewport(
+            offset.x,
+            offset.y,
+            size.width,
+            size.height,
+            min_depth,
+            max_depth,
+        );
+
+        render_pass.set_bind_group(0, layer.camera.bg(), &[]);
+        render_pass.set_bind_group(1, frame.assets.atlas_bg(), &[]);
+        layer.present(view, viewport, render_pass, frame, timer, section);
+
+        if let (Section::World, Some(lighting)) = (section, lighting) {
+            lighting.composite(render_pass);
+        }
     }
 }
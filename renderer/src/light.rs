@@ -0,0 +1,274 @@
+use crate::color::Color;
+use globals::consts;
+use gpu::core::{GpuBuffer, GpuBufferBuilder};
+use macros::With;
+use math::{Vector3, Vector4};
+use utils::{Handle, SlotMap};
+
+/// Light shining the same way everywhere, like the sun
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(With)]
+pub struct DirectionalLight {
+    /// Where the light goes, doesn't have to be normalized
+    #[with(into)]
+    pub direction: Vector3,
+
+    #[with(into)]
+    pub color: Color,
+
+    #[with]
+    pub intensity: f32,
+}
+
+impl DirectionalLight {
+    pub fn new<V: Into<Vector3>>(direction: V) -> Self {
+        Self {
+            direction: direction.into(),
+            color: Color::White,
+            intensity: 1.0,
+        }
+    }
+}
+
+/// Light shining all around a position, fading out until `range`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(With)]
+pub struct PointLight {
+    #[with(into)]
+    pub position: Vector3,
+
+    #[with(into)]
+    pub color: Color,
+
+    #[with]
+    pub intensity: f32,
+
+    /// Distance at which the light has faded out completely
+    #[with]
+    pub range: f32,
+}
+
+impl PointLight {
+    pub fn new<V: Into<Vector3>>(position: V, range: f32) -> Self {
+        Self {
+            position: position.into(),
+            color: Color::White,
+            intensity: 1.0,
+            range,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Default)]
+#[derive(Debug, Clone, Copy)]
+struct DirectionalLightGpu {
+    direction: Vector4,
+    color: Vector4,
+}
+
+#[repr(C)]
+#[derive(Default)]
+#[derive(Debug, Clone, Copy)]
+struct PointLightGpu {
+    /// Range in w
+    position: Vector4,
+    color: Vector4,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct LightsGpu {
+    ambient: Vector4,
+    /// Position of the camera, or the direction towards it with a w of 0
+    camera_position: Vector4,
+    /// Directional and point lights, as u32
+    counts: [u32; 4],
+    directional: [DirectionalLightGpu; consts::MAX_DIRECTIONAL_LIGHTS],
+    point: [PointLightGpu; consts::MAX_POINT_LIGHTS],
+}
+
+/// Lights of a layer, only affecting the meshes with a lit [`crate::Shading`]
+pub(crate) struct Lights {
+    directional: SlotMap<DirectionalLight>,
+    point: SlotMap<PointLight>,
+    ambient: Color,
+
+    buffer: GpuBuffer<LightsGpu>,
+    bgl: wgpu::BindGroupLayout,
+    bg: wgpu::BindGroup,
+    /// Written again when a light changes or the camera moves
+    dirty: bool,
+    eye: Vector4,
+}
+
+impl Lights {
+    pub(crate) fn new() -> Self {
+        let device = gpu::device();
+        let buffer = GpuBufferBuilder::new()
+            .label("Lights Buffer")
+            .uniform()
+            .copy_dst()
+            .build();
+
+        let bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Lights Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Lights Bind Group"),
+            layout: &bgl,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.inner().as_entire_binding(),
+            }],
+        });
+
+        Self {
+            directional: SlotMap::new(),
+            point: SlotMap::new(),
+            ambient: Color::rgb(0.1, 0.1, 0.1),
+            buffer,
+            bgl,
+            bg,
+            dirty: true,
+            eye: Vector4::zeros(),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn bgl(&self) -> &wgpu::BindGroupLayout {
+        &self.bgl
+    }
+
+    #[inline]
+    pub(crate) fn bg(&self) -> &wgpu::BindGroup {
+        &self.bg
+    }
+
+    #[inline]
+    pub(crate) fn ambient(&self) -> Color {
+        self.ambient
+    }
+
+    #[inline]
+    pub(crate) fn set_ambient(&mut self, color: Color) {
+        self.ambient = color;
+        self.dirty = true;
+    }
+
+    #[inline]
+    pub(crate) fn add_directional(&mut self, light: DirectionalLight) -> Handle<DirectionalLight> {
+        self.dirty = true;
+        self.directional.insert(light)
+    }
+
+    #[inline]
+    pub(crate) fn directional(
+        &self,
+        handle: Handle<DirectionalLight>,
+    ) -> Option<&DirectionalLight> {
+        self.directional.get(handle)
+    }
+
+    #[inline]
+    pub(crate) fn directional_mut(
+        &mut self,
+        handle: Handle<DirectionalLight>,
+    ) -> Option<&mut DirectionalLight> {
+        self.dirty = true;
+        self.directional.get_mut(handle)
+    }
+
+    #[inline]
+    pub(crate) fn remove_directional(&mut self, handle: Handle<DirectionalLight>) {
+        self.dirty = true;
+        self.directional.remove(handle);
+    }
+
+    #[inline]
+    pub(crate) fn add_point(&mut self, light: PointLight) -> Handle<PointLight> {
+        self.dirty = true;
+        self.point.insert(light)
+    }
+
+    #[inline]
+    pub(crate) fn point(&self, handle: Handle<PointLight>) -> Option<&PointLight> {
+        self.point.get(handle)
+    }
+
+    #[inline]
+    pub(crate) fn point_mut(&mut self, handle: Handle<PointLight>) -> Option<&mut PointLight> {
+        self.dirty = true;
+        self.point.get_mut(handle)
+    }
+
+    #[inline]
+    pub(crate) fn remove_point(&mut self, handle: Handle<PointLight>) {
+        self.dirty = true;
+        self.point.remove(handle);
+    }
+
+    /// Uploads the lights, the eye of the camera is needed for the specular highlights
+    pub(crate) fn update(&mut self, eye: Vector4) {
+        if !self.dirty && self.eye == eye {
+            return;
+        }
+
+        let mut gpu = LightsGpu {
+            ambient: self.ambient.into(),
+            camera_position: eye,
+            counts: [0; 4],
+            directional: Default::default(),
+            point: Default::default(),
+        };
+
+        let directional = self
+            .directional
+            .iter()
+            .map(|(_, light)| light)
+            .take(consts::MAX_DIRECTIONAL_LIGHTS);
+
+        for (slot, light) in gpu.directional.iter_mut().zip(directional) {
+            let color = Vector4::from(light.color) * light.intensity;
+
+            *slot = DirectionalLightGpu {
+                direction: light.direction.normalized().extend(0.0),
+                color,
+            };
+
+            gpu.counts[0] += 1;
+        }
+
+        let point = self
+            .point
+            .iter()
+            .map(|(_, light)| light)
+            .take(consts::MAX_POINT_LIGHTS);
+
+        for (slot, light) in gpu.point.iter_mut().zip(point) {
+            let color = Vector4::from(light.color) * light.intensity;
+
+            *slot = PointLightGpu {
+                position: light.position.extend(light.range.max(f32::EPSILON)),
+                color,
+            };
+
+            gpu.counts[1] += 1;
+        }
+
+        self.buffer.write(0, &[gpu]);
+        self.dirty = false;
+        self.eye = eye;
+    }
+}
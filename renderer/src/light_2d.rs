@@ -0,0 +1,518 @@
+use crate::{
+    camera::Camera,
+    color::Color,
+    light_2d_composite_shader, light_2d_shader,
+    target::{RenderTarget, RenderTargetConfig},
+    traits::LayoutDescriptor,
+};
+use globals::profiling;
+use gpu::core::{GpuBuffer, GpuBufferBuilder};
+use macros::With;
+use math::{Size, Vector2, Vector4};
+use std::{f32::consts::TAU, mem};
+use utils::{Handle, SlotMap};
+
+/// Format of the light map, lights can add up above 1 to brighten the World layer
+const LIGHT_MAP_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Light of the World layer, in world units. A point light by default,
+/// or a spot light with an `angle` smaller than a full turn
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(With)]
+pub struct Light2d {
+    #[with(into)]
+    pub position: Vector2,
+
+    #[with(into)]
+    pub color: Color,
+
+    #[with]
+    pub intensity: f32,
+
+    /// Distance at which the light has faded out completely
+    #[with]
+    pub radius: f32,
+
+    /// Exponent of the fade, 1 is linear and greater values fade out faster
+    #[with]
+    pub falloff: f32,
+
+    /// Where a spot light points, in radians
+    #[with]
+    pub direction: f32,
+
+    /// Width of the cone of a spot light, in radians
+    #[with]
+    pub angle: f32,
+
+    /// Size of the light source, the greater the softer the edges of the shadows
+    #[with]
+    pub softness: f32,
+
+    #[with]
+    pub shadows: bool,
+}
+
+impl Light2d {
+    pub fn point<V: Into<Vector2>>(position: V, radius: f32) -> Self {
+        Self {
+            position: position.into(),
+            color: Color::White,
+            intensity: 1.0,
+            radius,
+            falloff: 2.0,
+            direction: 0.0,
+            angle: TAU,
+            softness: 8.0,
+            shadows: true,
+        }
+    }
+
+    pub fn spot<V: Into<Vector2>>(position: V, radius: f32, direction: f32, angle: f32) -> Self {
+        Self {
+            direction,
+            angle,
+            ..Self::point(position, radius)
+        }
+    }
+}
+
+/// Shape blocking the 2D lights, its edges cast shadows
+#[derive(Debug, Clone, PartialEq)]
+pub struct Occluder {
+    /// Corners of the polygon, in world units
+    pub points: Vec<Vector2>,
+}
+
+impl Occluder {
+    pub fn polygon<I, V>(points: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Vector2>,
+    {
+        Self {
+            points: points.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn rect<V: Into<Vector2>>(position: V, width: f32, height: f32) -> Self {
+        let position = position.into();
+
+        Self::polygon([
+            position,
+            position + Vector2::new(width, 0.0),
+            position + Vector2::new(width, height),
+            position + Vector2::new(0.0, height),
+        ])
+    }
+
+    /// Edges of the polygon, as start and end points.
+    /// Two points are a single edge, and the polygon is closed with more
+    fn segments(&self) -> impl Iterator<Item = Vector4> + '_ {
+        let len = self.points.len();
+        let count = match len {
+            0 | 1 => 0,
+            2 => 1,
+            _ => len,
+        };
+
+        (0..count).map(move |i| {
+            let (a, b) = (self.points[i], self.points[(i + 1) % len]);
+
+            Vector4::new(a.x, a.y, b.x, b.y)
+        })
+    }
+}
+
+#[repr(C)]
+#[derive(Default)]
+#[derive(Debug, Clone, Copy)]
+struct Light2dGpu {
+    /// Radius in z, falloff in w
+    position: Vector4,
+    color: Vector4,
+    /// Direction, cosine of the half angle, softness, shadows
+    params: Vector4,
+}
+
+impl From<&Light2d> for Light2dGpu {
+    fn from(light: &Light2d) -> Self {
+        // -1 is a full turn, the cone is skipped
+        let cone = match light.angle < TAU {
+            true => (light.angle.max(0.0) / 2.0).cos(),
+            false => -1.0,
+        };
+
+        Self {
+            position: Vector4::new(
+                light.position.x,
+                light.position.y,
+                light.radius.max(f32::EPSILON),
+                light.falloff,
+            ),
+            color: Vector4::from(light.color) * light.intensity,
+            params: Vector4::new(
+                light.direction,
+                cone,
+                light.softness,
+                if light.shadows { 1.0 } else { 0.0 },
+            ),
+        }
+    }
+}
+
+impl LayoutDescriptor for Light2dGpu {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<Vector4>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: (mem::size_of::<Vector4>() * 2) as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// 2D lights and occluders of the World layer.
+///
+/// The lights are added up into a light map cleared with the ambient color,
+/// which the World layer is then multiplied by. Without any light,
+/// the World layer is drawn as is
+pub(crate) struct Lighting2d {
+    lights: SlotMap<Light2d>,
+    occluders: SlotMap<Occluder>,
+    ambient: Color,
+
+    light_buffer: GpuBuffer<Light2dGpu>,
+    segment_buffer: GpuBuffer<Vector4>,
+    /// Number of segments, as u32
+    uniforms: GpuBuffer<[u32; 4]>,
+    bgl: wgpu::BindGroupLayout,
+    bg: wgpu::BindGroup,
+
+    light_pipeline: wgpu::RenderPipeline,
+    composite_pipeline: wgpu::RenderPipeline,
+    texture_bgl: wgpu::BindGroupLayout,
+    format: wgpu::TextureFormat,
+    light_map: Option<RenderTarget>,
+    /// The occluders changed since the segments were uploaded
+    dirty: bool,
+}
+
+impl Lighting2d {
+    pub(crate) fn new(
+        format: wgpu::TextureFormat,
+        camera: &Camera,
+        texture_bgl: &wgpu::BindGroupLayout,
+        depth: Option<wgpu::TextureFormat>,
+    ) -> Self {
+        let device = gpu::device();
+
+        let light_buffer = GpuBufferBuilder::new()
+            .label("Light 2D Instance Buffer")
+            .vertex()
+            .copy_dst()
+            .capacity(64)
+            .build();
+
+        let segment_buffer = GpuBufferBuilder::new()
+            .label("Occluder Segment Buffer")
+            .storage()
+            .copy_dst()
+            .capacity(256)
+            .build();
+
+        let uniforms = GpuBufferBuilder::new()
+            .label("Lighting 2D Uniform Buffer")
+            .uniform()
+            .copy_dst()
+            .build();
+
+        let bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Lighting 2D Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let bg = Self::bind_group(&bgl, &uniforms, &segment_buffer);
+
+        let light_pipeline = light_2d_shader()
+            .pipeline_builder()
+            .label("Light 2D Pipeline")
+            .vertex_entry("vs_main")
+            .fragment_entry("fs_main")
+            .blend_state(Some(wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent::REPLACE,
+            }))
+            .build(
+                LIGHT_MAP_FORMAT,
+                &[camera.bgl(), &bgl],
+                &[Light2dGpu::desc()],
+            );
+
+        Self {
+            lights: SlotMap::new(),
+            occluders: SlotMap::new(),
+            ambient: Color::rgb(0.1, 0.1, 0.15),
+            light_buffer,
+            segment_buffer,
+            uniforms,
+            bgl,
+            bg,
+            light_pipeline,
+            composite_pipeline: Self::composite_pipeline(format, texture_bgl, depth),
+            texture_bgl: texture_bgl.clone(),
+            format,
+            light_map: None,
+            dirty: true,
+        }
+    }
+
+    fn bind_group(
+        bgl: &wgpu::BindGroupLayout,
+        uniforms: &GpuBuffer<[u32; 4]>,
+        segments: &GpuBuffer<Vector4>,
+    ) -> wgpu::BindGroup {
+        gpu::device().create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Lighting 2D Bind Group"),
+            layout: bgl,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniforms.inner().as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: segments.inner().as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    /// Multiplies what is under it by the light map
+    fn composite_pipeline(
+        format: wgpu::TextureFormat,
+        texture_bgl: &wgpu::BindGroupLayout,
+        depth: Option<wgpu::TextureFormat>,
+    ) -> wgpu::RenderPipeline {
+        light_2d_composite_shader()
+            .pipeline_builder()
+            .label("Light 2D Composite Pipeline")
+            .vertex_entry("vs_main")
+            .fragment_entry("fs_main")
+            .blend_state(Some(wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Dst,
+                    dst_factor: wgpu::BlendFactor::Zero,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Zero,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+            }))
+            .depth_format(depth)
+            .depth_compare(wgpu::CompareFunction::Always)
+            .depth_write(false)
+            .build(format, &[texture_bgl], &[])
+    }
+
+    /// The composite pipeline must match the depth buffer of the pass of the World layer
+    pub(crate) fn set_depth(&mut self, depth: Option<wgpu::TextureFormat>) {
+        self.composite_pipeline = Self::composite_pipeline(self.format, &self.texture_bgl, depth);
+    }
+
+    #[inline]
+    pub(crate) fn is_active(&self) -> bool {
+        !self.lights.is_empty()
+    }
+
+    #[inline]
+    pub(crate) fn ambient(&self) -> Color {
+        self.ambient
+    }
+
+    #[inline]
+    pub(crate) fn set_ambient(&mut self, color: Color) {
+        self.ambient = color;
+    }
+
+    #[inline]
+    pub(crate) fn add_light(&mut self, light: Light2d) -> Handle<Light2d> {
+        self.lights.insert(light)
+    }
+
+    #[inline]
+    pub(crate) fn light(&self, handle: Handle<Light2d>) -> Option<&Light2d> {
+        self.lights.get(handle)
+    }
+
+    #[inline]
+    pub(crate) fn light_mut(&mut self, handle: Handle<Light2d>) -> Option<&mut Light2d> {
+        self.lights.get_mut(handle)
+    }
+
+    #[inline]
+    pub(crate) fn remove_light(&mut self, handle: Handle<Light2d>) {
+        self.lights.remove(handle);
+    }
+
+    #[inline]
+    pub(crate) fn add_occluder(&mut self, occluder: Occluder) -> Handle<Occluder> {
+        self.dirty = true;
+        self.occluders.insert(occluder)
+    }
+
+    #[inline]
+    pub(crate) fn occluder(&self, handle: Handle<Occluder>) -> Option<&Occluder> {
+        self.occluders.get(handle)
+    }
+
+    #[inline]
+    pub(crate) fn occluder_mut(&mut self, handle: Handle<Occluder>) -> Option<&mut Occluder> {
+        self.dirty = true;
+        self.occluders.get_mut(handle)
+    }
+
+    #[inline]
+    pub(crate) fn remove_occluder(&mut self, handle: Handle<Occluder>) {
+        self.dirty = true;
+        self.occluders.remove(handle);
+    }
+
+    /// Uploads the occluders if they changed, and the lights which move every frame
+    fn upload(&mut self) {
+        if self.dirty {
+            let segments: Vec<Vector4> = self
+                .occluders
+                .iter()
+                .flat_map(|(_, occluder)| occluder.segments())
+                .collect();
+
+            let capacity = self.segment_buffer.capacity();
+
+            self.segment_buffer.write_all(&segments);
+
+            // A bigger buffer needs a new bind group
+            if self.segment_buffer.capacity() != capacity {
+                self.bg = Self::bind_group(&self.bgl, &self.uniforms, &self.segment_buffer);
+            }
+
+            self.uniforms.write(0, &[[segments.len() as u32, 0, 0, 0]]);
+            self.dirty = false;
+        }
+
+        let lights: Vec<Light2dGpu> = self.lights.iter().map(|(_, light)| light.into()).collect();
+
+        self.light_buffer.write_all(&lights);
+    }
+
+    /// Draws the lights into the light map, `view` being the size of the pass
+    /// of the World layer, and `viewport` its part of it
+    pub(crate) fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        camera: &Camera,
+        view: Size<u32>,
+        viewport: (Vector2, Size<f32>),
+    ) {
+        if self.light_map.as_ref().is_none_or(|map| map.size() != view) {
+            let config = RenderTargetConfig::new(view.width, view.height);
+            let map = RenderTarget::new(&config, LIGHT_MAP_FORMAT, &self.texture_bgl, false);
+
+            self.light_map = Some(map);
+        }
+
+        self.upload();
+
+        let Some(map) = &self.light_map else {
+            return;
+        };
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Light 2D Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: map.view(),
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(self.ambient.into()),
+                    store: wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+            multiview_mask: None,
+        });
+
+        let (offset, size) = viewport;
+
+        render_pass.set_viewport(offset.x, offset.y, size.width, size.height, 0.0, 1.0);
+        render_pass.set_pipeline(&self.light_pipeline);
+        profiling::record_pipeline_switches(1);
+
+        render_pass.set_bind_group(0, camera.bg(), &[]);
+        render_pass.set_bind_group(1, &self.bg, &[]);
+        render_pass.set_vertex_buffer(0, self.light_buffer.slice_all());
+
+        let count = self.lights.len() as u32;
+
+        render_pass.draw(0..6, 0..count);
+        profiling::record_draw_call(6 * count, 0);
+    }
+
+    /// Multiplies the World layer by the light map, with its viewport still set
+    pub(crate) fn composite(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        let Some(map) = &self.light_map else {
+            return;
+        };
+
+        render_pass.set_pipeline(&self.composite_pipeline);
+        profiling::record_pipeline_switches(1);
+
+        render_pass.set_bind_group(0, map.bind_group(), &[]);
+        render_pass.draw(0..3, 0..1);
+        profiling::record_draw_call(3, 0);
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{
+    post_shader,
+    target::{RenderTarget, RenderTargetConfig},
+};
+use globals::profiling;
+use math::{Size, Vector2};
+
+/// Renders the World layer at a fixed virtual resolution into a nearest-filtered
+/// target, upscaled by a whole factor and centered in the window
+pub(crate) struct PixelPerfect {
+    target: RenderTarget,
+    pipeline: wgpu::RenderPipeline,
+    texture_bgl: wgpu::BindGroupLayout,
+    format: wgpu::TextureFormat,
+}
+
+impl PixelPerfect {
+    pub(crate) fn new(
+        size: Size<u32>,
+        format: wgpu::TextureFormat,
+        texture_bgl: &wgpu::BindGroupLayout,
+        depth: Option<wgpu::TextureFormat>,
+    ) -> Self {
+        let config =
+            RenderTargetConfig::new(size.width, size.height).with_filter(wgpu::FilterMode::Nearest);
+
+        Self {
+            target: RenderTarget::new(&config, format, texture_bgl, depth.is_some()),
+            pipeline: Self::pipeline(format, texture_bgl, depth),
+            texture_bgl: texture_bgl.clone(),
+            format,
+        }
+    }
+
+    /// Drawn in the pass of the window, whatever its depth buffer holds
+    fn pipeline(
+        format: wgpu::TextureFormat,
+        texture_bgl: &wgpu::BindGroupLayout,
+        depth: Option<wgpu::TextureFormat>,
+    ) -> wgpu::RenderPipeline {
+        post_shader()
+            .pipeline_builder()
+            .label("Pixel Perfect Blit Pipeline")
+            .vertex_entry("vs_main")
+            .fragment_entry("fs_blit")
+            .blend_state(None)
+            .depth_format(depth)
+            .depth_compare(wgpu::CompareFunction::Always)
+            .depth_write(false)
+            .build(format, &[texture_bgl], &[])
+    }
+
+    #[inline]
+    pub(crate) fn size(&self) -> Size<u32> {
+        self.target.size()
+    }
+
+    #[inline]
+    pub(crate) fn view(&self) -> &wgpu::TextureView {
+        self.target.view()
+    }
+
+    /// Both the virtual target and the pass of the window get the depth buffer
+    pub(crate) fn set_depth(&mut self, depth: Option<wgpu::TextureFormat>) {
+        self.target.set_depth(depth.is_some());
+        self.pipeline = Self::pipeline(self.format, &self.texture_bgl, depth);
+    }
+
+    #[inline]
+    pub(crate) fn depth_attachment(&self) -> Option<wgpu::RenderPassDepthStencilAttachment<'_>> {
+        self.target.depth_attachment()
+    }
+
+    /// Scale of the virtual pixels, and where the top left one is in the window.
+    ///
+    /// The scale is a whole number, unless the window is smaller
+    /// than the virtual resolution, which is then shrunk to fit
+    pub(crate) fn viewport(&self, view: Size<u32>) -> (f32, Vector2) {
+        let size = self.size().to_f32();
+        let view = view.to_f32();
+        let fit = (view.width / size.width).min(view.height / size.height);
+
+        let scale = match fit >= 1.0 {
+            true => fit.floor(),
+            false => fit,
+        };
+
+        let offset = Vector2::new(
+            ((view.width - size.width * scale) / 2.0).floor(),
+            ((view.height - size.height * scale) / 2.0).floor(),
+        );
+
+        (scale, offset)
+    }
+
+    /// Converts a position in the window to the virtual resolution
+    #[inline]
+    pub(crate) fn to_virtual(&self, view: Size<u32>, position: Vector2) -> Vector2 {
+        let (scale, offset) = self.viewport(view);
+
+        (position - offset) / scale
+    }
+
+    /// Draws the upscaled World layer, the rest of the window is left as is
+    pub(crate) fn present(&self, view: Size<u32>, render_pass: &mut wgpu::RenderPass<'_>) {
+        let (scale, offset) = self.viewport(view);
+        let size = self.size().to_f32();
+
+        render_pass.set_viewport(
+            offset.x,
+            offset.y,
+            size.width * scale,
+            size.height * scale,
+            0.0,
+            1.0,
+        );
+
+        render_pass.set_pipeline(&self.pipeline);
+        profiling::record_pipeline_switches(1);
+
+        render_pass.set_bind_group(0, self.target.bind_group(), &[]);
+        render_pass.draw(0..3, 0..1);
+        profiling::record_draw_call(3, 0);
+
+        render_pass.set_viewport(0.0, 0.0, view.width as f32, view.height as f32, 0.0, 1.0);
+    }
+}
@@ -0,0 +1,609 @@
+use crate::{
+    color::Color,
+    post_shader,
+    shader::Shader,
+    target::{RenderTarget, RenderTargetConfig},
+    timing::{GpuTimer, Section},
+};
+use assets::{AssetServerGuard, Image};
+use globals::profiling;
+use gpu::core::{GpuBuffer, GpuBufferBuilder};
+use logging::{error, warn};
+use math::{Size, Vector2, Vector4};
+use std::sync::Arc;
+use utils::{Handle, Label};
+
+/// Prepended to the source of every [`CustomEffect`]
+const COMMON_SOURCE: &str = include_str!("../../shaders/post_common.wgsl");
+
+/// Appended to the source of every [`CustomEffect`]
+const CUSTOM_ENTRY: &str = "
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return effect(in.uv);
+}
+";
+
+#[repr(C)]
+#[derive(Default)]
+#[derive(Debug, Clone, Copy)]
+struct PostUniforms {
+    resolution: Vector2,
+    time: f32,
+    _padding: f32,
+    params: [Vector4; 4],
+}
+
+/// Adds a glow around the parts of the image brighter than `threshold`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bloom {
+    /// Brightness from which a color glows, between 0 and 1
+    pub threshold: f32,
+    pub intensity: f32,
+    /// Spread of the glow, in pixels between each sample
+    pub radius: f32,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Self {
+            threshold: 0.7,
+            intensity: 1.0,
+            radius: 2.0,
+        }
+    }
+}
+
+/// Darkens the edges of the screen towards `color`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vignette {
+    pub intensity: f32,
+    /// Distance from the center where the vignette starts, 1 being the corners
+    pub radius: f32,
+    pub softness: f32,
+    pub color: Color,
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Self {
+            intensity: 0.6,
+            radius: 0.5,
+            softness: 0.5,
+            color: Color::Black,
+        }
+    }
+}
+
+/// Remaps the colors through a lookup table.
+///
+/// The LUT is a horizontal strip of `N` squares of `N`x`N` pixels,
+/// red along x, green along y and blue across the squares, in sRGB.
+/// Its height gives `N`, the usual sizes being 16 and 32.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorGrading {
+    pub lut: Handle<Image>,
+    /// Blend between the original and the graded colors
+    pub intensity: f32,
+}
+
+impl ColorGrading {
+    pub fn new(lut: Handle<Image>) -> Self {
+        Self {
+            lut,
+            intensity: 1.0,
+        }
+    }
+}
+
+/// Curved screen, scanlines and darkened corners of an old monitor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Crt {
+    pub curvature: f32,
+    pub scanline_intensity: f32,
+    /// Scanlines over the height of the screen, `0` for one every two pixels
+    pub scanlines: f32,
+    pub vignette: f32,
+}
+
+impl Default for Crt {
+    fn default() -> Self {
+        Self {
+            curvature: 0.05,
+            scanline_intensity: 0.3,
+            scanlines: 0.0,
+            vignette: 0.5,
+        }
+    }
+}
+
+/// Splits the red and blue channels towards the edges of the screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChromaticAberration {
+    /// Offset of the channels at the edges, in pixels
+    pub offset: f32,
+}
+
+impl Default for ChromaticAberration {
+    fn default() -> Self {
+        Self { offset: 3.0 }
+    }
+}
+
+/// Effect written in WGSL.
+///
+/// The source must define `fn effect(uv: vec2<f32>) -> vec4<f32>`, which can use
+/// `sample_source(uv)`, `texel_size()` and the `post` uniforms
+/// (`resolution`, `time` and `params`, four `vec4<f32>` filled from [`CustomEffect::params`]),
+/// as declared in `shaders/post_common.wgsl`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomEffect {
+    source: Arc<str>,
+    pub params: [Vector4; 4],
+}
+
+impl CustomEffect {
+    pub fn new<S: Into<Arc<str>>>(source: S) -> Self {
+        Self {
+            source: source.into(),
+            params: [Vector4::zeros(); 4],
+        }
+    }
+
+    #[inline]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+/// A fullscreen pass of the [`PostProcess`] chain
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect {
+    Bloom(Bloom),
+    Vignette(Vignette),
+    ColorGrading(ColorGrading),
+    Crt(Crt),
+    ChromaticAberration(ChromaticAberration),
+    Custom(CustomEffect),
+}
+
+impl Effect {
+    /// Fragment entry of the built-in shader, `None` for custom effects
+    fn entry(&self) -> Option<&'static str> {
+        match self {
+            Self::Bloom(_) => Some("fs_bloom"),
+            Self::Vignette(_) => Some("fs_vignette"),
+            Self::ColorGrading(_) => Some("fs_color_grading"),
+            Self::Crt(_) => Some("fs_crt"),
+            Self::ChromaticAberration(_) => Some("fs_chromatic_aberration"),
+            Self::Custom(_) => None,
+        }
+    }
+
+    /// What the pipeline was built from, so that it is rebuilt
+    /// when the effect is replaced through [`PostProcess::get_mut`]
+    fn pipeline_key(&self) -> PipelineKey {
+        match self {
+            Self::Custom(custom) => PipelineKey::Custom(Arc::clone(&custom.source)),
+            effect => PipelineKey::Builtin(effect.entry().unwrap_or_default()),
+        }
+    }
+
+    fn params(&self, assets: &AssetServerGuard<'_>) -> [Vector4; 4] {
+        let mut params = [Vector4::zeros(); 4];
+
+        match self {
+            Self::Bloom(bloom) => {
+                params[0] = Vector4::new(bloom.threshold, bloom.intensity, bloom.radius, 0.0);
+            }
+            Self::Vignette(vignette) => {
+                params[0] =
+                    Vector4::new(vignette.intensity, vignette.radius, vignette.softness, 0.0);
+                params[1] = vignette.color.into();
+            }
+            Self::ColorGrading(grading) => {
+                let (x, y, w, h, _, size) = assets.get_texture_uv(grading.lut);
+
+                params[0] = Vector4::new(grading.intensity, size, 0.0, 0.0);
+                params[1] = Vector4::new(x, y, w, h);
+            }
+            Self::Crt(crt) => {
+                params[0] = Vector4::new(
+                    crt.curvature,
+                    crt.scanline_intensity,
+                    crt.scanlines,
+                    crt.vignette,
+                );
+            }
+            Self::ChromaticAberration(aberration) => {
+                params[0] = Vector4::new(aberration.offset, 0.0, 0.0, 0.0);
+            }
+            Self::Custom(custom) => params = custom.params,
+        }
+
+        params
+    }
+}
+
+macro_rules! impl_from_effect {
+    ($($variant:ident),*) => {
+        $(
+            impl From<$variant> for Effect {
+                #[inline]
+                fn from(effect: $variant) -> Self {
+                    Self::$variant(effect)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_effect!(Bloom, Vignette, ColorGrading, Crt, ChromaticAberration);
+
+impl From<CustomEffect> for Effect {
+    #[inline]
+    fn from(effect: CustomEffect) -> Self {
+        Self::Custom(effect)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PipelineKey {
+    Builtin(&'static str),
+    Custom(Arc<str>),
+}
+
+struct PostEffect {
+    label: Label,
+    effect: Effect,
+    enabled: bool,
+    key: PipelineKey,
+    pipeline: wgpu::RenderPipeline,
+    uniforms: GpuBuffer<PostUniforms>,
+    bind_group: wgpu::BindGroup,
+}
+
+/// Chain of fullscreen effects applied to the layers rendered in the window,
+/// in the order they were added.
+///
+/// Layers are post-processed unless excluded with [`crate::Scene::set_post_processed`],
+/// the excluded ones (the Ui layer by default) are drawn on top of the result.
+/// Without any enabled effect, the layers are rendered to the window directly.
+pub struct PostProcess {
+    effects: Vec<PostEffect>,
+
+    format: wgpu::TextureFormat,
+    texture_bgl: wgpu::BindGroupLayout,
+    uniform_bgl: wgpu::BindGroupLayout,
+    /// The layers are rendered into the first, then the effects
+    /// go back and forth between the two
+    targets: Option<[RenderTarget; 2]>,
+    time: f32,
+}
+
+impl PostProcess {
+    pub(crate) fn new(format: wgpu::TextureFormat, texture_bgl: &wgpu::BindGroupLayout) -> Self {
+        let uniform_bgl =
+            gpu::device().create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Post Process Uniforms Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        Self {
+            effects: Vec::new(),
+            format,
+            texture_bgl: texture_bgl.clone(),
+            uniform_bgl,
+            targets: None,
+            time: 0.0,
+        }
+    }
+
+    /// Appends an effect at the end of the chain,
+    /// replacing the one with the same label if any.
+    ///
+    /// Fails when the source of a [`CustomEffect`] doesn't compile,
+    /// the chain is then left as it was
+    pub fn add<E: Into<Effect>>(&mut self, label: Label, effect: E) -> Result<(), String> {
+        let effect = effect.into();
+
+        match self.position(label) {
+            Some(index) => {
+                warn!("Replacing the post-processing effect {label:?}");
+
+                let pipeline = self.pipeline(&effect)?;
+                let slot = &mut self.effects[index];
+
+                slot.key = effect.pipeline_key();
+                slot.pipeline = pipeline;
+                slot.effect = effect;
+            }
+            None => {
+                let effect = self.build(label, effect)?;
+                self.effects.push(effect);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Inserts an effect at `index` in the chain, or at its end if it is out of bounds.
+    ///
+    /// Fails when the source of a [`CustomEffect`] doesn't compile,
+    /// the chain is then left as it was
+    pub fn insert<E: Into<Effect>>(
+        &mut self,
+        index: usize,
+        label: Label,
+        effect: E,
+    ) -> Result<(), String> {
+        let effect = self.build(label, effect.into())?;
+
+        self.remove(label);
+        self.effects.insert(index.min(self.effects.len()), effect);
+
+        Ok(())
+    }
+
+    pub fn remove(&mut self, label: Label) -> Option<Effect> {
+        let index = self.position(label)?;
+
+        Some(self.effects.remove(index).effect)
+    }
+
+    #[inline]
+    pub fn get(&self, label: Label) -> Option<&Effect> {
+        self.effects
+            .iter()
+            .find(|effect| effect.label == label)
+            .map(|effect| &effect.effect)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, label: Label) -> Option<&mut Effect> {
+        self.effects
+            .iter_mut()
+            .find(|effect| effect.label == label)
+            .map(|effect| &mut effect.effect)
+    }
+
+    #[inline]
+    pub fn is_enabled(&self, label: Label) -> bool {
+        self.effects
+            .iter()
+            .any(|effect| effect.label == label && effect.enabled)
+    }
+
+    /// Disabled effects keep their place in the chain
+    #[inline]
+    pub fn set_enabled(&mut self, label: Label, enabled: bool) {
+        if let Some(effect) = self.effects.iter_mut().find(|effect| effect.label == label) {
+            effect.enabled = enabled;
+        }
+    }
+
+    /// Moves an effect to `index` in the chain, or to its end if it is out of bounds
+    pub fn move_to(&mut self, label: Label, index: usize) {
+        let Some(current) = self.position(label) else {
+            return;
+        };
+
+        let effect = self.effects.remove(current);
+        self.effects.insert(index.min(self.effects.len()), effect);
+    }
+
+    /// Labels of the effects, in the order they are applied
+    #[inline]
+    pub fn order(&self) -> impl Iterator<Item = Label> + '_ {
+        self.effects.iter().map(|effect| effect.label)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.effects.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.effects.clear();
+    }
+
+    /// Whether any effect will run this frame
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.effects.iter().any(|effect| effect.enabled)
+    }
+
+    #[inline]
+    fn position(&self, label: Label) -> Option<usize> {
+        self.effects.iter().position(|effect| effect.label == label)
+    }
+
+    fn pipeline(&self, effect: &Effect) -> Result<wgpu::RenderPipeline, String> {
+        let layouts = [&self.texture_bgl, &self.uniform_bgl, &self.texture_bgl];
+
+        let builder = |shader: &Shader, entry| {
+            shader
+                .pipeline_builder()
+                .label("Post Process Pipeline")
+                .vertex_entry("vs_main")
+                .fragment_entry(entry)
+                .blend_state(None)
+                .build(self.format, &layouts, &[])
+        };
+
+        match effect {
+            Effect::Custom(custom) => {
+                let source = format!("{COMMON_SOURCE}\n{}\n{CUSTOM_ENTRY}", custom.source);
+
+                gpu::validated(|| {
+                    let shader = Shader::from_wgsl(&source, Some("Custom Post Process shader"));
+                    builder(&shader, "fs_main")
+                })
+                .map_err(|e| format!("Invalid custom post-processing effect: {e}"))
+            }
+            effect => Ok(builder(post_shader(), effect.entry().unwrap_or_default())),
+        }
+    }
+
+    fn build(&self, label: Label, effect: Effect) -> Result<PostEffect, String> {
+        let pipeline = self.pipeline(&effect)?;
+
+        let uniforms = GpuBufferBuilder::new()
+            .label("Post Process Uniform Buffer")
+            .uniform()
+            .copy_dst()
+            .build();
+
+        let bind_group = gpu::device().create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Post Process Uniforms Bind Group"),
+            layout: &self.uniform_bgl,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniforms.inner().as_entire_binding(),
+            }],
+        });
+
+        Ok(PostEffect {
+            label,
+            key: effect.pipeline_key(),
+            pipeline,
+            effect,
+            enabled: true,
+            uniforms,
+            bind_group,
+        })
+    }
+
+    /// Resizes the targets to the window, rebuilds the pipelines of the replaced
+    /// effects and uploads the parameters, returns the view the layers are rendered
+    /// into, or `None` when no effect is left enabled after the rebuilds
+    pub(crate) fn prepare(
+        &mut self,
+        view: Size<u32>,
+        delta: f32,
+        assets: &AssetServerGuard<'_>,
+    ) -> Option<&wgpu::TextureView> {
+        self.time += delta;
+
+        if self
+            .targets
+            .as_ref()
+            .is_none_or(|[target, _]| target.size() != view)
+        {
+            let config = RenderTargetConfig::new(view.width, view.height);
+
+            self.targets = Some([
+                RenderTarget::new(&config, self.format, &self.texture_bgl, false),
+                RenderTarget::new(&config, self.format, &self.texture_bgl, false),
+            ]);
+        }
+
+        for i in 0..self.effects.len() {
+            let key = self.effects[i].effect.pipeline_key();
+
+            if self.effects[i].key != key {
+                // Changed through `get_mut`, where the error can't be returned
+                match self.pipeline(&self.effects[i].effect) {
+                    Ok(pipeline) => self.effects[i].pipeline = pipeline,
+                    Err(e) => {
+                        error!("{e}, disabling {:?}", self.effects[i].label);
+                        self.effects[i].enabled = false;
+                    }
+                }
+
+                self.effects[i].key = key;
+            }
+        }
+
+        if !self.is_active() {
+            return None;
+        }
+
+        let resolution = view.to_f32().into();
+
+        for effect in self.effects.iter().filter(|effect| effect.enabled) {
+            effect.uniforms.write(
+                0,
+                &[PostUniforms {
+                    resolution,
+                    time: self.time,
+                    _padding: 0.0,
+                    params: effect.effect.params(assets),
+                }],
+            );
+        }
+
+        // Created just above
+        self.targets.as_ref().map(|[target, _]| target.view())
+    }
+
+    /// Runs the enabled effects, the last one writing to `output`
+    pub(crate) fn apply(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        output: &wgpu::TextureView,
+        assets: &AssetServerGuard<'_>,
+        timer: &mut GpuTimer,
+    ) {
+        let Some(targets) = &self.targets else {
+            return;
+        };
+
+        let effects = self.effects.iter().filter(|effect| effect.enabled);
+        let count = effects.clone().count();
+
+        for (i, effect) in effects.enumerate() {
+            let source = &targets[i % 2];
+            let destination = match i + 1 == count {
+                true => output,
+                false => targets[(i + 1) % 2].view(),
+            };
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Post Process Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: destination,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                    depth_slice: None,
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+                multiview_mask: None,
+            });
+
+            let token = timer.begin(&mut render_pass, Section::Post);
+
+            render_pass.set_pipeline(&effect.pipeline);
+            profiling::record_pipeline_switches(1);
+
+            render_pass.set_bind_group(0, source.bind_group(), &[]);
+            render_pass.set_bind_group(1, &effect.bind_group, &[]);
+            render_pass.set_bind_group(2, assets.atlas_bg(), &[]);
+
+            render_pass.draw(0..3, 0..1);
+            profiling::record_draw_call(3, 0);
+
+            timer.end(&mut render_pass, token);
+        }
+    }
+}
@@ -1,8 +1,10 @@
 use crate::{
-    Camera, Color, Renderer,
+    Camera, Color, DirectionalLight, Layer, Light2d, Occluder, PointLight, PostProcess, Renderer,
     retained::{RetainedRenderer, Text, mesh::Mesh},
+    target::{RenderTarget, RenderTargetConfig},
 };
 use macros::{Get, Set};
+use math::Size;
 use utils::Handle;
 
 #[derive(Get, Set)]
@@ -10,6 +12,8 @@ pub struct Scene<'a> {
     #[get(prop = "clear_color", ty = &Color, name = "clear_color")]
     #[get(mut, prop = "clear_color", ty = &Color, name = "clear_color_mut")]
     #[set(into, prop = "clear_color", ty = Color, name = "set_clear_color")]
+    #[get(prop = "active_layer", ty = &Layer, name = "layer")]
+    #[set(prop = "active_layer", ty = Layer, name = "set_layer")]
     renderer: &'a mut Renderer,
 }
 
@@ -75,6 +79,36 @@ impl<'a> Scene<'a> {
         layer.text.remove_text(handle);
     }
 
+    /// Creates an offscreen texture, drawn into by selecting
+    /// [`Layer::Target`] with [`Scene::set_layer`] or [`crate::Draw::set_layer`]
+    #[inline]
+    pub fn create_target(&mut self, config: RenderTargetConfig) -> Handle<RenderTarget> {
+        self.renderer.create_target(config)
+    }
+
+    #[inline]
+    pub fn get_target(&self, handle: Handle<RenderTarget>) -> Option<&RenderTarget> {
+        self.renderer.targets.get(handle)
+    }
+
+    #[inline]
+    pub fn get_target_mut(&mut self, handle: Handle<RenderTarget>) -> Option<&mut RenderTarget> {
+        self.renderer.targets.get_mut(handle)
+    }
+
+    /// Recreates the texture of the target, its content is lost until the next frame
+    #[inline]
+    pub fn resize_target(&mut self, handle: Handle<RenderTarget>, width: u32, height: u32) {
+        self.renderer
+            .resize_target(handle, Size::new(width, height));
+    }
+
+    /// Removes the target and its layer, what still samples it isn't drawn anymore
+    #[inline]
+    pub fn remove_target(&mut self, handle: Handle<RenderTarget>) {
+        self.renderer.remove_target(handle);
+    }
+
     #[inline]
     pub fn retained(&mut self) -> &mut RetainedRenderer {
         let layer = self.renderer.layer_mut(self.renderer.active_layer);
@@ -89,12 +123,224 @@ impl<'a> Scene<'a> {
         &layer.camera
     }
 
+    /// Whether the meshes of the active layer are drawn as wireframes
+    #[inline]
+    pub fn wireframe(&self) -> bool {
+        let layer = self.renderer.layer(self.renderer.active_layer);
+
+        layer.retained.wireframe
+    }
+
+    #[inline]
+    pub fn set_wireframe(&mut self, wireframe: bool) {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer.retained.wireframe = wireframe;
+    }
+
     #[inline]
     pub fn camera_mut(&mut self) -> &mut Camera {
         let layer = self.renderer.layer_mut(self.renderer.active_layer);
 
         &mut layer.camera
     }
+
+    /// Renders the World layer at `width`x`height`, upscaled by a whole factor
+    /// and letterboxed in the window, for crisp pixel art.
+    ///
+    /// Its camera then works in virtual pixels, like `Input::virtual_mouse_position`
+    #[inline]
+    pub fn set_virtual_resolution(&mut self, width: u32, height: u32) {
+        self.renderer
+            .set_virtual_resolution(Some(Size::new(width, height)));
+    }
+
+    /// Goes back to rendering the World layer at the window's resolution
+    #[inline]
+    pub fn clear_virtual_resolution(&mut self) {
+        self.renderer.set_virtual_resolution(None);
+    }
+
+    #[inline]
+    pub fn virtual_resolution(&self) -> Option<Size<u32>> {
+        self.renderer.virtual_resolution()
+    }
+
+    /// Lights of the active layer, only meshes with a lit [`crate::Shading`] react to them
+    #[inline]
+    pub fn add_directional_light(&mut self, light: DirectionalLight) -> Handle<DirectionalLight> {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer.lights.add_directional(light)
+    }
+
+    #[inline]
+    pub fn get_directional_light(
+        &self,
+        handle: Handle<DirectionalLight>,
+    ) -> Option<&DirectionalLight> {
+        let layer = self.renderer.layer(self.renderer.active_layer);
+
+        layer.lights.directional(handle)
+    }
+
+    #[inline]
+    pub fn get_directional_light_mut(
+        &mut self,
+        handle: Handle<DirectionalLight>,
+    ) -> Option<&mut DirectionalLight> {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer.lights.directional_mut(handle)
+    }
+
+    #[inline]
+    pub fn remove_directional_light(&mut self, handle: Handle<DirectionalLight>) {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer.lights.remove_directional(handle);
+    }
+
+    #[inline]
+    pub fn add_point_light(&mut self, light: PointLight) -> Handle<PointLight> {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer.lights.add_point(light)
+    }
+
+    #[inline]
+    pub fn get_point_light(&self, handle: Handle<PointLight>) -> Option<&PointLight> {
+        let layer = self.renderer.layer(self.renderer.active_layer);
+
+        layer.lights.point(handle)
+    }
+
+    #[inline]
+    pub fn get_point_light_mut(&mut self, handle: Handle<PointLight>) -> Option<&mut PointLight> {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer.lights.point_mut(handle)
+    }
+
+    #[inline]
+    pub fn remove_point_light(&mut self, handle: Handle<PointLight>) {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer.lights.remove_point(handle);
+    }
+
+    #[inline]
+    pub fn ambient_light(&self) -> Color {
+        let layer = self.renderer.layer(self.renderer.active_layer);
+
+        layer.lights.ambient()
+    }
+
+    /// Lights every side of the lit meshes of the active layer the same way
+    #[inline]
+    pub fn set_ambient_light<C: Into<Color>>(&mut self, color: C) {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer.lights.set_ambient(color.into());
+    }
+
+    /// 2D lights of the World layer, whatever the active one.
+    /// Once there is one, the World layer is only lit by them and the ambient light
+    #[inline]
+    pub fn add_light_2d(&mut self, light: Light2d) -> Handle<Light2d> {
+        self.renderer.lighting.add_light(light)
+    }
+
+    #[inline]
+    pub fn get_light_2d(&self, handle: Handle<Light2d>) -> Option<&Light2d> {
+        self.renderer.lighting.light(handle)
+    }
+
+    #[inline]
+    pub fn get_light_2d_mut(&mut self, handle: Handle<Light2d>) -> Option<&mut Light2d> {
+        self.renderer.lighting.light_mut(handle)
+    }
+
+    #[inline]
+    pub fn remove_light_2d(&mut self, handle: Handle<Light2d>) {
+        self.renderer.lighting.remove_light(handle);
+    }
+
+    /// Shapes casting shadows from the 2D lights of the World layer
+    #[inline]
+    pub fn add_occluder(&mut self, occluder: Occluder) -> Handle<Occluder> {
+        self.renderer.lighting.add_occluder(occluder)
+    }
+
+    #[inline]
+    pub fn get_occluder(&self, handle: Handle<Occluder>) -> Option<&Occluder> {
+        self.renderer.lighting.occluder(handle)
+    }
+
+    #[inline]
+    pub fn get_occluder_mut(&mut self, handle: Handle<Occluder>) -> Option<&mut Occluder> {
+        self.renderer.lighting.occluder_mut(handle)
+    }
+
+    #[inline]
+    pub fn remove_occluder(&mut self, handle: Handle<Occluder>) {
+        self.renderer.lighting.remove_occluder(handle);
+    }
+
+    #[inline]
+    pub fn ambient_light_2d(&self) -> Color {
+        self.renderer.lighting.ambient()
+    }
+
+    /// Light of the World layer where no 2D light reaches
+    #[inline]
+    pub fn set_ambient_light_2d<C: Into<Color>>(&mut self, color: C) {
+        self.renderer.lighting.set_ambient(color.into());
+    }
+
+    /// Depth tests the retained meshes, so that 3D scenes overlap correctly.
+    ///
+    /// Opaque meshes are drawn first, translucent ones after them from back to front.
+    /// Immediate drawing and text are still drawn in order, and layers drawn later
+    /// still cover the earlier ones
+    #[inline]
+    pub fn set_depth_buffer(&mut self, enabled: bool) {
+        self.renderer.set_depth_buffer(enabled);
+    }
+
+    #[inline]
+    pub fn depth_buffer(&self) -> bool {
+        self.renderer.depth_buffer()
+    }
+
+    #[inline]
+    pub fn post_process(&self) -> &PostProcess {
+        &self.renderer.post
+    }
+
+    /// Effects applied to the layers rendered in the window
+    #[inline]
+    pub fn post_process_mut(&mut self) -> &mut PostProcess {
+        &mut self.renderer.post
+    }
+
+    /// Whether the active layer goes through the post-processing effects,
+    /// every layer but the Ui one does by default
+    #[inline]
+    pub fn post_processed(&self) -> bool {
+        let layer = self.renderer.layer(self.renderer.active_layer);
+
+        layer.post_processed
+    }
+
+    /// Excluded layers are drawn on top of the post-processed ones.
+    /// Doesn't apply to render targets, which are never post-processed
+    #[inline]
+    pub fn set_post_processed(&mut self, post_processed: bool) {
+        let layer = self.renderer.layer_mut(self.renderer.active_layer);
+
+        layer.post_processed = post_processed;
+    }
 }
 
 pub struct SceneView<'a> {
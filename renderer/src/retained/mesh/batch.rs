@@ -1,9 +1,16 @@
-use crate::retained::mesh::{GeometryBuffer, Mesh, MeshGpu};
+use crate::{
+    retained::mesh::{GeometryBuffer, Mesh, MeshGpu},
+    target::RenderTarget,
+};
 use globals::consts;
 use gpu::core::{GpuBuffer, GpuBufferBuilder};
 use std::sync::Arc;
 use utils::Handle;
 
+/// Geometry id, the render target sampled instead of the atlas,
+/// the variant of the [`crate::Shading`] and whether the meshes are translucent
+pub type BatchKey = (u64, Option<Handle<RenderTarget>>, u8, bool);
+
 pub struct MeshBatch {
     pub buffer: Arc<GeometryBuffer>,
     pub handles: Vec<Handle<Mesh>>,
@@ -1,4 +1,5 @@
 use crate::vertex::Vertex;
+use assets::ModelPrimitive;
 use gpu::core::{GpuBuffer, GpuBufferBuilder};
 use math::{Size, Vector2, Vector3, Vector4};
 use std::{
@@ -127,10 +128,7 @@ impl Geometry {
 
         let indices = vec![0, 1, 2, 2, 3, 0];
 
-        let id = Self::hash(&vertices, &indices);
-        let buffer = get_or_insert(id, || GeometryBuffer::new(&vertices, &indices));
-
-        Self { id, buffer }
+        Self::from_parts(vertices, indices)
     }
 
     pub fn circle(radius: f32, segments: u32) -> Self {
@@ -161,9 +159,7 @@ impl Geometry {
             indices.push(if i + 2 > segments { 1 } else { i + 2 });
         }
 
-        let id = Self::hash(&vertices, &indices);
-        let buffer = get_or_insert(id, || GeometryBuffer::new(&vertices, &indices));
-        Self { id, buffer }
+        Self::from_parts(vertices, indices)
     }
 
     pub fn cube(size: f32) -> Self {
@@ -307,20 +303,99 @@ impl Geometry {
             20, 21, 22, 22, 23, 20, // Left
         ];
 
-        let id = Self::hash(&vertices, &indices);
-        let buffer = get_or_insert(id, || GeometryBuffer::new(&vertices, &indices));
-
-        Self { id, buffer }
+        Self::from_parts(vertices, indices)
     }
 
     pub fn unit_rect() -> Self {
         Self::rect((1.0, 1.0))
     }
 
-    pub fn new(vertices: &Vec<Vertex>, indices: &Vec<u32>) -> Self {
-        let id = Self::hash(vertices, indices);
-        let buffer = get_or_insert(id, || GeometryBuffer::new(vertices, indices));
+    /// Vertices without a normal get one from the triangles they are part of
+    pub fn new(vertices: &[Vertex], indices: &[u32]) -> Self {
+        Self::from_parts(vertices.to_vec(), indices.to_vec())
+    }
+
+    /// Vertices of a loaded model, white and with
+    /// computed normals when the file has none
+    pub fn from_primitive(primitive: &ModelPrimitive) -> Self {
+        let white = Vector4::new(1.0, 1.0, 1.0, 1.0);
+
+        let vertices = primitive
+            .positions
+            .iter()
+            .enumerate()
+            .map(|(i, &position)| {
+                let color = primitive.colors.get(i).copied().unwrap_or(white);
+                let uv = primitive.uvs.get(i).copied().unwrap_or(Vector2::zeros());
+                let normal = primitive
+                    .normals
+                    .get(i)
+                    .copied()
+                    .unwrap_or(Vector3::zeros());
+
+                Vertex::new(position, color, uv).with_normal(normal)
+            })
+            .collect();
+
+        Self::from_parts(vertices, primitive.indices.clone())
+    }
+
+    /// Generated shapes are cached by their parameters,
+    /// so that `build` only runs for a shape that is not in the cache
+    pub(super) fn cached<K: Hash>(key: K, build: impl FnOnce() -> (Vec<Vertex>, Vec<u32>)) -> Self {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let id = hasher.finish();
+
+        let buffer = get_or_insert(id, || {
+            let (mut vertices, indices) = build();
+            Self::compute_normals(&mut vertices, &indices);
+
+            GeometryBuffer::new(&vertices, &indices)
+        });
 
         Self { id, buffer }
     }
+
+    fn from_parts(mut vertices: Vec<Vertex>, indices: Vec<u32>) -> Self {
+        Self::compute_normals(&mut vertices, &indices);
+
+        let id = Self::hash(&vertices, &indices);
+        let buffer = get_or_insert(id, || GeometryBuffer::new(&vertices, &indices));
+
+        Self { id, buffer }
+    }
+
+    /// Gives the vertices without a normal the average of the normals of
+    /// their triangles, weighted by area. A triangle `abc` faces `(b - a) × (c - a)`,
+    /// like the faces of [`Geometry::cube`]
+    pub fn compute_normals(vertices: &mut [Vertex], indices: &[u32]) {
+        if vertices.iter().all(|v| v.normal != Vector3::zeros()) {
+            return;
+        }
+
+        let mut normals = vec![Vector3::zeros(); vertices.len()];
+
+        for triangle in indices.chunks_exact(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| i as usize);
+
+            let (Some(va), Some(vb), Some(vc)) =
+                (vertices.get(a), vertices.get(b), vertices.get(c))
+            else {
+                continue;
+            };
+
+            let normal = (vb.position - va.position).cross(&(vc.position - va.position));
+
+            normals[a] += normal;
+            normals[b] += normal;
+            normals[c] += normal;
+        }
+
+        for (vertex, normal) in vertices.iter_mut().zip(normals) {
+            if vertex.normal == Vector3::zeros() && normal != Vector3::zeros() {
+                vertex.normal = normal.normalized();
+            }
+        }
+    }
 }
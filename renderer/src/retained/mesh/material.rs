@@ -1,23 +1,90 @@
-use crate::color::Color;
-use assets::Image;
+use crate::{color::Color, target::RenderTarget};
+use assets::{Image, ModelPrimitive};
 use utils::Handle;
 
 #[derive(Debug, Clone, Copy)]
 pub enum TextureKind {
     None,
     Full(Handle<Image>),
+    /// The whole texture of a render target
+    Target(Handle<RenderTarget>),
+}
+
+impl TextureKind {
+    #[inline]
+    pub(crate) fn target(&self) -> Option<Handle<RenderTarget>> {
+        match self {
+            Self::Target(target) => Some(*target),
+            _ => None,
+        }
+    }
+}
+
+/// How a mesh reacts to the lights of its layer
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Shading {
+    /// Drawn with its own colors, whatever the lights
+    #[default]
+    Unlit,
+    /// Diffuse lighting only, for matte surfaces
+    Lambert,
+    /// Diffuse lighting and specular highlights,
+    /// smaller and sharper with a greater `shininess`
+    BlinnPhong { specular: f32, shininess: f32 },
+}
+
+impl Shading {
+    /// Somewhat shiny plastic
+    #[inline]
+    pub fn blinn_phong() -> Self {
+        Self::BlinnPhong {
+            specular: 0.5,
+            shininess: 32.0,
+        }
+    }
+
+    /// Each variant has its own pipeline, so its own batches
+    #[inline]
+    pub(crate) fn variant(&self) -> u8 {
+        match self {
+            Self::Unlit => 0,
+            Self::Lambert => 1,
+            Self::BlinnPhong { .. } => 2,
+        }
+    }
+}
+
+/// Whether a mesh hides what is behind it or is blended with it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AlphaMode {
+    /// Blended when the color has an alpha below one or there is
+    /// a texture, which can have transparent pixels
+    #[default]
+    Auto,
+    /// Writes to the depth buffer and hides what is behind it,
+    /// whatever the alpha of its color and texture
+    Opaque,
+    /// Drawn after the opaque meshes, back to front
+    Blend,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct Material {
     pub color: Color,
     pub texture: TextureKind,
+    pub shading: Shading,
+    pub alpha: AlphaMode,
 }
 
 impl Material {
     #[inline]
     pub fn new(color: Color, texture: TextureKind) -> Self {
-        Self { color, texture }
+        Self {
+            color,
+            texture,
+            shading: Shading::Unlit,
+            alpha: AlphaMode::Auto,
+        }
     }
 
     #[inline]
@@ -25,6 +92,8 @@ impl Material {
         Self {
             color,
             texture: TextureKind::None,
+            shading: Shading::Unlit,
+            alpha: AlphaMode::Auto,
         }
     }
 
@@ -33,6 +102,41 @@ impl Material {
         Self {
             color: Color::White,
             texture,
+            shading: Shading::Unlit,
+            alpha: AlphaMode::Auto,
         }
     }
+
+    /// Base color and texture of a loaded model. Texture coordinates
+    /// outside of `0..1` are not repeated, since textures share the atlas.
+    ///
+    /// Textures of models are taken as opaque, only the alpha of the base color blends
+    #[inline]
+    pub fn from_primitive(primitive: &ModelPrimitive) -> Self {
+        let alpha = match primitive.base_color.w < 1.0 {
+            true => AlphaMode::Blend,
+            false => AlphaMode::Opaque,
+        };
+
+        Self {
+            color: primitive.base_color.into(),
+            texture: primitive
+                .texture
+                .map_or(TextureKind::None, TextureKind::Full),
+            shading: Shading::Unlit,
+            alpha,
+        }
+    }
+
+    #[inline]
+    pub fn with_shading(mut self, shading: Shading) -> Self {
+        self.shading = shading;
+        self
+    }
+
+    #[inline]
+    pub fn with_alpha(mut self, alpha: AlphaMode) -> Self {
+        self.alpha = alpha;
+        self
+    }
 }
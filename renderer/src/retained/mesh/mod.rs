@@ -1,10 +1,11 @@
 mod batch;
 mod geometry;
 mod material;
+mod shapes;
 mod transform;
 
 use crate::{color::Color, traits::LayoutDescriptor};
-use assets::AssetServerGuard;
+use assets::{AssetServerGuard, Model};
 use macros::{Get, Set, track_dirty};
 use math::{Vector2, Vector3, Vector4};
 use std::mem;
@@ -24,6 +25,8 @@ pub struct MeshGpu {
     color: Vector4,
     uv_offset: Vector2,
     uv_scale: Vector2,
+    /// Specular strength and shininess
+    shading: Vector2,
 }
 
 impl LayoutDescriptor for MeshGpu {
@@ -66,6 +69,14 @@ impl LayoutDescriptor for MeshGpu {
                     shader_location: 8,
                     format: wgpu::VertexFormat::Float32x2,
                 },
+                wgpu::VertexAttribute {
+                    offset: (mem::size_of::<Vector3>() * 3
+                        + mem::size_of::<Vector4>()
+                        + mem::size_of::<Vector2>() * 2)
+                        as wgpu::BufferAddress,
+                    shader_location: 9,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
             ],
         }
     }
@@ -141,6 +152,13 @@ pub struct Mesh {
     #[set(prop = "scale.z", ty = f32, name = "set_scale_z", also = self.tracker |= Self::transform_f())]
     transform: Transform3d,
 
+    /// Meshes with a greater z index are drawn over the ones with a lower one, and
+    /// over the shapes and texts of the layer with a lower one. The depth buffer
+    /// still hides what is behind opaque meshes
+    #[get(copied)]
+    #[set]
+    z_index: i32,
+
     pub(crate) gpu: MeshGpu,
 }
 
@@ -150,6 +168,7 @@ impl Mesh {
             geometry,
             material,
             transform,
+            z_index: 0,
             gpu: MeshGpu::default(),
             tracker: 0,
         };
@@ -158,6 +177,28 @@ impl Mesh {
         mesh
     }
 
+    /// One mesh per primitive of the model, placed by the transforms of its
+    /// nodes. They can be added with [`crate::Scene::add_mesh`]
+    pub fn from_model(model: &Model) -> Vec<Self> {
+        model
+            .primitives
+            .iter()
+            .map(|primitive| {
+                Self::new(
+                    Geometry::from_primitive(primitive),
+                    Material::from_primitive(primitive),
+                    Transform3d::from_matrix(primitive.transform),
+                )
+            })
+            .collect()
+    }
+
+    #[inline]
+    pub fn with_z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
     #[inline]
     pub(crate) fn prepare(&mut self, assets: &AssetServerGuard<'_>) -> bool {
         let mut changed = false;
@@ -176,6 +217,7 @@ impl Mesh {
             let (uvx, uvy, uvw, uvh, _, _) = match self.material.texture {
                 TextureKind::Full(handle) => assets.get_texture_uv(handle),
                 TextureKind::None => assets.get_white_uv_coords(),
+                TextureKind::Target(_) => (0.0, 0.0, 1.0, 1.0, 0.0, 0.0),
             };
 
             self.gpu.uv_offset.x = uvx;
@@ -183,6 +225,14 @@ impl Mesh {
             self.gpu.uv_scale.x = uvw;
             self.gpu.uv_scale.y = uvh;
 
+            self.gpu.shading = match self.material.shading {
+                Shading::BlinnPhong {
+                    specular,
+                    shininess,
+                } => Vector2::new(specular, shininess),
+                _ => Vector2::zeros(),
+            };
+
             changed = true;
         }
 
@@ -192,4 +242,28 @@ impl Mesh {
 
         changed
     }
+
+    /// Blended with what is behind it, so drawn after the opaque meshes
+    #[inline]
+    pub(crate) fn is_translucent(&self) -> bool {
+        match self.material.alpha {
+            AlphaMode::Auto => {
+                self.material.color.a < 1.0 || !matches!(self.material.texture, TextureKind::None)
+            }
+            AlphaMode::Opaque => false,
+            AlphaMode::Blend => true,
+        }
+    }
+
+    /// Meshes are batched by geometry, by render target since a target is
+    /// bound instead of the atlas, by shading and by translucency
+    #[inline]
+    pub(crate) fn batch_key(&self) -> BatchKey {
+        (
+            self.geometry.id,
+            self.material.texture.target(),
+            self.material.shading.variant(),
+            self.is_translucent(),
+        )
+    }
 }
@@ -0,0 +1,556 @@
+//! More shapes for [`Geometry`]. The flat ones lie in the `z = 0` plane
+//! facing `+z`, the solids are centered on the origin with `y` up
+
+use super::Geometry;
+use crate::{
+    tessellate::{self, LineJoin, Stroke, Tessellation},
+    vertex::Vertex,
+};
+use math::{Size, Vector2, Vector3, Vector4};
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+use utils::FastHashMap;
+
+impl Geometry {
+    /// A rectangle with its corners rounded by `radius`,
+    /// using `segments` triangles per corner
+    pub fn rounded_rect<S>(size: S, radius: f32, segments: u32) -> Self
+    where
+        S: Into<Size<f32>>,
+    {
+        let size: Size<f32> = size.into();
+        let key = (
+            "rounded_rect",
+            size.width.to_bits(),
+            size.height.to_bits(),
+            radius.to_bits(),
+            segments,
+        );
+
+        Self::cached(key, || {
+            let half = Vector2::new(size.width, size.height) / 2.0;
+            let outline =
+                tessellate::rounded_rect_outline(Vector2::zeros(), half, radius, segments);
+
+            flat(convex_fan(outline))
+        })
+    }
+
+    /// A disc with a hole of `inner_radius` in the middle
+    pub fn ring(inner_radius: f32, outer_radius: f32, segments: u32) -> Self {
+        Self::arc(inner_radius, outer_radius, 0.0, TAU, segments)
+    }
+
+    /// Part of a ring between two angles in radians,
+    /// a pie slice when `inner_radius` is zero
+    pub fn arc(
+        inner_radius: f32,
+        outer_radius: f32,
+        start_angle: f32,
+        end_angle: f32,
+        segments: u32,
+    ) -> Self {
+        let key = (
+            "arc",
+            inner_radius.to_bits(),
+            outer_radius.to_bits(),
+            start_angle.to_bits(),
+            end_angle.to_bits(),
+            segments,
+        );
+
+        Self::cached(key, || {
+            let mut out = Tessellation::default();
+            let segments = segments.max(1);
+
+            for i in 0..=segments {
+                let direction = Vector2::from_angle(
+                    start_angle + (end_angle - start_angle) * i as f32 / segments as f32,
+                );
+
+                out.vertex(direction * inner_radius);
+                out.vertex(direction * outer_radius);
+
+                if i > 0 {
+                    let [a, b, c, d] = [2 * i - 2, 2 * i - 1, 2 * i + 1, 2 * i];
+
+                    out.triangle(a, b, c);
+                    out.triangle(a, c, d);
+                }
+            }
+
+            flat(out)
+        })
+    }
+
+    /// A polygon with `sides` equal sides, the first corner on `+x`
+    pub fn regular_polygon(radius: f32, sides: u32) -> Self {
+        Self::cached(("regular_polygon", radius.to_bits(), sides), || {
+            let sides = sides.max(3);
+            let outline = (0..sides)
+                .map(|i| Vector2::from_angle(TAU * i as f32 / sides as f32) * radius)
+                .collect();
+
+            flat(convex_fan(outline))
+        })
+    }
+
+    /// Any simple polygon, convex or concave, in either winding.
+    /// The outline must not cross itself
+    pub fn polygon(points: &[Vector2]) -> Self {
+        let key = ("polygon", bits(points));
+
+        Self::cached(key, || {
+            let indices = tessellate::triangulate(points);
+
+            flat(Tessellation {
+                positions: points.to_vec(),
+                indices,
+            })
+        })
+    }
+
+    /// A line of the given `thickness` through `points`, cut square at both ends
+    pub fn polyline(points: &[Vector2], thickness: f32, join: LineJoin) -> Self {
+        let key = ("polyline", bits(points), thickness.to_bits(), join);
+
+        Self::cached(key, || {
+            flat(tessellate::stroke(
+                points,
+                false,
+                &Stroke::new(thickness).with_join(join),
+            ))
+        })
+    }
+
+    /// A sphere made of `stacks` rings of `sectors` quads,
+    /// with the texture wrapped around like a map of the world
+    pub fn uv_sphere(radius: f32, sectors: u32, stacks: u32) -> Self {
+        Self::cached(("uv_sphere", radius.to_bits(), sectors, stacks), || {
+            let stacks = stacks.max(2);
+            let rows = (0..=stacks).map(|i| {
+                let (sin, cos) = (PI * i as f32 / stacks as f32).sin_cos();
+                (cos * radius, sin * radius, Vector3::new(sin, cos, sin))
+            });
+
+            lathe(rows, sectors, radius * 2.0)
+        })
+    }
+
+    /// A subdivided icosahedron, its triangles are all about
+    /// the same size unlike the ones of a [`Geometry::uv_sphere`]
+    pub fn ico_sphere(radius: f32, subdivisions: u32) -> Self {
+        Self::cached(("ico_sphere", radius.to_bits(), subdivisions), || {
+            let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+
+            let mut points: Vec<Vector3> = [
+                [-1.0, t, 0.0],
+                [1.0, t, 0.0],
+                [-1.0, -t, 0.0],
+                [1.0, -t, 0.0],
+                [0.0, -1.0, t],
+                [0.0, 1.0, t],
+                [0.0, -1.0, -t],
+                [0.0, 1.0, -t],
+                [t, 0.0, -1.0],
+                [t, 0.0, 1.0],
+                [-t, 0.0, -1.0],
+                [-t, 0.0, 1.0],
+            ]
+            .into_iter()
+            .map(|p| Vector3::from_array(p).normalized())
+            .collect();
+
+            let mut indices: Vec<u32> = vec![
+                0, 11, 5, 0, 5, 1, 0, 1, 7, 0, 7, 10, 0, 10, 11, 1, 5, 9, 5, 11, 4, 11, 10, 2, 10,
+                7, 6, 7, 1, 8, 3, 9, 4, 3, 4, 2, 3, 2, 6, 3, 6, 8, 3, 8, 9, 4, 9, 5, 2, 4, 11, 6,
+                2, 10, 8, 6, 7, 9, 8, 1,
+            ];
+
+            for _ in 0..subdivisions {
+                let mut midpoints = FastHashMap::default();
+                let mut next = Vec::with_capacity(indices.len() * 4);
+
+                let mut midpoint = |a: u32, b: u32, points: &mut Vec<Vector3>| {
+                    *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                        let p = (points[a as usize] + points[b as usize]).normalized();
+                        points.push(p);
+                        (points.len() - 1) as u32
+                    })
+                };
+
+                for triangle in indices.chunks_exact(3) {
+                    let [a, b, c] = [triangle[0], triangle[1], triangle[2]];
+                    let ab = midpoint(a, b, &mut points);
+                    let bc = midpoint(b, c, &mut points);
+                    let ca = midpoint(c, a, &mut points);
+
+                    next.extend([a, ab, ca, b, bc, ab, c, ca, bc, ab, bc, ca]);
+                }
+
+                indices = next;
+            }
+
+            let vertices = points
+                .into_iter()
+                .map(|normal| {
+                    let uv = Vector2::new(
+                        0.5 + normal.z.atan2(normal.x) / TAU,
+                        0.5 - normal.y.asin() / PI,
+                    );
+
+                    Vertex::new(normal * radius, Vector4::ones(), uv).with_normal(normal)
+                })
+                .collect();
+
+            solid(vertices, indices)
+        })
+    }
+
+    /// A cylinder standing on the `y` axis, closed at both ends
+    pub fn cylinder(radius: f32, height: f32, segments: u32) -> Self {
+        Self::cached(
+            ("cylinder", radius.to_bits(), height.to_bits(), segments),
+            || {
+                let hh = height / 2.0;
+                let mut vertices = Vec::new();
+                let mut indices = Vec::new();
+
+                let normal = Vector3::new(1.0, 0.0, 1.0);
+                side(
+                    &mut vertices,
+                    &mut indices,
+                    segments,
+                    [(hh, radius), (-hh, radius)],
+                    normal,
+                );
+                cap(&mut vertices, &mut indices, segments, radius, hh, 1.0);
+                cap(&mut vertices, &mut indices, segments, radius, -hh, -1.0);
+
+                solid(vertices, indices)
+            },
+        )
+    }
+
+    /// A cone standing on the `y` axis, its tip at the top
+    pub fn cone(radius: f32, height: f32, segments: u32) -> Self {
+        Self::cached(
+            ("cone", radius.to_bits(), height.to_bits(), segments),
+            || {
+                let hh = height / 2.0;
+                let mut vertices = Vec::new();
+                let mut indices = Vec::new();
+
+                // The normals of the side lean up by the slope, and the tip
+                // is a ring of points so that each quad has its own normal
+                let slope = Vector3::new(height, radius, height);
+                side(
+                    &mut vertices,
+                    &mut indices,
+                    segments,
+                    [(hh, 0.0), (-hh, radius)],
+                    slope,
+                );
+                cap(&mut vertices, &mut indices, segments, radius, -hh, -1.0);
+
+                solid(vertices, indices)
+            },
+        )
+    }
+
+    /// A flat grid in the `xz` plane facing `+y`, made of `columns * rows`
+    /// quads, such as a floor or a surface to displace
+    pub fn plane<S>(size: S, columns: u32, rows: u32) -> Self
+    where
+        S: Into<Size<f32>>,
+    {
+        let size: Size<f32> = size.into();
+        let key = (
+            "plane",
+            size.width.to_bits(),
+            size.height.to_bits(),
+            columns,
+            rows,
+        );
+
+        Self::cached(key, || {
+            let columns = columns.max(1);
+            let rows = rows.max(1);
+            let mut vertices = Vec::with_capacity(((columns + 1) * (rows + 1)) as usize);
+            let mut indices = Vec::with_capacity((columns * rows * 6) as usize);
+
+            for row in 0..=rows {
+                for column in 0..=columns {
+                    let uv = Vector2::new(column as f32 / columns as f32, row as f32 / rows as f32);
+                    let position =
+                        Vector3::new((uv.x - 0.5) * size.width, 0.0, (uv.y - 0.5) * size.height);
+
+                    vertices.push(
+                        Vertex::new(position, Vector4::ones(), uv)
+                            .with_normal(Vector3::new(0.0, 1.0, 0.0)),
+                    );
+                }
+            }
+
+            grid_indices(&mut indices, 0, columns, rows);
+
+            solid(vertices, indices)
+        })
+    }
+
+    /// A ring shaped tube around the `y` axis, `major_radius` from the
+    /// center to the middle of the tube and `minor_radius` across the tube
+    pub fn torus(
+        major_radius: f32,
+        minor_radius: f32,
+        major_segments: u32,
+        minor_segments: u32,
+    ) -> Self {
+        let key = (
+            "torus",
+            major_radius.to_bits(),
+            minor_radius.to_bits(),
+            major_segments,
+            minor_segments,
+        );
+
+        Self::cached(key, || {
+            let major = major_segments.max(3);
+            let minor = minor_segments.max(3);
+            let mut vertices = Vec::with_capacity(((major + 1) * (minor + 1)) as usize);
+            let mut indices = Vec::with_capacity((major * minor * 6) as usize);
+
+            for i in 0..=minor {
+                let (sin_phi, cos_phi) = (TAU * i as f32 / minor as f32).sin_cos();
+
+                for j in 0..=major {
+                    let (sin_theta, cos_theta) = (TAU * j as f32 / major as f32).sin_cos();
+                    let normal = Vector3::new(cos_phi * cos_theta, sin_phi, cos_phi * sin_theta);
+                    let center = Vector3::new(cos_theta, 0.0, sin_theta) * major_radius;
+                    let uv = Vector2::new(j as f32 / major as f32, i as f32 / minor as f32);
+
+                    vertices.push(
+                        Vertex::new(center + normal * minor_radius, Vector4::ones(), uv)
+                            .with_normal(normal),
+                    );
+                }
+            }
+
+            grid_indices(&mut indices, 0, major, minor);
+
+            solid(vertices, indices)
+        })
+    }
+
+    /// A cylinder standing on the `y` axis with half spheres at both ends.
+    /// `height` is the length of the straight part, so the capsule
+    /// is `height + 2 * radius` tall
+    pub fn capsule(radius: f32, height: f32, segments: u32, rings: u32) -> Self {
+        Self::cached(
+            (
+                "capsule",
+                radius.to_bits(),
+                height.to_bits(),
+                segments,
+                rings,
+            ),
+            || {
+                let hh = height / 2.0;
+                let rings = rings.max(1);
+
+                let hemisphere = |offset: f32, from: f32| {
+                    (0..=rings).map(move |i| {
+                        let (sin, cos) = (from + FRAC_PI_2 * i as f32 / rings as f32).sin_cos();
+                        (
+                            cos * radius + offset,
+                            sin * radius,
+                            Vector3::new(sin, cos, sin),
+                        )
+                    })
+                };
+
+                let rows = hemisphere(hh, 0.0).chain(hemisphere(-hh, FRAC_PI_2));
+
+                lathe(rows, segments, height + radius * 2.0)
+            },
+        )
+    }
+}
+
+/// Hashable positions
+fn bits(points: &[Vector2]) -> Vec<[u32; 2]> {
+    points
+        .iter()
+        .map(|p| [p.x.to_bits(), p.y.to_bits()])
+        .collect()
+}
+
+/// Triangles from the first point of a convex outline
+fn convex_fan(outline: Vec<Vector2>) -> Tessellation {
+    let mut out = Tessellation {
+        positions: outline,
+        indices: Vec::new(),
+    };
+
+    for i in 1..out.positions.len().saturating_sub(1) as u32 {
+        out.triangle(0, i, i + 1);
+    }
+
+    out
+}
+
+/// White vertices facing `+z`, with the texture stretched over the bounds
+fn flat(shape: Tessellation) -> (Vec<Vertex>, Vec<u32>) {
+    let mut min = Vector2::splat(f32::MAX);
+    let mut max = Vector2::splat(f32::MIN);
+
+    for p in &shape.positions {
+        min = Vector2::new(min.x.min(p.x), min.y.min(p.y));
+        max = Vector2::new(max.x.max(p.x), max.y.max(p.y));
+    }
+
+    let extent = Vector2::new(
+        (max.x - min.x).max(f32::EPSILON),
+        (max.y - min.y).max(f32::EPSILON),
+    );
+
+    let vertices = shape
+        .positions
+        .iter()
+        .map(|&p| {
+            let uv = Vector2::new((p.x - min.x) / extent.x, (p.y - min.y) / extent.y);
+            Vertex::new(p.extend(0.0), Vector4::ones(), uv).with_normal(Vector3::new(0.0, 0.0, 1.0))
+        })
+        .collect();
+
+    (vertices, shape.indices)
+}
+
+/// Turns the triangles facing away from their normals around,
+/// so that the back faces that get culled are the inner ones
+fn solid(vertices: Vec<Vertex>, mut indices: Vec<u32>) -> (Vec<Vertex>, Vec<u32>) {
+    for triangle in indices.chunks_exact_mut(3) {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| &vertices[i as usize]);
+        let face = (b.position - a.position).cross(&(c.position - a.position));
+
+        if face.dot(&(a.normal + b.normal + c.normal)) < 0.0 {
+            triangle.swap(1, 2);
+        }
+    }
+
+    (vertices, indices)
+}
+
+/// Quads between consecutive rows of `columns + 1` vertices
+fn grid_indices(indices: &mut Vec<u32>, first: u32, columns: u32, rows: u32) {
+    let stride = columns + 1;
+
+    for row in 0..rows {
+        for column in 0..columns {
+            let a = first + row * stride + column;
+            let b = a + stride;
+
+            indices.extend([a, b, a + 1, a + 1, b, b + 1]);
+        }
+    }
+}
+
+/// Spins rows of `(y, radius, normal)` around the `y` axis. The `x` and `z`
+/// of a normal are scaled by the direction of each column
+fn lathe(
+    rows: impl Iterator<Item = (f32, f32, Vector3)>,
+    segments: u32,
+    height: f32,
+) -> (Vec<Vertex>, Vec<u32>) {
+    let segments = segments.max(3);
+    let rows: Vec<_> = rows.collect();
+    let top = rows.first().map_or(0.0, |row| row.0);
+
+    let mut vertices = Vec::with_capacity(rows.len() * (segments as usize + 1));
+    let mut indices = Vec::with_capacity(rows.len() * segments as usize * 6);
+
+    for &(y, radius, normal) in &rows {
+        for j in 0..=segments {
+            let (sin, cos) = (TAU * j as f32 / segments as f32).sin_cos();
+            let position = Vector3::new(cos * radius, y, sin * radius);
+            let normal = Vector3::new(normal.x * cos, normal.y, normal.z * sin).normalized();
+            let uv = Vector2::new(
+                j as f32 / segments as f32,
+                (top - y) / height.max(f32::EPSILON),
+            );
+
+            vertices.push(Vertex::new(position, Vector4::ones(), uv).with_normal(normal));
+        }
+    }
+
+    grid_indices(&mut indices, 0, segments, rows.len() as u32 - 1);
+
+    solid(vertices, indices)
+}
+
+/// The wall of a cylinder or a cone, from a top and a bottom `(y, radius)`.
+/// The `x` and `z` of `normal` are scaled by the direction of each column
+fn side(
+    vertices: &mut Vec<Vertex>,
+    indices: &mut Vec<u32>,
+    segments: u32,
+    rows: [(f32, f32); 2],
+    normal: Vector3,
+) {
+    let segments = segments.max(3);
+    let first = vertices.len() as u32;
+    let height = rows[0].0 - rows[1].0;
+
+    for (y, radius) in rows {
+        for j in 0..=segments {
+            let (sin, cos) = (TAU * j as f32 / segments as f32).sin_cos();
+            let position = Vector3::new(cos * radius, y, sin * radius);
+            let uv = Vector2::new(
+                j as f32 / segments as f32,
+                (rows[0].0 - y) / height.abs().max(f32::EPSILON),
+            );
+
+            vertices.push(
+                Vertex::new(position, Vector4::ones(), uv).with_normal(
+                    Vector3::new(normal.x * cos, normal.y, normal.z * sin).normalized(),
+                ),
+            );
+        }
+    }
+
+    grid_indices(indices, first, segments, 1);
+}
+
+/// A disc closing a cylinder or a cone at `y`, facing up or down
+fn cap(
+    vertices: &mut Vec<Vertex>,
+    indices: &mut Vec<u32>,
+    segments: u32,
+    radius: f32,
+    y: f32,
+    facing: f32,
+) {
+    let segments = segments.max(3);
+    let normal = Vector3::new(0.0, facing, 0.0);
+    let center = vertices.len() as u32;
+
+    vertices.push(
+        Vertex::new(
+            Vector3::new(0.0, y, 0.0),
+            Vector4::ones(),
+            Vector2::new(0.5, 0.5),
+        )
+        .with_normal(normal),
+    );
+
+    for j in 0..segments {
+        let (sin, cos) = (TAU * j as f32 / segments as f32).sin_cos();
+        let position = Vector3::new(cos * radius, y, sin * radius);
+        let uv = Vector2::new(0.5 + cos * 0.5, 0.5 + sin * 0.5);
+
+        vertices.push(Vertex::new(position, Vector4::ones(), uv).with_normal(normal));
+    }
+
+    for j in 0..segments {
+        indices.extend([center, center + 1 + j, center + 1 + (j + 1) % segments]);
+    }
+}
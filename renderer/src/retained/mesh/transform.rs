@@ -1,5 +1,5 @@
 use macros::With;
-use math::Vector3;
+use math::{Matrix4, Vector3};
 
 #[derive(Debug, Clone, Copy)]
 #[derive(With)]
@@ -23,3 +23,44 @@ impl Default for Transform3d {
         }
     }
 }
+
+impl Transform3d {
+    /// Splits a matrix into a position, a rotation and a scale.
+    ///
+    /// Shearing, which a parent with a non uniform scale can
+    /// give to a rotated child, cannot be represented and is lost
+    pub fn from_matrix(matrix: Matrix4) -> Self {
+        let m = |row, col| matrix.get(row, col);
+        let length = |col| Vector3::new(m(0, col), m(1, col), m(2, col)).length();
+
+        let mut scale = Vector3::new(length(0), length(1), length(2));
+
+        // A mirrored matrix is given a negative scale on x
+        if matrix.determinant() < 0.0 {
+            scale.x = -scale.x;
+        }
+
+        let r = |row, col: usize| {
+            if scale[col] == 0.0 {
+                0.0
+            } else {
+                m(row, col) / scale[col]
+            }
+        };
+
+        // The rotation is applied as `z * y * x`, like in the shader
+        let y = (-r(2, 0)).clamp(-1.0, 1.0).asin();
+
+        let (x, z) = if y.cos().abs() > 1e-6 {
+            (r(2, 1).atan2(r(2, 2)), r(1, 0).atan2(r(0, 0)))
+        } else {
+            (0.0, (-r(0, 1)).atan2(r(1, 1)))
+        };
+
+        Self {
+            position: matrix.translation(),
+            rotation: Vector3::new(x, y, z),
+            scale,
+        }
+    }
+}
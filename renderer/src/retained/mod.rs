@@ -5,7 +5,9 @@ pub mod mesh;
 
 use crate::{
     Camera,
-    retained::mesh::{Mesh, MeshBatch, MeshGpu},
+    layer::LayerFrame,
+    light::Lights,
+    retained::mesh::{BatchKey, GeometryBuffer, Mesh, MeshBatch, MeshGpu},
     retained_shader,
     traits::LayoutDescriptor,
     vertex::Vertex,
@@ -13,6 +15,7 @@ use crate::{
 use assets::AssetServerGuard;
 use globals::{consts, profiling};
 use logging::warn;
+use std::{cmp::Ordering, ops::Range, sync::Arc};
 use utils::{FastHashMap, Handle, SlotMap};
 
 pub use handle::*;
@@ -20,10 +23,75 @@ pub use text::*;
 
 pub struct RetainedRenderer {
     meshes: SlotMap<Mesh>,
-    batches: FastHashMap<u64, MeshBatch>,
-    mesh_to_batch: FastHashMap<u64, u64>, // handle hash -> geometry_id
+    batches: FastHashMap<BatchKey, MeshBatch>,
+    mesh_to_batch: FastHashMap<u64, BatchKey>, // handle hash -> batch key
 
-    pipeline: wgpu::RenderPipeline,
+    /// Draw calls of the frame, in the order they are made
+    draws: Vec<RetainedDraw>,
+
+    pipelines: RetainedPipelines,
+    pub(crate) wireframe: bool,
+}
+
+/// The meshes of a batch that share a z index, drawn in one call
+struct RetainedDraw {
+    key: BatchKey,
+    z_index: i32,
+    translucent: bool,
+    instances: Range<u32>,
+}
+
+/// With a depth buffer, opaque meshes write to it and translucent ones
+/// are drawn after them, back to front, only testing against it.
+///
+/// Both have a pipeline per [`crate::Shading`] variant
+struct RetainedPipelines {
+    opaque: [wgpu::RenderPipeline; 3],
+    translucent: [wgpu::RenderPipeline; 3],
+    wireframe: wgpu::RenderPipeline,
+}
+
+impl RetainedPipelines {
+    /// Fragment entries of the [`crate::Shading`] variants
+    const SHADING_ENTRIES: [&'static str; 3] = ["fs_main", "fs_lambert", "fs_blinn_phong"];
+
+    fn new(
+        surface_format: wgpu::TextureFormat,
+        bind_group_layouts: &[&wgpu::BindGroupLayout; 3],
+        depth: Option<wgpu::TextureFormat>,
+    ) -> Self {
+        let vertex_buffers = [Vertex::desc(), MeshGpu::desc()];
+
+        let builder = |label, fragment_entry| {
+            retained_shader()
+                .pipeline_builder()
+                .label(label)
+                .vertex_entry("vs_main")
+                .fragment_entry(fragment_entry)
+                .topology(wgpu::PrimitiveTopology::TriangleList)
+                .cull_mode(wgpu::Face::Back)
+                .blend_state(Some(wgpu::BlendState::ALPHA_BLENDING))
+                .depth_format(depth)
+        };
+
+        Self {
+            opaque: Self::SHADING_ENTRIES.map(|entry| {
+                builder("Retained Triangle Pipeline", entry).build(
+                    surface_format,
+                    bind_group_layouts,
+                    &vertex_buffers,
+                )
+            }),
+            translucent: Self::SHADING_ENTRIES.map(|entry| {
+                builder("Retained Translucent Pipeline", entry)
+                    .depth_write(false)
+                    .build(surface_format, bind_group_layouts, &vertex_buffers)
+            }),
+            wireframe: builder("Retained Wireframe Pipeline", "fs_main")
+                .polygon_mode(wgpu::PolygonMode::Line)
+                .build(surface_format, bind_group_layouts, &vertex_buffers),
+        }
+    }
 }
 
 impl RetainedRenderer {
@@ -31,49 +99,63 @@ impl RetainedRenderer {
     pub fn new(
         surface_format: wgpu::TextureFormat,
         camera: &Camera,
-        assets: &AssetServerGuard<'_>,
+        texture_bgl: &wgpu::BindGroupLayout,
+        lights_bgl: &wgpu::BindGroupLayout,
+        depth: Option<wgpu::TextureFormat>,
     ) -> Self {
-        let pipeline = retained_shader()
-            .pipeline_builder()
-            .label("Retained Triangle Pipeline")
-            .vertex_entry("vs_main")
-            .fragment_entry("fs_main")
-            .topology(wgpu::PrimitiveTopology::TriangleList)
-            .cull_mode(wgpu::Face::Back)
-            .blend_state(Some(wgpu::BlendState::ALPHA_BLENDING))
-            .build(
-                surface_format,
-                &[camera.bgl(), assets.atlas_bgl()],
-                &[Vertex::desc(), MeshGpu::desc()],
-            );
+        let bind_group_layouts = [camera.bgl(), texture_bgl, lights_bgl];
 
         Self {
             meshes: SlotMap::with_capacity(consts::MESH_INSTANCE_BASE_CAPACITY),
             batches: FastHashMap::default(),
             mesh_to_batch: FastHashMap::default(),
-            pipeline,
+            draws: Vec::new(),
+            pipelines: RetainedPipelines::new(surface_format, &bind_group_layouts, depth),
+            wireframe: false,
         }
     }
 
+    /// Rebuilds the pipelines for passes with or without a depth buffer
+    pub(crate) fn set_depth(
+        &mut self,
+        surface_format: wgpu::TextureFormat,
+        camera: &Camera,
+        texture_bgl: &wgpu::BindGroupLayout,
+        lights_bgl: &wgpu::BindGroupLayout,
+        depth: Option<wgpu::TextureFormat>,
+    ) {
+        let bind_group_layouts = [camera.bgl(), texture_bgl, lights_bgl];
+
+        self.pipelines = RetainedPipelines::new(surface_format, &bind_group_layouts, depth);
+    }
+
     #[inline]
     pub fn add_mesh(&mut self, mesh: Mesh) -> Handle<Mesh> {
-        let geometry_id = mesh.geometry().id;
+        let key = mesh.batch_key();
         let buffer = mesh.geometry().buffer.clone();
+        let handle = self.meshes.insert(mesh);
+
+        self.insert_in_batch(handle, key, buffer);
+
+        handle
+    }
 
+    #[inline]
+    fn insert_in_batch(
+        &mut self,
+        handle: Handle<Mesh>,
+        key: BatchKey,
+        buffer: Arc<GeometryBuffer>,
+    ) {
         let batch = self
             .batches
-            .entry(geometry_id)
+            .entry(key)
             .or_insert_with(|| MeshBatch::new(buffer));
 
-        let handle = self.meshes.insert(mesh);
-
         batch.handles.push(handle);
         batch.needs_rebuild = true;
 
-        self.mesh_to_batch
-            .insert(Self::handle_key(handle), geometry_id);
-
-        handle
+        self.mesh_to_batch.insert(Self::handle_key(handle), key);
     }
 
     #[inline]
@@ -90,14 +172,14 @@ impl RetainedRenderer {
     pub fn remove_mesh(&mut self, handle: Handle<Mesh>) {
         let key = Self::handle_key(handle);
 
-        if let Some(geometry_id) = self.mesh_to_batch.remove(&key) {
-            if let Some(batch) = self.batches.get_mut(&geometry_id) {
-                batch.handles.retain(|&h| h != handle);
-                batch.needs_rebuild = true;
+        if let Some(batch_key) = self.mesh_to_batch.remove(&key)
+            && let Some(batch) = self.batches.get_mut(&batch_key)
+        {
+            batch.handles.retain(|&h| h != handle);
+            batch.needs_rebuild = true;
 
-                if batch.handles.is_empty() {
-                    self.batches.remove(&geometry_id);
-                }
+            if batch.handles.is_empty() {
+                self.batches.remove(&batch_key);
             }
         }
 
@@ -110,13 +192,113 @@ impl RetainedRenderer {
         ((handle.index() as u64) << 32) | (handle.generation() as u64)
     }
 
-    pub(crate) fn present<'a>(
-        &'a mut self,
-        render_pass: &mut wgpu::RenderPass<'a>,
-        assets: &AssetServerGuard<'_>,
-    ) {
-        render_pass.set_pipeline(&self.pipeline);
-        profiling::record_pipeline_switches(1);
+    /// Moves the meshes whose material switched to or from a render target
+    /// to the batch of their new key
+    fn rebatch(&mut self) {
+        let mut moved = Vec::new();
+
+        for (&key, batch) in &mut self.batches {
+            let count = batch.handles.len();
+
+            batch.handles.retain(|&handle| {
+                let Some(mesh) = self.meshes.get(handle) else {
+                    return true;
+                };
+
+                if !mesh.is_dirty(Mesh::material_f()) || mesh.batch_key() == key {
+                    return true;
+                }
+
+                moved.push(handle);
+                false
+            });
+
+            batch.needs_rebuild |= batch.handles.len() != count;
+        }
+
+        for handle in moved {
+            let Some(mesh) = self.meshes.get(handle) else {
+                continue;
+            };
+            let (key, buffer) = (mesh.batch_key(), mesh.geometry().buffer.clone());
+
+            self.insert_in_batch(handle, key, buffer);
+        }
+
+        self.batches.retain(|_, batch| !batch.handles.is_empty());
+    }
+
+    /// Sorts the meshes of each batch by z index, translucent ones back to front,
+    /// and lists the draw calls by z index, opaque meshes first
+    fn sort(&mut self, camera: &Camera) {
+        let meshes = &self.meshes;
+        let mut translucent = Vec::new();
+
+        self.draws.clear();
+
+        for (&key, batch) in &mut self.batches {
+            let is_translucent = key.3;
+
+            let z_index = |handle: &Handle<Mesh>| meshes.get(*handle).map_or(0, Mesh::z_index);
+
+            let depth = |handle: &Handle<Mesh>| {
+                meshes
+                    .get(*handle)
+                    .map_or(0.0, |mesh| camera.view_depth(mesh.transform().position))
+            };
+
+            let order = |a: &Handle<Mesh>, b: &Handle<Mesh>| {
+                z_index(a)
+                    .cmp(&z_index(b))
+                    .then_with(|| match is_translucent {
+                        true => depth(b).total_cmp(&depth(a)),
+                        false => Ordering::Equal,
+                    })
+            };
+
+            // Stable, meshes at the same depth keep the order they were added in
+            let sorted = batch
+                .handles
+                .is_sorted_by(|a, b| order(a, b) != Ordering::Greater);
+
+            if !sorted {
+                batch.handles.sort_by(order);
+                batch.needs_rebuild = true;
+            }
+
+            let mut start = 0;
+
+            for run in batch.handles.chunk_by(|a, b| z_index(a) == z_index(b)) {
+                let end = start + run.len() as u32;
+                let draw = RetainedDraw {
+                    key,
+                    z_index: z_index(&run[0]),
+                    translucent: is_translucent,
+                    instances: start..end,
+                };
+
+                match is_translucent {
+                    true => translucent.push((depth(&run[0]), draw)),
+                    false => self.draws.push(draw),
+                }
+
+                start = end;
+            }
+        }
+
+        // Farthest first, then grouped by z index with a stable sort
+        translucent.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+        self.draws
+            .extend(translucent.into_iter().map(|(_, draw)| draw));
+        self.draws
+            .sort_by_key(|draw| (draw.z_index, draw.translucent));
+    }
+
+    /// Uploads the instances that changed and orders the draw calls of the frame
+    pub(crate) fn prepare(&mut self, camera: &Camera, assets: &AssetServerGuard<'_>) {
+        self.rebatch();
+        self.sort(camera);
 
         let mut writes = 0;
 
@@ -158,10 +340,55 @@ impl RetainedRenderer {
         }
 
         profiling::record_instance_writes(writes);
+    }
 
-        for batch in self.batches.values() {
-            if batch.handles.is_empty() {
+    /// Z indices of the meshes, in increasing order
+    #[inline]
+    pub(crate) fn z_indices(&self) -> impl Iterator<Item = i32> + '_ {
+        self.draws.iter().map(|draw| draw.z_index)
+    }
+
+    /// Draws the meshes that have `z_index`
+    pub(crate) fn draw(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        z_index: i32,
+        lights: &Lights,
+        frame: &LayerFrame<'_, '_>,
+    ) {
+        let draws = self.draws.iter().filter(|draw| draw.z_index == z_index);
+        let mut current = None;
+
+        for draw in draws {
+            let Some(batch) = self.batches.get(&draw.key) else {
                 continue;
+            };
+
+            let key = draw.key;
+            let shading = key.2 as usize;
+            let pipeline = match (self.wireframe, draw.translucent) {
+                (true, _) => &self.pipelines.wireframe,
+                (false, false) => &self.pipelines.opaque[shading],
+                (false, true) => &self.pipelines.translucent[shading],
+            };
+
+            if current.is_none() {
+                render_pass.set_bind_group(2, lights.bg(), &[]);
+            }
+
+            if current != Some(pipeline) {
+                render_pass.set_pipeline(pipeline);
+                profiling::record_pipeline_switches(1);
+                current = Some(pipeline);
+            }
+
+            match key.1 {
+                Some(target) => match frame.target(target) {
+                    Some(target) => render_pass.set_bind_group(1, target.bind_group(), &[]),
+                    // The target was removed or is being rendered, the meshes using it are hidden
+                    None => continue,
+                },
+                None => render_pass.set_bind_group(1, frame.assets.atlas_bg(), &[]),
             }
 
             let vertex_count = batch.buffer.vertex_buffer.len() as u32;
@@ -174,7 +401,7 @@ impl RetainedRenderer {
                 wgpu::IndexFormat::Uint32,
             );
 
-            render_pass.draw_indexed(0..index_count, 0, 0..batch.handles.len() as u32);
+            render_pass.draw_indexed(0..index_count, 0, draw.instances.clone());
             profiling::record_draw_call(vertex_count, index_count);
         }
     }
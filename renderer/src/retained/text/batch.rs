@@ -1,6 +1,7 @@
 use crate::retained::{GlyphGpu, Text};
 use globals::consts;
 use gpu::core::{GpuBuffer, GpuBufferBuilder};
+use std::ops::Range;
 use utils::Handle;
 
 pub struct TextBatch {
@@ -8,6 +9,8 @@ pub struct TextBatch {
     pub instance_buffer: GpuBuffer<GlyphGpu>,
     pub needs_rebuild: bool,
     pub total_glyphs: usize,
+    /// Instances of the texts sharing a z index, in increasing z index
+    pub runs: Vec<(i32, Range<u32>)>,
 }
 
 impl TextBatch {
@@ -22,6 +25,7 @@ impl TextBatch {
                 .build(),
             needs_rebuild: false,
             total_glyphs: 0,
+            runs: Vec::new(),
         }
     }
 }
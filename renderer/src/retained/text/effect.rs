@@ -0,0 +1,99 @@
+use crate::color::Color;
+use math::Vector2;
+
+/// Animation applied to every glyph of a [`super::Text`] each frame.
+///
+/// Effects only change the offsets and colors sent to the GPU,
+/// the text is never laid out again while animating.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GlyphEffect {
+    /// Glyphs bob up and down along a sine wave
+    Wave {
+        /// In pixels
+        amplitude: f32,
+        /// Phase difference between two consecutive glyphs, in radians
+        frequency: f32,
+        /// In radians per second
+        speed: f32,
+    },
+
+    /// Glyphs jitter randomly around their position
+    Shake {
+        /// Maximum offset, in pixels
+        intensity: f32,
+        /// How many times per second the offsets change
+        rate: f32,
+    },
+
+    /// Glyphs cycle through the hues, keeping the alpha of their color
+    Rainbow {
+        /// Hue turns per second
+        speed: f32,
+        /// Hue difference between two consecutive glyphs, in turns
+        spread: f32,
+    },
+}
+
+impl GlyphEffect {
+    /// Used by the `[wave]` markup tag
+    pub const WAVE: Self = Self::Wave {
+        amplitude: 3.0,
+        frequency: 0.6,
+        speed: 8.0,
+    };
+
+    /// Used by the `[shake]` markup tag
+    pub const SHAKE: Self = Self::Shake {
+        intensity: 1.5,
+        rate: 30.0,
+    };
+
+    /// Used by the `[rainbow]` markup tag
+    pub const RAINBOW: Self = Self::Rainbow {
+        speed: 0.5,
+        spread: 0.05,
+    };
+
+    /// Applies the effect to the glyph at `index`, `time` seconds after the text was added
+    #[inline]
+    pub(crate) fn apply(&self, index: usize, time: f32, offset: &mut Vector2, color: &mut Color) {
+        let i = index as f32;
+
+        match *self {
+            Self::Wave {
+                amplitude,
+                frequency,
+                speed,
+            } => {
+                offset.y += (time * speed + i * frequency).sin() * amplitude;
+            }
+
+            Self::Shake { intensity, rate } => {
+                let step = (time * rate) as u32;
+
+                offset.x += noise(index as u32, step, 0) * intensity;
+                offset.y += noise(index as u32, step, 1) * intensity;
+            }
+
+            Self::Rainbow { speed, spread } => {
+                *color = Color::hsv(time * speed + i * spread, 0.8, 1.0).with_alpha(color.a);
+            }
+        }
+    }
+}
+
+/// Cheap deterministic noise in `-1.0..=1.0`, so that shaking
+/// glyphs keep the same offset between two steps
+#[inline]
+fn noise(index: u32, step: u32, axis: u32) -> f32 {
+    let mut x = index
+        .wrapping_mul(0x9E37_79B9)
+        .wrapping_add(step.wrapping_mul(0x85EB_CA6B))
+        .wrapping_add(axis.wrapping_mul(0xC2B2_AE35));
+
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7FEB_352D);
+    x ^= x >> 15;
+
+    (x as f32 / u32::MAX as f32) * 2.0 - 1.0
+}
@@ -0,0 +1,197 @@
+//! Inline markup used by [`super::Text`] when markup is enabled.
+//!
+//! Supported tags:
+//! - `[color=red]...[/color]` or `[color=#ff8800]...[/color]`
+//! - `[b]...[/b]` and `[i]...[/i]`, using the bold / italic font of the text
+//! - `[size=20]...[/size]`
+//! - `[img=coin]`, where `coin` is an image named with `AssetServer::name_image`
+//! - `[wave]...[/wave]`, `[shake]...[/shake]` and `[rainbow]...[/rainbow]`,
+//!   see [`GlyphEffect`] for the parameters used
+//!
+//! `[[` produces a literal `[`. Unknown or malformed tags are kept as plain text.
+
+use crate::{color::Color, retained::text::effect::GlyphEffect};
+use utils::Label;
+
+#[derive(Default)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct RunStyle {
+    pub color: Option<Color>,
+    pub bold: bool,
+    pub italic: bool,
+    pub size: Option<u8>,
+    pub effect: Option<GlyphEffect>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum RunKind {
+    Text(String),
+    Image(Label),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TextRun {
+    pub kind: RunKind,
+    pub style: RunStyle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TagKind {
+    Color,
+    Bold,
+    Italic,
+    Size,
+    Effect(GlyphEffect),
+}
+
+enum Tag<'a> {
+    Open(TagKind, Option<&'a str>),
+    Close(TagKind),
+    Image(&'a str),
+}
+
+impl<'a> Tag<'a> {
+    fn parse(src: &'a str) -> Option<Self> {
+        if let Some(name) = src.strip_prefix('/') {
+            return Some(Self::Close(Self::kind(name)?));
+        }
+
+        let (name, value) = match src.split_once('=') {
+            Some((name, value)) => (name, Some(value.trim())),
+            None => (src, None),
+        };
+
+        if name == "img" {
+            return value.filter(|v| !v.is_empty()).map(Self::Image);
+        }
+
+        let kind = Self::kind(name)?;
+
+        match (kind, value) {
+            (TagKind::Color | TagKind::Size, None) => None,
+            (TagKind::Bold | TagKind::Italic | TagKind::Effect(_), Some(_)) => None,
+            _ => Some(Self::Open(kind, value)),
+        }
+    }
+
+    fn kind(name: &str) -> Option<TagKind> {
+        match name {
+            "color" => Some(TagKind::Color),
+            "b" => Some(TagKind::Bold),
+            "i" => Some(TagKind::Italic),
+            "size" => Some(TagKind::Size),
+            "wave" => Some(TagKind::Effect(GlyphEffect::WAVE)),
+            "shake" => Some(TagKind::Effect(GlyphEffect::SHAKE)),
+            "rainbow" => Some(TagKind::Effect(GlyphEffect::RAINBOW)),
+            _ => None,
+        }
+    }
+}
+
+struct Parser {
+    runs: Vec<TextRun>,
+    buffer: String,
+    style: RunStyle,
+    /// Open tags, with the style that was active before them
+    stack: Vec<(TagKind, RunStyle)>,
+}
+
+impl Parser {
+    fn flush(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        self.runs.push(TextRun {
+            kind: RunKind::Text(std::mem::take(&mut self.buffer)),
+            style: self.style,
+        });
+    }
+
+    /// Applies a tag, returns false if the tag is invalid
+    fn apply(&mut self, tag: Tag<'_>) -> bool {
+        match tag {
+            Tag::Open(kind, value) => {
+                let mut style = self.style;
+
+                match kind {
+                    TagKind::Color => match value.and_then(Color::parse) {
+                        Some(color) => style.color = Some(color),
+                        None => return false,
+                    },
+                    TagKind::Size => match value.and_then(|v| v.parse().ok()) {
+                        Some(size) => style.size = Some(size),
+                        None => return false,
+                    },
+                    TagKind::Bold => style.bold = true,
+                    TagKind::Italic => style.italic = true,
+                    TagKind::Effect(effect) => style.effect = Some(effect),
+                }
+
+                self.flush();
+                self.stack.push((kind, self.style));
+                self.style = style;
+            }
+
+            Tag::Close(kind) => {
+                let Some(i) = self.stack.iter().rposition(|(k, _)| *k == kind) else {
+                    return false;
+                };
+
+                self.flush();
+                self.style = self.stack[i].1;
+                self.stack.truncate(i);
+            }
+
+            Tag::Image(name) => {
+                self.flush();
+                self.runs.push(TextRun {
+                    kind: RunKind::Image(Label::new(name)),
+                    style: self.style,
+                });
+            }
+        }
+
+        true
+    }
+}
+
+/// Splits `src` into runs of text sharing the same style
+pub(crate) fn parse(src: &str) -> Vec<TextRun> {
+    let mut parser = Parser {
+        runs: Vec::new(),
+        buffer: String::new(),
+        style: RunStyle::default(),
+        stack: Vec::new(),
+    };
+
+    let mut rest = src;
+
+    while let Some(start) = rest.find('[') {
+        parser.buffer.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(after) = rest.strip_prefix("[[") {
+            parser.buffer.push('[');
+            rest = after;
+            continue;
+        }
+
+        let applied = rest
+            .find(']')
+            .map(|end| (end, Tag::parse(&rest[1..end])))
+            .and_then(|(end, tag)| Some((end, parser.apply(tag?))));
+
+        match applied {
+            Some((end, true)) => rest = &rest[end + 1..],
+            _ => {
+                parser.buffer.push('[');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    parser.buffer.push_str(rest);
+    parser.flush();
+    parser.runs
+}
@@ -1,14 +1,23 @@
 mod batch;
+mod effect;
+mod markup;
 mod renderer;
 
-use crate::{Transform3d, color::Color, traits::LayoutDescriptor};
-use assets::{AssetServerGuard, Font};
+use crate::{
+    Transform3d,
+    color::Color,
+    retained::text::markup::{RunKind, RunStyle, TextRun},
+    text_layout::{TextBounds, TextLayout},
+    traits::LayoutDescriptor,
+};
+use assets::{AssetServerGuard, FontFace};
 use fontdue::layout::{CoordinateSystem, Layout, TextStyle};
+use logging::warn;
 use macros::{Get, Set, With, track_dirty};
 use math::{Vector2, Vector3, Vector4};
 use std::mem::{self, offset_of};
-use utils::Handle;
 
+pub use effect::GlyphEffect;
 pub use renderer::TextRenderer;
 
 #[repr(C)]
@@ -81,21 +90,45 @@ pub struct Glyph {
     pub size: Vector2,
     pub uv_offset: Vector2,
     pub uv_scale: Vector2,
+    /// Color from markup, `None` uses the color of the text
+    pub color: Option<Color>,
+    /// Whether this is an inline image rather than a character
+    pub image: bool,
+    /// Effect from markup, `None` uses the effect of the text
+    pub effect: Option<GlyphEffect>,
 }
 
-#[track_dirty]
+/// Non-breaking space, used to reserve room for inline images
+const IMAGE_PLACEHOLDER: char = '\u{a0}';
+
+#[track_dirty(u32)]
 #[derive(Get, Set, With)]
 pub struct Text {
     #[get]
-    #[get(mut, also = self.tracker |= Self::content_f())]
-    #[set(also = self.tracker |= Self::content_f())]
+    #[get(mut, also = self.restart())]
+    #[set(also = self.restart())]
     #[with(into)]
     content: String,
 
     #[get(copied)]
     #[get(mut, also = self.tracker |= Self::content_f())]
+    #[set(into, also = self.tracker |= Self::content_f())]
+    font: FontFace,
+
+    /// Font used inside `[b]` tags, defaults to `font`
+    #[get(copied)]
+    bold_font: Option<FontFace>,
+
+    /// Font used inside `[i]` tags, defaults to `font`
+    #[get(copied)]
+    italic_font: Option<FontFace>,
+
+    /// Whether the content is parsed for inline markup,
+    /// see [`markup`] for the supported tags
+    #[get(copied)]
     #[set(also = self.tracker |= Self::content_f())]
-    font: Handle<Font>,
+    #[with]
+    markup: bool,
 
     #[get]
     #[get(prop = "position", ty = &Vector3, name = "position")]
@@ -160,22 +193,72 @@ pub struct Text {
     #[with(into)]
     color: Color,
 
-    layout: Layout,
+    /// Wrapping, alignment and line spacing
+    #[get]
+    #[get(mut, also = self.tracker |= Self::content_f())]
+    #[set(also = self.tracker |= Self::content_f())]
+    #[with]
+    layout: TextLayout,
+
+    /// Bounding box of the laid out text, relative to its position and
+    /// without the transform applied. Updated when the text is prepared
+    #[get(copied)]
+    bounds: TextBounds,
+
+    /// Effect applied to the glyphs that don't have one from markup
+    #[get(copied)]
+    #[set(into, also = self.tracker |= Self::color_f())]
+    #[with(into)]
+    effect: Option<GlyphEffect>,
+
+    /// Reveals the glyphs one by one, at this many glyphs per second.
+    /// Restarts every time the content changes
+    #[get(copied)]
+    #[set(into)]
+    #[with(into)]
+    typewriter: Option<f32>,
+
+    /// Texts with a greater z index are drawn over the ones with a lower
+    /// one, and over the shapes and meshes of the layer with a lower one
+    #[get(copied)]
+    #[set]
+    #[with]
+    z_index: i32,
+
+    /// Seconds since the text was added, drives the effects
+    elapsed: f32,
+    /// Glyphs revealed by the typewriter
+    revealed: f32,
+    /// Whether any glyph has an effect
+    animated: bool,
+
+    glyph_layout: Layout<usize>,
     glyphs: Vec<Glyph>,
     pub(crate) gpu_glyphs: Vec<GlyphGpu>,
 }
 
 impl Text {
-    pub fn new(font: Handle<Font>) -> Self {
+    pub fn new<F: Into<FontFace>>(font: F) -> Self {
         let mut text = Self {
             content: String::new(),
-            font,
+            font: font.into(),
+            bold_font: None,
+            italic_font: None,
+            markup: false,
             transform: Transform3d::default(),
             color: Color::White,
             glyphs: Vec::new(),
             gpu_glyphs: Vec::new(),
             tracker: 0,
-            layout: Layout::new(CoordinateSystem::PositiveYDown),
+            layout: TextLayout::default(),
+            bounds: TextBounds::default(),
+            effect: None,
+            typewriter: None,
+            z_index: 0,
+            elapsed: 0.0,
+            revealed: 0.0,
+            animated: false,
+            glyph_layout: Layout::new(CoordinateSystem::PositiveYDown),
         };
 
         text.set_all_dirty();
@@ -187,6 +270,112 @@ impl Text {
         self.gpu_glyphs.len()
     }
 
+    /// Whether the typewriter revealed every glyph,
+    /// always true when the typewriter is disabled
+    #[inline]
+    pub fn is_revealed(&self) -> bool {
+        self.visible_count() == self.gpu_glyphs.len()
+    }
+
+    /// Reveals every glyph at once
+    #[inline]
+    pub fn skip_typewriter(&mut self) {
+        self.revealed = f32::INFINITY;
+    }
+
+    /// Starts revealing the glyphs again
+    #[inline]
+    pub fn restart_typewriter(&mut self) {
+        self.revealed = 0.0;
+    }
+
+    #[inline]
+    fn restart(&mut self) {
+        self.tracker |= Self::content_f();
+        self.restart_typewriter();
+    }
+
+    #[inline]
+    fn visible_count(&self) -> usize {
+        match self.typewriter {
+            Some(_) => (self.revealed as usize).min(self.gpu_glyphs.len()),
+            None => self.gpu_glyphs.len(),
+        }
+    }
+
+    /// Glyphs to draw this frame
+    #[inline]
+    pub(crate) fn visible_glyphs(&self) -> &[GlyphGpu] {
+        &self.gpu_glyphs[..self.visible_count()]
+    }
+
+    /// Advances the effects and the typewriter by `delta` seconds,
+    /// returns true if the visible glyphs changed
+    pub(crate) fn animate(&mut self, delta: f32) -> bool {
+        let animated = self.animated || self.effect.is_some();
+        let revealing = self.typewriter.is_some() && !self.is_revealed();
+
+        if !animated && !revealing {
+            return false;
+        }
+
+        self.elapsed += delta;
+
+        let mut changed = false;
+
+        if let Some(speed) = self.typewriter {
+            let before = self.visible_count();
+
+            self.revealed += speed * delta;
+            changed |= self.visible_count() != before;
+        }
+
+        if animated {
+            self.update_gpu_data();
+            changed = true;
+        }
+
+        changed
+    }
+
+    #[inline]
+    pub fn set_bold_font<F: Into<FontFace>>(&mut self, font: F) {
+        self.bold_font = Some(font.into());
+        self.tracker |= Self::content_f();
+    }
+
+    #[inline]
+    pub fn with_bold_font<F: Into<FontFace>>(mut self, font: F) -> Self {
+        self.set_bold_font(font);
+        self
+    }
+
+    #[inline]
+    pub fn set_italic_font<F: Into<FontFace>>(&mut self, font: F) {
+        self.italic_font = Some(font.into());
+        self.tracker |= Self::content_f();
+    }
+
+    #[inline]
+    pub fn with_italic_font<F: Into<FontFace>>(mut self, font: F) -> Self {
+        self.set_italic_font(font);
+        self
+    }
+
+    /// Font face used for a run of text with the given style
+    fn face(&self, style: &RunStyle) -> FontFace {
+        let face = match (style.bold, style.italic) {
+            (true, _) => self.bold_font.unwrap_or(self.font),
+            (false, true) => self.italic_font.unwrap_or(self.font),
+            (false, false) => self.font,
+        };
+
+        match style.size {
+            Some(size) => FontFace::new(face.handle(), size),
+            None => face,
+        }
+    }
+
     #[inline]
     pub(crate) fn prepare(&mut self, assets: &AssetServerGuard<'_>) -> bool {
         let content_changed = self.is_dirty(Self::content_f());
@@ -197,60 +386,191 @@ impl Text {
             return false;
         }
 
-        if content_changed {
-            self.layout_glyphs(assets);
-        }
-
-        if content_changed || transform_changed || color_changed {
-            self.update_gpu_data();
+        // The font size may still need to be rasterized,
+        // keep the content dirty so that it's laid out again next frame
+        if content_changed && !self.layout_glyphs(assets) {
+            return false;
         }
 
+        self.update_gpu_data();
         self.clear_all_dirty();
 
         true
     }
 
+    /// Returns false if the glyphs of some font face are not available yet
     #[inline]
-    fn layout_glyphs(&mut self, assets: &AssetServerGuard<'_>) {
-        let font = assets.get_font(self.font);
+    fn layout_glyphs(&mut self, assets: &AssetServerGuard<'_>) -> bool {
+        let runs = match self.markup {
+            true => markup::parse(&self.content),
+            false => vec![TextRun {
+                kind: RunKind::Text(self.content.clone()),
+                style: RunStyle::default(),
+            }],
+        };
+
+        let faces = runs
+            .iter()
+            .map(|run| self.face(&run.style))
+            .collect::<Vec<_>>();
+
+        // Check every face, so that all the missing sizes are requested at once
+        let ready = faces
+            .iter()
+            .filter(|face| !assets.font_face_ready(**face))
+            .count()
+            == 0;
+
+        if !ready {
+            return false;
+        }
+
+        self.glyph_layout.reset(&self.layout.settings());
+
+        for (i, (run, face)) in runs.iter().zip(&faces).enumerate() {
+            let font = assets.get_font(face.handle());
+            let px = face.size(font) as f32;
+
+            match &run.kind {
+                RunKind::Text(text) => {
+                    self.glyph_layout
+                        .append(&[font.inner()], &TextStyle::with_user_data(text, px, 0, i));
+                }
 
-        self.layout.append(
-            &[font.inner()],
-            &TextStyle::new(&self.content, font.size() as f32, 0),
-        );
+                RunKind::Image(name) => {
+                    let Some(image) = assets.find_image(*name) else {
+                        warn!("Unknown image in text markup: {:?}", name);
+                        continue;
+                    };
+
+                    let size = assets.get_image(image).size.to_f32();
+                    let height = Self::image_height(font, px);
+                    let width = size.width * height / size.height;
+
+                    let advance = Self::placeholder_advance(font, px);
+                    let placeholder = IMAGE_PLACEHOLDER
+                        .to_string()
+                        .repeat((width / advance).ceil() as usize);
+
+                    self.glyph_layout.append(
+                        &[font.inner()],
+                        &TextStyle::with_user_data(&placeholder, px, 0, i),
+                    );
+                }
+            }
+        }
 
         self.glyphs.clear();
 
-        for glyph in self.layout.glyphs() {
+        let mut last_run = None;
+        // Room reserved by the placeholders of each inline image
+        let mut inline = Vec::new();
+
+        for glyph in self.glyph_layout.glyphs() {
+            let run = &runs[glyph.user_data];
+            let face = faces[glyph.user_data];
+
+            if let RunKind::Image(name) = run.kind {
+                // Only the first placeholder of the run draws the image
+                if last_run == Some(glyph.user_data) {
+                    continue;
+                }
+
+                last_run = Some(glyph.user_data);
+
+                let Some(image) = assets.find_image(name) else {
+                    continue;
+                };
+
+                let font = assets.get_font(face.handle());
+                let px = face.size(font) as f32;
+                let size = assets.get_image(image).size.to_f32();
+                let height = Self::image_height(font, px);
+                let width = size.width * height / size.height;
+                let advance = Self::placeholder_advance(font, px);
+                let reserved = (width / advance).ceil() * advance;
+                let (uv_x, uv_y, uv_w, uv_h, _, _) = assets.get_texture_uv(image);
+
+                // Placeholders have no height, so their y is the baseline
+                inline.push((
+                    Vector2::new(glyph.x, glyph.y - height),
+                    Vector2::new(reserved.max(width), height),
+                ));
+
+                self.glyphs.push(Glyph {
+                    local_position: Vector2::new(glyph.x, glyph.y - height),
+                    size: Vector2::new(width, height),
+                    uv_offset: Vector2::new(uv_x, uv_y),
+                    uv_scale: Vector2::new(uv_w, uv_h),
+                    color: None,
+                    image: true,
+                    effect: run.style.effect,
+                });
+
+                continue;
+            }
+
+            last_run = Some(glyph.user_data);
+
             if glyph.width == 0 || glyph.height == 0 {
                 continue;
             }
 
-            let (uv_x, uv_y, uv_w, uv_h, _, _) = assets.get_glyph_uv(self.font, glyph.parent);
+            let (uv_x, uv_y, uv_w, uv_h, _, _) = assets.get_glyph_uv(face, glyph.parent);
 
             self.glyphs.push(Glyph {
                 local_position: Vector2::new(glyph.x, glyph.y),
                 size: Vector2::new(glyph.width as f32, glyph.height as f32),
                 uv_offset: Vector2::new(uv_x, uv_y),
                 uv_scale: Vector2::new(uv_w, uv_h),
+                color: run.style.color,
+                image: false,
+                effect: run.style.effect,
             });
         }
 
+        self.bounds = TextBounds::of(&self.glyph_layout, &inline);
+        self.animated = self.glyphs.iter().any(|glyph| glyph.effect.is_some());
         self.gpu_glyphs
             .resize(self.glyphs.len(), GlyphGpu::default());
+
+        true
+    }
+
+    /// Width of each of the placeholders reserving room for inline images
+    #[inline]
+    fn placeholder_advance(font: &assets::Font, px: f32) -> f32 {
+        font.metrics(IMAGE_PLACEHOLDER, px).advance_width.max(1.0)
+    }
+
+    /// Inline images are as tall as the ascent of the font
+    #[inline]
+    fn image_height(font: &assets::Font, px: f32) -> f32 {
+        font.horizontal_line_metrics(px)
+            .map(|m| m.ascent)
+            .unwrap_or(px)
     }
 
     fn update_gpu_data(&mut self) {
-        let color: Vector4 = self.color.into();
+        // Images are not tinted, but still follow the opacity of the text
+        let image_color = Color::White.with_alpha(self.color.a);
         let pos = &self.transform.position;
         let rot = &self.transform.rotation;
         let scale = Vector2::new(self.transform.scale.x, self.transform.scale.y);
 
         for (i, glyph) in self.glyphs.iter().enumerate() {
-            let scaled_offset = Vector2::new(
-                glyph.local_position.x * scale.x,
-                glyph.local_position.y * scale.y,
-            );
+            let mut offset = glyph.local_position;
+            let mut color = match (glyph.image, glyph.color) {
+                (true, _) => image_color,
+                (false, Some(color)) => color.with_alpha(color.a * self.color.a),
+                (false, None) => self.color,
+            };
+
+            if let Some(effect) = glyph.effect.or(self.effect) {
+                effect.apply(i, self.elapsed, &mut offset, &mut color);
+            }
+
+            let scaled_offset = Vector2::new(offset.x * scale.x, offset.y * scale.y);
 
             self.gpu_glyphs[i] = GlyphGpu {
                 position: *pos, // Text pivot point
@@ -260,7 +580,7 @@ impl Text {
                 scale,
                 uv_offset: glyph.uv_offset,
                 uv_scale: glyph.uv_scale,
-                color,
+                color: color.into(),
             };
         }
     }
@@ -27,35 +27,57 @@ impl TextRenderer {
     pub fn new(
         surface_format: wgpu::TextureFormat,
         camera: &Camera,
-        assets: &AssetServerGuard<'_>,
+        texture_bgl: &wgpu::BindGroupLayout,
+        depth: Option<wgpu::TextureFormat>,
     ) -> Self {
         let quad_geometry = Geometry::unit_rect();
 
-        let pipeline = text_shader()
+        Self {
+            texts: SlotMap::with_capacity(256),
+            batches: FastHashMap::default(),
+            text_to_font: FastHashMap::default(),
+            quad_geometry: quad_geometry.buffer,
+            pipeline: Self::pipeline(surface_format, camera, texture_bgl, depth),
+        }
+    }
+
+    /// Text is drawn in order on top of what came before it, whatever the depth buffer
+    fn pipeline(
+        surface_format: wgpu::TextureFormat,
+        camera: &Camera,
+        texture_bgl: &wgpu::BindGroupLayout,
+        depth: Option<wgpu::TextureFormat>,
+    ) -> wgpu::RenderPipeline {
+        text_shader()
             .pipeline_builder()
             .label("Text Pipeline")
             .vertex_entry("vs_main")
             .fragment_entry("fs_main")
             .topology(wgpu::PrimitiveTopology::TriangleList)
             .blend_state(Some(wgpu::BlendState::ALPHA_BLENDING))
+            .depth_format(depth)
+            .depth_compare(wgpu::CompareFunction::Always)
+            .depth_write(false)
             .build(
                 surface_format,
-                &[camera.bgl(), assets.atlas_bgl()],
+                &[camera.bgl(), texture_bgl],
                 &[Vertex::desc(), GlyphGpu::desc()],
-            );
+            )
+    }
 
-        Self {
-            texts: SlotMap::with_capacity(256),
-            batches: FastHashMap::default(),
-            text_to_font: FastHashMap::default(),
-            quad_geometry: quad_geometry.buffer,
-            pipeline,
-        }
+    pub(crate) fn set_depth(
+        &mut self,
+        surface_format: wgpu::TextureFormat,
+        camera: &Camera,
+        texture_bgl: &wgpu::BindGroupLayout,
+        depth: Option<wgpu::TextureFormat>,
+    ) {
+        self.pipeline = Self::pipeline(surface_format, camera, texture_bgl, depth);
     }
 
     #[inline]
     pub fn add_text(&mut self, text: Text) -> Handle<Text> {
-        let font_key = text.font().index() as u64;
+        let font_key = text.font().handle().index() as u64;
 
         let batch = self
             .batches
@@ -111,28 +133,48 @@ impl TextRenderer {
         ((handle.index() as u64) << 32) | (handle.generation() as u64)
     }
 
-    #[inline]
-    pub(crate) fn present<'a>(
-        &'a mut self,
-        render_pass: &mut wgpu::RenderPass<'a>,
-        assets: &AssetServerGuard<'_>,
-    ) {
-        render_pass.set_pipeline(&self.pipeline);
-        profiling::record_pipeline_switches(1);
-
+    /// Animates the texts and uploads the glyphs of the batches that changed
+    pub(crate) fn prepare(&mut self, assets: &AssetServerGuard<'_>, delta: f32) {
         for batch in self.batches.values_mut() {
             if batch.handles.is_empty() {
                 continue;
             }
 
+            // Effects and typewriters only rewrite the instances, no layout involved
+            for &handle in &batch.handles {
+                if let Some(text) = self.texts.get_mut(handle) {
+                    batch.needs_rebuild |= text.animate(delta);
+                }
+            }
+
             if batch.needs_rebuild {
+                let texts = &self.texts;
+
+                // Stable, texts with the same z index keep the order they were added in
+                batch
+                    .handles
+                    .sort_by_key(|&h| texts.get(h).map_or(0, Text::z_index));
+
                 let mut all_glyphs: Vec<GlyphGpu> = Vec::new();
 
+                let mut pending = false;
+
+                batch.runs.clear();
+
                 for &handle in &batch.handles {
                     if let Some(text) = self.texts.get_mut(handle) {
                         text.prepare(assets);
+                        pending |= text.any_dirty();
+
+                        let start = all_glyphs.len() as u32;
+                        all_glyphs.extend_from_slice(text.visible_glyphs());
+                        let end = all_glyphs.len() as u32;
 
-                        all_glyphs.extend_from_slice(&text.gpu_glyphs);
+                        match batch.runs.last_mut() {
+                            Some((z_index, run)) if *z_index == text.z_index() => run.end = end,
+                            _ if start == end => {}
+                            _ => batch.runs.push((text.z_index(), start..end)),
+                        }
                     }
                 }
 
@@ -141,28 +183,46 @@ impl TextRenderer {
                 }
 
                 batch.total_glyphs = all_glyphs.len();
-                batch.needs_rebuild = false;
+                batch.needs_rebuild = pending;
 
                 profiling::record_instance_writes(all_glyphs.len() as u32);
             }
         }
+    }
 
-        for batch in self.batches.values() {
-            if batch.total_glyphs == 0 {
-                continue;
-            }
-
-            render_pass.set_vertex_buffer(0, self.quad_geometry.vertex_buffer.slice(..));
-            render_pass.set_vertex_buffer(1, batch.instance_buffer.slice(..));
-            render_pass.set_index_buffer(
-                self.quad_geometry.index_buffer.slice(..),
-                wgpu::IndexFormat::Uint32,
-            );
+    /// Z indices of the texts that have glyphs to draw
+    #[inline]
+    pub(crate) fn z_indices(&self) -> impl Iterator<Item = i32> + '_ {
+        self.batches
+            .values()
+            .flat_map(|batch| batch.runs.iter().map(|(z_index, _)| *z_index))
+    }
 
-            let index_count = self.quad_geometry.index_buffer.len() as u32;
-            render_pass.draw_indexed(0..index_count, 0, 0..batch.total_glyphs as u32);
+    /// Draws the texts that have `z_index`
+    pub(crate) fn draw(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        z_index: i32,
+        assets: &AssetServerGuard<'_>,
+    ) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(1, assets.atlas_bg(), &[]);
+        profiling::record_pipeline_switches(1);
 
-            profiling::record_draw_call(4, index_count);
+        for batch in self.batches.values() {
+            for (_, instances) in batch.runs.iter().filter(|(z, _)| *z == z_index) {
+                render_pass.set_vertex_buffer(0, self.quad_geometry.vertex_buffer.slice(..));
+                render_pass.set_vertex_buffer(1, batch.instance_buffer.slice(..));
+                render_pass.set_index_buffer(
+                    self.quad_geometry.index_buffer.slice(..),
+                    wgpu::IndexFormat::Uint32,
+                );
+
+                let index_count = self.quad_geometry.index_buffer.len() as u32;
+                render_pass.draw_indexed(0..index_count, 0, instances.clone());
+
+                profiling::record_draw_call(4, index_count);
+            }
         }
     }
 }
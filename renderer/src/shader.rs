@@ -34,6 +34,9 @@ pub struct PipelineBuilder<'a> {
     topology: wgpu::PrimitiveTopology,
     blend_state: Option<wgpu::BlendState>,
     polygon_mode: wgpu::PolygonMode,
+    depth_format: Option<wgpu::TextureFormat>,
+    depth_compare: wgpu::CompareFunction,
+    depth_write: bool,
 }
 
 impl<'a> PipelineBuilder<'a> {
@@ -47,6 +50,9 @@ impl<'a> PipelineBuilder<'a> {
             topology: wgpu::PrimitiveTopology::TriangleList,
             blend_state: Some(wgpu::BlendState::ALPHA_BLENDING),
             polygon_mode: wgpu::PolygonMode::Fill,
+            depth_format: None,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            depth_write: true,
         }
     }
 
@@ -80,12 +86,30 @@ impl<'a> PipelineBuilder<'a> {
         self
     }
 
-    #[allow(unused)]
     pub fn polygon_mode(mut self, mode: wgpu::PolygonMode) -> Self {
         self.polygon_mode = mode;
         self
     }
 
+    /// Format of the depth buffer of the passes the pipeline is used in,
+    /// `None` when they have none
+    pub fn depth_format(mut self, format: Option<wgpu::TextureFormat>) -> Self {
+        self.depth_format = format;
+        self
+    }
+
+    /// Only used with a depth format, `Always` draws in submission order
+    pub fn depth_compare(mut self, compare: wgpu::CompareFunction) -> Self {
+        self.depth_compare = compare;
+        self
+    }
+
+    /// Only used with a depth format
+    pub fn depth_write(mut self, write: bool) -> Self {
+        self.depth_write = write;
+        self
+    }
+
     pub fn build(
         self,
         format: wgpu::TextureFormat,
@@ -128,7 +152,13 @@ impl<'a> PipelineBuilder<'a> {
                 unclipped_depth: false,
                 conservative: false,
             },
-            depth_stencil: None,
+            depth_stencil: self.depth_format.map(|format| wgpu::DepthStencilState {
+                format,
+                depth_write_enabled: self.depth_write,
+                depth_compare: self.depth_compare,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
             multisample: wgpu::MultisampleState {
                 count: 1,
                 mask: !0,
@@ -0,0 +1,175 @@
+use crate::{color::Color, depth::DepthBuffer};
+use assets::Image;
+use macros::{Get, Set, With};
+use math::Size;
+use utils::Handle;
+
+/// What [`crate::Draw::image`] can draw, at its size in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageSource {
+    Image(Handle<Image>),
+    Target(Handle<RenderTarget>),
+}
+
+impl From<Handle<Image>> for ImageSource {
+    #[inline]
+    fn from(image: Handle<Image>) -> Self {
+        Self::Image(image)
+    }
+}
+
+impl From<Handle<RenderTarget>> for ImageSource {
+    #[inline]
+    fn from(target: Handle<RenderTarget>) -> Self {
+        Self::Target(target)
+    }
+}
+
+/// How a [`RenderTarget`] is created, see [`crate::Scene::create_target`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Get, Set, With)]
+pub struct RenderTargetConfig {
+    #[get(copied)]
+    #[set(into)]
+    #[with(into)]
+    size: Size<u32>,
+
+    /// Must be renderable and filterable, defaults to the format of the window
+    #[get(copied)]
+    #[set(into)]
+    #[with(into)]
+    format: Option<wgpu::TextureFormat>,
+
+    /// How the target is sampled when drawn, `Nearest` keeps pixel art crisp
+    #[get(copied)]
+    #[set]
+    #[with]
+    filter: wgpu::FilterMode,
+
+    #[get(copied)]
+    #[set(into)]
+    #[with(into)]
+    clear_color: Color,
+}
+
+impl RenderTargetConfig {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            size: Size::new(width, height),
+            format: None,
+            filter: wgpu::FilterMode::Linear,
+            clear_color: Color::rgba(0.0, 0.0, 0.0, 0.0),
+        }
+    }
+}
+
+/// Offscreen texture a layer is rendered into before the window,
+/// it can then be drawn like an image with [`crate::Draw::image`]
+/// or used by a [`crate::Material`] through [`crate::TextureKind::Target`].
+///
+/// A target can't sample itself, what draws it into its own layer is skipped.
+#[derive(Debug)]
+#[derive(Get, Set)]
+pub struct RenderTarget {
+    #[get(copied)]
+    size: Size<u32>,
+
+    #[get(copied)]
+    format: wgpu::TextureFormat,
+
+    #[get(copied)]
+    filter: wgpu::FilterMode,
+
+    /// Color the target is cleared with at the start of every frame
+    #[get(copied)]
+    #[set(into)]
+    clear_color: Color,
+
+    #[get(visibility = "pub(crate)")]
+    view: wgpu::TextureView,
+
+    #[get(visibility = "pub(crate)")]
+    bind_group: wgpu::BindGroup,
+
+    #[get]
+    texture: wgpu::Texture,
+
+    /// Only while the renderer has a depth buffer, see [`crate::Scene::set_depth_buffer`]
+    depth: Option<DepthBuffer>,
+}
+
+impl RenderTarget {
+    pub(crate) fn new(
+        config: &RenderTargetConfig,
+        format: wgpu::TextureFormat,
+        texture_bgl: &wgpu::BindGroupLayout,
+        depth: bool,
+    ) -> Self {
+        let device = gpu::device();
+        let size = Size::new(config.size.width.max(1), config.size.height.max(1));
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Render Target"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Render Target Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: config.filter,
+            min_filter: config.filter,
+            ..Default::default()
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Render Target Bind Group"),
+            layout: texture_bgl,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        Self {
+            size,
+            format,
+            filter: config.filter,
+            clear_color: config.clear_color,
+            view,
+            bind_group,
+            texture,
+            depth: depth.then(|| DepthBuffer::new(size)),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn set_depth(&mut self, depth: bool) {
+        self.depth = depth.then(|| DepthBuffer::new(self.size));
+    }
+
+    #[inline]
+    pub(crate) fn depth_attachment(&self) -> Option<wgpu::RenderPassDepthStencilAttachment<'_>> {
+        self.depth.as_ref().map(DepthBuffer::attachment)
+    }
+}
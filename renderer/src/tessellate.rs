@@ -0,0 +1,682 @@
+//! Turns polygons and polylines into triangles,
+//! shared by the retained geometry and immediate mode
+
+use macros::With;
+use math::Vector2;
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+/// Past this ratio of the miter length to the half width,
+/// a miter join is drawn as a bevel instead of a long spike
+const MITER_LIMIT: f32 = 4.0;
+
+/// Largest angle covered by one triangle of a round join
+const ROUND_STEP: f32 = PI / 12.0;
+
+/// How the outer corner between two segments of a line is filled
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineJoin {
+    /// Segments are extended until they meet,
+    /// sharp angles fall back to a bevel
+    #[default]
+    Miter,
+    /// An arc centered on the corner
+    Round,
+    /// The outer corners are linked by a straight edge
+    Bevel,
+}
+
+/// How the ends of a line that is not closed are drawn
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineCap {
+    /// The line stops at its end points
+    #[default]
+    Butt,
+    /// A half circle around each end point
+    Round,
+    /// The line goes half its width past its end points
+    Square,
+}
+
+/// How lines and outlines are drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(With)]
+pub struct Stroke {
+    /// Width of the line, a width of zero draws
+    /// hairlines of one pixel whatever the zoom
+    #[with]
+    pub width: f32,
+
+    #[with]
+    pub join: LineJoin,
+
+    #[with]
+    pub cap: LineCap,
+
+    /// Lengths of the dashes and of the gaps between them, solid when `None`
+    pub dash: Option<(f32, f32)>,
+
+    /// How far into the dash pattern lines start,
+    /// moving it over time makes the dashes crawl
+    #[with]
+    pub dash_offset: f32,
+}
+
+/// Hairlines, thick lines are drawn once a stroke with a width is set
+impl Default for Stroke {
+    fn default() -> Self {
+        Self::new(0.0)
+    }
+}
+
+impl Stroke {
+    #[inline]
+    pub fn new(width: f32) -> Self {
+        Self {
+            width,
+            join: LineJoin::Miter,
+            cap: LineCap::Butt,
+            dash: None,
+            dash_offset: 0.0,
+        }
+    }
+
+    #[inline]
+    pub fn with_dash(mut self, on: f32, off: f32) -> Self {
+        self.dash = Some((on, off));
+        self
+    }
+
+    /// Drawn with line primitives instead of triangles
+    #[inline]
+    pub(crate) fn is_hairline(&self) -> bool {
+        self.width <= 0.0
+    }
+}
+
+/// Triangles with positions in the `z = 0` plane,
+/// all wound counter-clockwise
+#[derive(Debug, Default)]
+pub(crate) struct Tessellation {
+    pub positions: Vec<Vector2>,
+    pub indices: Vec<u32>,
+}
+
+impl Tessellation {
+    #[inline]
+    pub fn vertex(&mut self, position: Vector2) -> u32 {
+        self.positions.push(position);
+        (self.positions.len() - 1) as u32
+    }
+
+    /// Adds a triangle, swapping two corners when it is clockwise
+    #[inline]
+    pub fn triangle(&mut self, a: u32, b: u32, c: u32) {
+        let [pa, pb, pc] = [a, b, c].map(|i| self.positions[i as usize]);
+
+        if (pb - pa).cross(&(pc - pa)) < 0.0 {
+            self.indices.extend([a, c, b]);
+        } else {
+            self.indices.extend([a, b, c]);
+        }
+    }
+
+    #[inline]
+    pub fn quad(&mut self, a: Vector2, b: Vector2, c: Vector2, d: Vector2) {
+        let [a, b, c, d] = [a, b, c, d].map(|p| self.vertex(p));
+
+        self.triangle(a, b, c);
+        self.triangle(a, c, d);
+    }
+
+    /// Triangles between a center and the points of an arc
+    pub fn fan(&mut self, center: Vector2, radius: f32, start: f32, sweep: f32) {
+        let steps = (sweep.abs() / ROUND_STEP).ceil().max(1.0) as u32;
+        let center_index = self.vertex(center);
+        let mut last = self.vertex(center + Vector2::from_angle(start) * radius);
+
+        for step in 1..=steps {
+            let angle = start + sweep * step as f32 / steps as f32;
+            let next = self.vertex(center + Vector2::from_angle(angle) * radius);
+
+            self.triangle(center_index, last, next);
+            last = next;
+        }
+    }
+
+    /// Triangles between `pivot` and the points of an arc around `center`,
+    /// from the vertex `from` to the vertex `to`
+    pub fn arc(&mut self, pivot: u32, center: Vector2, from: u32, to: u32, sweep: f32) {
+        let start = self.positions[from as usize] - center;
+        let (radius, start) = (start.length(), start.angle());
+        let steps = (sweep.abs() / ROUND_STEP).ceil().max(1.0) as u32;
+        let mut last = from;
+
+        for step in 1..steps {
+            let angle = start + sweep * step as f32 / steps as f32;
+            let next = self.vertex(center + Vector2::from_angle(angle) * radius);
+
+            self.triangle(pivot, last, next);
+            last = next;
+        }
+
+        self.triangle(pivot, last, to);
+    }
+}
+
+/// Points without the ones equal to the point before them
+pub(crate) fn dedup(points: &[Vector2], closed: bool) -> Vec<Vector2> {
+    let mut out: Vec<Vector2> = Vec::with_capacity(points.len());
+
+    for &point in points {
+        if out
+            .last()
+            .is_none_or(|last| (*last - point).length_squared() > f32::EPSILON)
+        {
+            out.push(point);
+        }
+    }
+
+    if closed && out.len() > 1 && (out[0] - out[out.len() - 1]).length_squared() <= f32::EPSILON {
+        out.pop();
+    }
+
+    out
+}
+
+/// Twice the signed area, positive for a counter-clockwise polygon
+pub(crate) fn signed_area(points: &[Vector2]) -> f32 {
+    (0..points.len())
+        .map(|i| points[i].cross(&points[(i + 1) % points.len()]))
+        .sum()
+}
+
+/// Enough segments for a circle to look round, the
+/// points are never further than a quarter pixel from the curve
+pub(crate) fn circle_segments(radius: f32) -> u32 {
+    let radius = radius.abs().max(1.0);
+    let step = (1.0 - 0.25 / radius).clamp(-1.0, 1.0).acos() * 2.0;
+
+    ((TAU / step).ceil() as u32).clamp(8, 512)
+}
+
+/// Points around a circle, counter-clockwise from `+x`
+pub(crate) fn circle_outline(center: Vector2, radius: f32, segments: u32) -> Vec<Vector2> {
+    (0..segments)
+        .map(|i| center + Vector2::from_angle(TAU * i as f32 / segments as f32) * radius)
+        .collect()
+}
+
+/// Points around a rectangle of half size `half` with corners rounded by `radius`,
+/// using `segments` triangles per corner
+pub(crate) fn rounded_rect_outline(
+    center: Vector2,
+    half: Vector2,
+    radius: f32,
+    segments: u32,
+) -> Vec<Vector2> {
+    let radius = radius.clamp(0.0, half.x.min(half.y));
+    let segments = segments.max(1);
+
+    let corners = [
+        (Vector2::new(half.x - radius, half.y - radius), 0.0),
+        (Vector2::new(-half.x + radius, half.y - radius), FRAC_PI_2),
+        (Vector2::new(-half.x + radius, -half.y + radius), PI),
+        (
+            Vector2::new(half.x - radius, -half.y + radius),
+            PI + FRAC_PI_2,
+        ),
+    ];
+
+    let mut outline = Vec::with_capacity(corners.len() * (segments as usize + 1));
+
+    for (corner, start) in corners {
+        for i in 0..=segments {
+            let angle = start + FRAC_PI_2 * i as f32 / segments as f32;
+            outline.push(center + corner + Vector2::from_angle(angle) * radius);
+        }
+    }
+
+    outline
+}
+
+/// Ear clipping of a simple polygon, convex or concave, in either winding.
+/// The indices refer to `points` and make counter-clockwise triangles
+pub(crate) fn triangulate(points: &[Vector2]) -> Vec<u32> {
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+
+    // Repeated points would make empty ears
+    remaining.dedup_by(|b, a| (points[*a] - points[*b]).length_squared() <= f32::EPSILON);
+
+    if remaining.len() > 1
+        && (points[remaining[0]] - points[remaining[remaining.len() - 1]]).length_squared()
+            <= f32::EPSILON
+    {
+        remaining.pop();
+    }
+
+    if remaining.len() < 3 {
+        return Vec::new();
+    }
+
+    let outline: Vec<Vector2> = remaining.iter().map(|&i| points[i]).collect();
+
+    if signed_area(&outline) < 0.0 {
+        remaining.reverse();
+    }
+
+    let mut indices = Vec::with_capacity((remaining.len() - 2) * 3);
+
+    while remaining.len() > 3 {
+        let len = remaining.len();
+
+        let ear = (0..len).find(|&i| {
+            let [a, b, c] = [(i + len - 1) % len, i, (i + 1) % len].map(|j| remaining[j]);
+            is_ear(points, &remaining, a, b, c)
+        });
+
+        // A self intersecting polygon can run out of ears,
+        // clipping any corner still makes progress
+        let i = ear.unwrap_or(0);
+        let [a, b, c] = [(i + len - 1) % len, i, (i + 1) % len].map(|j| remaining[j]);
+
+        indices.extend([a as u32, b as u32, c as u32]);
+        remaining.remove(i);
+    }
+
+    indices.extend(remaining.iter().map(|&i| i as u32));
+    indices
+}
+
+fn is_ear(points: &[Vector2], remaining: &[usize], a: usize, b: usize, c: usize) -> bool {
+    let [pa, pb, pc] = [points[a], points[b], points[c]];
+
+    // Reflex corners are not ears
+    if (pb - pa).cross(&(pc - pb)) <= 0.0 {
+        return false;
+    }
+
+    remaining
+        .iter()
+        .filter(|&&i| i != a && i != b && i != c)
+        .all(|&i| !in_triangle(points[i], pa, pb, pc))
+}
+
+#[inline]
+fn in_triangle(p: Vector2, a: Vector2, b: Vector2, c: Vector2) -> bool {
+    (b - a).cross(&(p - a)) >= 0.0
+        && (c - b).cross(&(p - b)) >= 0.0
+        && (a - c).cross(&(p - c)) >= 0.0
+}
+
+/// Triangles covering a line through `points` drawn with `style`.
+/// A closed line also links the last point to the first one
+pub(crate) fn stroke(points: &[Vector2], closed: bool, style: &Stroke) -> Tessellation {
+    let mut out = Tessellation::default();
+
+    for (piece, closed) in pieces(points, closed, style) {
+        stroke_path(&mut out, &piece, closed, style);
+    }
+
+    out
+}
+
+/// The parts of a line left by the dash pattern of `style`,
+/// with whether each of them is closed
+pub(crate) fn pieces(
+    points: &[Vector2],
+    closed: bool,
+    style: &Stroke,
+) -> Vec<(Vec<Vector2>, bool)> {
+    let mut points = dedup(points, closed);
+
+    match style.dash {
+        _ if points.len() < 2 => Vec::new(),
+        Some((on, off)) if on > 0.0 && off > 0.0 => {
+            if closed && points.len() > 2 {
+                points.push(points[0]);
+            }
+
+            dashes(&points, on, off, style.dash_offset)
+                .into_iter()
+                .map(|dash| (dedup(&dash, false), false))
+                .collect()
+        }
+        Some((on, _)) if on <= 0.0 => Vec::new(),
+        _ => vec![(points, closed)],
+    }
+}
+
+/// One outline around the whole line. Segments share the vertices of the corners
+/// between them, and the joins and caps only fill the gaps on the outside, so that
+/// nothing is covered twice and translucent strokes blend evenly
+fn stroke_path(out: &mut Tessellation, points: &[Vector2], closed: bool, style: &Stroke) {
+    let len = points.len();
+
+    if len < 2 || style.width <= 0.0 {
+        return;
+    }
+
+    let half = style.width / 2.0;
+    let closed = closed && len > 2;
+    let segments = if closed { len } else { len - 1 };
+
+    // Left and right vertices where the segments reaching
+    // and leaving each point end and start
+    let mut ends = Vec::with_capacity(len);
+    let mut starts = Vec::with_capacity(len);
+
+    for i in 0..len {
+        let p = points[i];
+        let prev = (closed || i > 0).then(|| points[(i + len - 1) % len]);
+        let next = (closed || i + 1 < len).then(|| points[(i + 1) % len]);
+
+        let (end, start) = match (prev, next) {
+            (Some(prev), Some(next)) => stroke_join(out, prev, p, next, half, style.join),
+            (None, Some(next)) => {
+                // The line goes the other way, its left is on the right of `outward`
+                let (left, right) = stroke_cap(out, p, p - next, half, style.cap);
+                ((right, left), (right, left))
+            }
+            (Some(prev), None) => {
+                let pair = stroke_cap(out, p, p - prev, half, style.cap);
+                (pair, pair)
+            }
+            (None, None) => unreachable!("a line has at least two points"),
+        };
+
+        ends.push(end);
+        starts.push(start);
+    }
+
+    for i in 0..segments {
+        let (l0, r0) = starts[i];
+        let (l1, r1) = ends[(i + 1) % len];
+
+        out.triangle(l0, r0, r1);
+        out.triangle(l0, r1, l1);
+    }
+}
+
+/// Closes the end of a line at `p` going `outward`, and returns the vertices
+/// of the end of the line, on the left of `outward` then on its right
+fn stroke_cap(
+    out: &mut Tessellation,
+    p: Vector2,
+    outward: Vector2,
+    half: f32,
+    cap: LineCap,
+) -> (u32, u32) {
+    let outward = outward.normalized();
+    let normal = outward.perp_ccw() * half;
+
+    let left = out.vertex(p + normal);
+    let right = out.vertex(p - normal);
+
+    match cap {
+        LineCap::Butt => {}
+        LineCap::Square => {
+            let end = p + outward * half;
+            let [end_left, end_right] = [end + normal, end - normal].map(|q| out.vertex(q));
+
+            out.triangle(left, right, end_right);
+            out.triangle(left, end_right, end_left);
+        }
+        LineCap::Round => {
+            let center = out.vertex(p);
+            out.arc(center, p, left, right, -PI);
+        }
+    }
+
+    (left, right)
+}
+
+/// Adds the vertices of the corner at `p` and fills the gap on its outer side.
+/// Returns the left and right vertices of the segment ending there, then
+/// of the one starting there, which share the vertex of the inner side
+fn stroke_join(
+    out: &mut Tessellation,
+    prev: Vector2,
+    p: Vector2,
+    next: Vector2,
+    half: f32,
+    join: LineJoin,
+) -> ((u32, u32), (u32, u32)) {
+    let d0 = (p - prev).normalized();
+    let d1 = (next - p).normalized();
+    let turn = d0.cross(&d1);
+
+    if turn.abs() <= f32::EPSILON && d0.dot(&d1) > 0.0 {
+        let normal = d0.perp_ccw() * half;
+        let pair = (out.vertex(p + normal), out.vertex(p - normal));
+
+        return (pair, pair);
+    }
+
+    // The outer side is on the right of a left turn
+    let side = if turn > 0.0 { -1.0 } else { 1.0 };
+    let n0 = d0.perp_ccw() * side;
+    let n1 = d1.perp_ccw() * side;
+    let mid = n0 + n1;
+
+    // Cosine of half the angle between the normals
+    let cos = if mid.length_squared() > f32::EPSILON {
+        mid.normalized().dot(&n0)
+    } else {
+        0.0
+    };
+
+    // Where the inner edges meet, kept within the
+    // shorter segment so that it doesn't fold over
+    let inner = match cos > f32::EPSILON {
+        true => {
+            let shortest = (p - prev).length().min((next - p).length());
+            let distance = (half / cos).min((half * half + shortest * shortest).sqrt());
+
+            p - mid.normalized() * distance
+        }
+        false => p,
+    };
+
+    let inner = out.vertex(inner);
+    let a = out.vertex(p + n0 * half);
+    let b = out.vertex(p + n1 * half);
+
+    match join {
+        LineJoin::Round => out.arc(inner, p, a, b, n0.signed_angle_to(&n1)),
+        LineJoin::Miter if cos > 1.0 / MITER_LIMIT => {
+            let tip = out.vertex(p + mid.normalized() * (half / cos));
+
+            out.triangle(inner, a, tip);
+            out.triangle(inner, tip, b);
+        }
+        LineJoin::Miter | LineJoin::Bevel => out.triangle(inner, a, b),
+    }
+
+    match side > 0.0 {
+        true => ((a, inner), (b, inner)),
+        false => ((inner, a), (inner, b)),
+    }
+}
+
+/// Splits a line into the pieces drawn by a dash pattern,
+/// `offset` is how far into the pattern the line starts
+fn dashes(points: &[Vector2], on: f32, off: f32, offset: f32) -> Vec<Vec<Vector2>> {
+    let period = on + off;
+    let mut phase = offset.rem_euclid(period);
+    let mut out = Vec::new();
+    let mut dash = Vec::new();
+
+    if phase < on {
+        dash.push(points[0]);
+    }
+
+    for segment in points.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+        let length = (b - a).length();
+        let direction = (b - a) / length;
+        let mut travelled = 0.0;
+
+        while travelled < length {
+            let drawing = phase < on;
+            let boundary = if drawing { on } else { period };
+            // Landing exactly on the boundary, so that rounding
+            // never leaves a step too small to make progress
+            if boundary - phase <= length - travelled {
+                travelled += boundary - phase;
+                phase = boundary;
+            } else {
+                phase += length - travelled;
+                travelled = length;
+            }
+
+            let p = a + direction * travelled;
+
+            if drawing {
+                dash.push(p);
+            }
+
+            if phase >= boundary {
+                if drawing {
+                    out.push(std::mem::take(&mut dash));
+                } else {
+                    phase = 0.0;
+                    dash.push(p);
+                }
+            }
+        }
+    }
+
+    if dash.len() > 1 {
+        out.push(dash);
+    }
+
+    out
+}
+
+/// Cuts the triangles where the affine function `value` crosses one of `cuts`, so that
+/// something piecewise linear in it can be interpolated exactly between the vertices
+pub(crate) fn split_bands(
+    shape: &Tessellation,
+    value: impl Fn(Vector2) -> f32,
+    cuts: &[f32],
+) -> Tessellation {
+    let mut out = Tessellation::default();
+
+    for triangle in shape.indices.chunks_exact(3) {
+        let corners: Vec<(Vector2, f32)> = triangle
+            .iter()
+            .map(|&i| {
+                let p = shape.positions[i as usize];
+                (p, value(p))
+            })
+            .collect();
+
+        let low = corners.iter().map(|c| c.1).fold(f32::MAX, f32::min);
+        let high = corners.iter().map(|c| c.1).fold(f32::MIN, f32::max);
+
+        let mut bounds = vec![low];
+        bounds.extend(cuts.iter().copied().filter(|&cut| cut > low && cut < high));
+        bounds.push(high);
+
+        for band in bounds.windows(2) {
+            let piece = clip_band(&corners, band[0], true);
+            let piece = clip_band(&piece, band[1], false);
+
+            if piece.len() < 3 {
+                continue;
+            }
+
+            // The pieces of a triangle are convex
+            let indices: Vec<u32> = piece.iter().map(|&(p, _)| out.vertex(p)).collect();
+
+            for i in 1..indices.len() - 1 {
+                out.triangle(indices[0], indices[i], indices[i + 1]);
+            }
+        }
+    }
+
+    out
+}
+
+/// Part of a convex polygon on one side of `limit`, the
+/// points carrying the value the polygon is clipped by
+fn clip_band(polygon: &[(Vector2, f32)], limit: f32, above: bool) -> Vec<(Vector2, f32)> {
+    let inside = |value: f32| {
+        if above {
+            value >= limit
+        } else {
+            value <= limit
+        }
+    };
+    let mut out = Vec::with_capacity(polygon.len() + 1);
+
+    for (i, &(p, value)) in polygon.iter().enumerate() {
+        let (q, next) = polygon[(i + 1) % polygon.len()];
+
+        if inside(value) {
+            out.push((p, value));
+        }
+
+        if inside(value) != inside(next) {
+            let t = (limit - value) / (next - value);
+            out.push((p + (q - p) * t, limit));
+        }
+    }
+
+    out
+}
+
+/// Splits each triangle in a grid of smaller ones with edges no longer than
+/// `max_edge`, for values that are not linear across the shape.
+///
+/// The edges are made longer when that would take more than `budget` triangles
+pub(crate) fn subdivide(shape: &Tessellation, max_edge: f32, budget: u32) -> Tessellation {
+    const MAX_STEPS: u32 = 32;
+
+    let triangles: Vec<[Vector2; 3]> = shape
+        .indices
+        .chunks_exact(3)
+        .map(|triangle| [0, 1, 2].map(|i| shape.positions[triangle[i] as usize]))
+        .collect();
+
+    let steps: Vec<u32> = triangles
+        .iter()
+        .map(|[a, b, c]| {
+            let longest = (b - a).length().max((c - b).length()).max((a - c).length());
+            ((longest / max_edge.max(f32::EPSILON)).ceil() as u32).clamp(1, MAX_STEPS)
+        })
+        .collect();
+
+    // A triangle split in `n` steps per edge gives `n * n` triangles
+    let total: u64 = steps.iter().map(|&n| u64::from(n * n)).sum();
+    let scale = (f64::from(budget) / total.max(1) as f64).sqrt().min(1.0) as f32;
+
+    let mut out = Tessellation::default();
+
+    for (&[a, b, c], &steps) in triangles.iter().zip(&steps) {
+        let steps = ((steps as f32 * scale) as u32).max(1);
+        let step = 1.0 / steps as f32;
+
+        // Rows along `a -> b`, each one shorter than the one before
+        let base = out.positions.len() as u32;
+        let index = |i: u32, j: u32| base + i * (steps + 1) - i * i.saturating_sub(1) / 2 + j;
+
+        for i in 0..=steps {
+            for j in 0..=steps - i {
+                out.vertex(a + (b - a) * (i as f32 * step) + (c - a) * (j as f32 * step));
+            }
+        }
+
+        for i in 0..steps {
+            for j in 0..steps - i {
+                out.triangle(index(i, j), index(i + 1, j), index(i, j + 1));
+
+                if j + 1 < steps - i {
+                    out.triangle(index(i + 1, j), index(i + 1, j + 1), index(i, j + 1));
+                }
+            }
+        }
+    }
+
+    out
+}
@@ -0,0 +1,178 @@
+use assets::Font;
+use fontdue::layout::{self, CoordinateSystem, Layout, LayoutSettings, TextStyle};
+use macros::{Get, Set, With};
+use math::Vector2;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HorizontalAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WrapStyle {
+    /// Lines are broken between words
+    #[default]
+    Word,
+    /// Lines are broken at any character
+    Letter,
+}
+
+/// How text is laid out, shared by retained [`crate::Text`] and [`crate::Draw::text`].
+///
+/// Alignment is relative to the box defined by `max_width` and `max_height`,
+/// so `horizontal_align` does nothing without a `max_width`, and
+/// `vertical_align` does nothing without a `max_height`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Get, Set, With)]
+pub struct TextLayout {
+    /// Lines longer than this are wrapped
+    #[get(copied)]
+    #[set(into)]
+    #[with(into)]
+    max_width: Option<f32>,
+
+    #[get(copied)]
+    #[set(into)]
+    #[with(into)]
+    max_height: Option<f32>,
+
+    #[get(copied)]
+    #[set]
+    #[with]
+    horizontal_align: HorizontalAlign,
+
+    #[get(copied)]
+    #[set]
+    #[with]
+    vertical_align: VerticalAlign,
+
+    /// Multiplier of the height of each line, `1.0` being the font's line height
+    #[get(copied)]
+    #[set]
+    #[with]
+    line_spacing: f32,
+
+    #[get(copied)]
+    #[set]
+    #[with]
+    wrap: WrapStyle,
+}
+
+impl Default for TextLayout {
+    fn default() -> Self {
+        Self {
+            max_width: None,
+            max_height: None,
+            horizontal_align: HorizontalAlign::Left,
+            vertical_align: VerticalAlign::Top,
+            line_spacing: 1.0,
+            wrap: WrapStyle::Word,
+        }
+    }
+}
+
+impl TextLayout {
+    #[inline]
+    pub(crate) fn settings(&self) -> LayoutSettings {
+        LayoutSettings {
+            max_width: self.max_width,
+            max_height: self.max_height,
+            horizontal_align: match self.horizontal_align {
+                HorizontalAlign::Left => layout::HorizontalAlign::Left,
+                HorizontalAlign::Center => layout::HorizontalAlign::Center,
+                HorizontalAlign::Right => layout::HorizontalAlign::Right,
+            },
+            vertical_align: match self.vertical_align {
+                VerticalAlign::Top => layout::VerticalAlign::Top,
+                VerticalAlign::Middle => layout::VerticalAlign::Middle,
+                VerticalAlign::Bottom => layout::VerticalAlign::Bottom,
+            },
+            line_height: self.line_spacing,
+            wrap_style: match self.wrap {
+                WrapStyle::Word => layout::WrapStyle::Word,
+                WrapStyle::Letter => layout::WrapStyle::Letter,
+            },
+            ..LayoutSettings::default()
+        }
+    }
+
+    /// Returns the bounding box of `text` rendered with `font` at `size` pixels,
+    /// relative to the position the text would be drawn at.
+    ///
+    /// Only the font metrics are needed, so this works
+    /// before the glyphs of that size are rasterized.
+    pub fn measure(&self, text: &str, font: &Font, size: u8) -> TextBounds {
+        let mut layout = Layout::<()>::new(CoordinateSystem::PositiveYDown);
+
+        layout.reset(&self.settings());
+        layout.append(&[font.inner()], &TextStyle::new(text, size as f32, 0));
+
+        TextBounds::of(&layout, &[])
+    }
+}
+
+/// Axis aligned box around laid out text
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TextBounds {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl TextBounds {
+    /// `inline` are the positions and sizes of the inline images,
+    /// their placeholders have no width in the layout
+    pub(crate) fn of<U: Copy + Clone>(layout: &Layout<U>, inline: &[(Vector2, Vector2)]) -> Self {
+        let Some(lines) = layout.lines() else {
+            return Self::default();
+        };
+
+        let (first, last) = (lines[0], lines[lines.len() - 1]);
+
+        let glyphs = layout
+            .glyphs()
+            .iter()
+            .filter(|glyph| glyph.width > 0)
+            .map(|glyph| (glyph.x, glyph.x + glyph.width as f32));
+        let images = inline.iter().map(|(pos, size)| (pos.x, pos.x + size.x));
+
+        let (min_x, max_x) = glyphs
+            .chain(images)
+            .fold((f32::MAX, f32::MIN), |(min, max), (left, right)| {
+                (min.min(left), max.max(right))
+            });
+
+        // Only whitespace
+        if min_x > max_x {
+            return Self::default();
+        }
+
+        let top = inline
+            .iter()
+            .map(|(pos, _)| pos.y)
+            .fold(first.baseline_y - first.max_ascent, f32::min);
+        let bottom = inline
+            .iter()
+            .map(|(pos, size)| pos.y + size.y)
+            .fold(last.baseline_y - last.min_descent, f32::max);
+
+        Self {
+            x: min_x,
+            y: top,
+            width: max_x - min_x,
+            height: bottom - top,
+        }
+    }
+}
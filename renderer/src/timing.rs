@@ -0,0 +1,280 @@
+use globals::profiling::{self, GpuTimings};
+use logging::warn;
+use std::sync::{
+    Arc,
+    atomic::{AtomicU8, Ordering},
+};
+
+/// Timestamps written in a single frame, two per measured section
+const MAX_QUERIES: u32 = 128;
+
+/// Frames that can be waiting for their timestamps at once,
+/// frames started while all of them are busy are not measured
+const READBACKS: usize = 3;
+
+const QUERY_BYTES: u64 = wgpu::QUERY_SIZE as u64;
+
+// Readback states
+const FREE: u8 = 0;
+const MAPPING: u8 = 1;
+const READY: u8 = 2;
+
+/// Part of the frame measured by a pair of timestamps
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Section {
+    Frame,
+    World,
+    Ui,
+    Custom,
+    Target,
+    Immediate,
+    Retained,
+    Text,
+    Post,
+}
+
+/// Begin query of a section, given back to [`GpuTimer::end`]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SectionToken(Option<u32>);
+
+struct Readback {
+    buffer: wgpu::Buffer,
+    sections: Vec<(Section, u32)>,
+    queries: u32,
+    state: Arc<AtomicU8>,
+}
+
+struct Queries {
+    set: wgpu::QuerySet,
+    resolve: wgpu::Buffer,
+    readbacks: Vec<Readback>,
+    /// Nanoseconds per timestamp tick
+    period: f32,
+    inside_passes: bool,
+    /// Sections of the frame being recorded
+    sections: Vec<(Section, u32)>,
+    next_query: u32,
+    /// Readback filled by this frame, mapped once submitted
+    pending: Option<usize>,
+}
+
+/// Measures the gpu time of the frame with timestamp queries,
+/// reading the results back asynchronously a few frames later
+pub(crate) struct GpuTimer {
+    queries: Option<Queries>,
+}
+
+impl GpuTimer {
+    pub(crate) fn new() -> Self {
+        if !gpu::has_features(wgpu::Features::TIMESTAMP_QUERY) {
+            warn!("The adapter doesn't support timestamp queries, gpu timings are disabled");
+            return Self { queries: None };
+        }
+
+        let device = gpu::device();
+        let size = MAX_QUERIES as u64 * QUERY_BYTES;
+
+        let set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Gpu Timer Queries"),
+            ty: wgpu::QueryType::Timestamp,
+            count: MAX_QUERIES,
+        });
+
+        let resolve = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Gpu Timer Resolve Buffer"),
+            size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let readbacks = (0..READBACKS)
+            .map(|_| Readback {
+                buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Gpu Timer Readback Buffer"),
+                    size,
+                    usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }),
+                sections: Vec::new(),
+                queries: 0,
+                state: Arc::new(AtomicU8::new(FREE)),
+            })
+            .collect();
+
+        Self {
+            queries: Some(Queries {
+                set,
+                resolve,
+                readbacks,
+                period: gpu::queue().get_timestamp_period(),
+                inside_passes: gpu::has_features(wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES),
+                sections: Vec::new(),
+                next_query: 0,
+                pending: None,
+            }),
+        }
+    }
+
+    /// Starts a frame, must be called before any section is measured
+    pub(crate) fn begin_frame(&mut self) {
+        let Some(queries) = self.queries.as_mut() else {
+            return;
+        };
+
+        queries.sections.clear();
+        queries.sections.push((Section::Frame, 0));
+        queries.next_query = 2;
+    }
+
+    /// Writes measuring the frame from the beginning of the first render pass
+    /// of the window to the end of the last one, which can be the same
+    pub(crate) fn frame_writes(
+        &self,
+        first: bool,
+        last: bool,
+    ) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        let queries = self.queries.as_ref()?;
+
+        Some(wgpu::RenderPassTimestampWrites {
+            query_set: &queries.set,
+            beginning_of_pass_write_index: first.then_some(0),
+            end_of_pass_write_index: last.then_some(1),
+        })
+    }
+
+    /// Writes the begin timestamp of a section inside the render pass
+    pub(crate) fn begin(&mut self, pass: &mut wgpu::RenderPass, section: Section) -> SectionToken {
+        let Some(queries) = self.queries.as_mut() else {
+            return SectionToken(None);
+        };
+
+        if !queries.inside_passes || queries.next_query + 2 > MAX_QUERIES {
+            return SectionToken(None);
+        }
+
+        let index = queries.next_query;
+
+        queries.next_query += 2;
+        queries.sections.push((section, index));
+        pass.write_timestamp(&queries.set, index);
+
+        SectionToken(Some(index))
+    }
+
+    #[inline]
+    pub(crate) fn end(&mut self, pass: &mut wgpu::RenderPass, token: SectionToken) {
+        if let (Some(queries), Some(index)) = (self.queries.as_ref(), token.0) {
+            pass.write_timestamp(&queries.set, index + 1);
+        }
+    }
+
+    /// Copies the timestamps of the frame to a free readback buffer,
+    /// must be called after the render pass ended
+    pub(crate) fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let Some(queries) = self.queries.as_mut() else {
+            return;
+        };
+
+        let free = queries
+            .readbacks
+            .iter()
+            .position(|readback| readback.state.load(Ordering::Acquire) == FREE);
+
+        // The gpu is too far behind, this frame is skipped
+        let Some(free) = free else {
+            return;
+        };
+
+        let count = queries.next_query;
+        let readback = &mut queries.readbacks[free];
+
+        encoder.resolve_query_set(&queries.set, 0..count, &queries.resolve, 0);
+        encoder.copy_buffer_to_buffer(
+            &queries.resolve,
+            0,
+            &readback.buffer,
+            0,
+            count as u64 * QUERY_BYTES,
+        );
+
+        readback.queries = count;
+        readback.sections.clone_from(&queries.sections);
+        readback.state.store(MAPPING, Ordering::Release);
+        queries.pending = Some(free);
+    }
+
+    /// Maps the buffer resolved this frame, must be called after submitting
+    pub(crate) fn after_submit(&mut self) {
+        let Some(queries) = self.queries.as_mut() else {
+            return;
+        };
+
+        let Some(pending) = queries.pending.take() else {
+            return;
+        };
+
+        let readback = &queries.readbacks[pending];
+        let state = Arc::clone(&readback.state);
+        let size = readback.queries as u64 * QUERY_BYTES;
+
+        readback
+            .buffer
+            .map_async(wgpu::MapMode::Read, 0..size, move |result| {
+                state.store(if result.is_ok() { READY } else { FREE }, Ordering::Release);
+            });
+    }
+
+    /// Reads the frames the gpu finished with, and records the latest one
+    pub(crate) fn collect(&mut self) {
+        let Some(queries) = self.queries.as_mut() else {
+            profiling::record_gpu_timings(GpuTimings::default());
+            return;
+        };
+
+        // Only to run the map callbacks, it doesn't wait
+        let _ = gpu::device().poll(wgpu::PollType::Poll);
+
+        for readback in &mut queries.readbacks {
+            if readback.state.load(Ordering::Acquire) != READY {
+                continue;
+            }
+
+            let size = readback.queries as u64 * QUERY_BYTES;
+            let timestamps = {
+                let view = readback.buffer.slice(0..size).get_mapped_range();
+
+                view.chunks_exact(QUERY_BYTES as usize)
+                    .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+                    .collect::<Vec<_>>()
+            };
+
+            readback.buffer.unmap();
+            readback.state.store(FREE, Ordering::Release);
+
+            let mut timings = GpuTimings {
+                supported: true,
+                ..Default::default()
+            };
+
+            for &(section, index) in &readback.sections {
+                let index = index as usize;
+                let ticks = timestamps[index + 1].saturating_sub(timestamps[index]);
+                let ms = ticks as f32 * queries.period / 1_000_000.0;
+
+                match section {
+                    Section::Frame => timings.frame += ms,
+                    Section::World => timings.world += ms,
+                    Section::Ui => timings.ui += ms,
+                    Section::Custom => timings.custom += ms,
+                    Section::Target => timings.target += ms,
+                    Section::Immediate => timings.immediate += ms,
+                    Section::Retained => timings.retained += ms,
+                    Section::Text => timings.text += ms,
+                    Section::Post => timings.post += ms,
+                }
+            }
+
+            profiling::record_gpu_timings(timings);
+        }
+    }
+}
@@ -15,6 +15,8 @@ pub struct Vertex {
     pub position: Vector3,
     pub color: Vector4,
     pub uv: Vector2,
+    /// Only used by lit meshes, computed by [`crate::Geometry`] when left to zero
+    pub normal: Vector3,
 }
 
 impl Vertex {
@@ -24,8 +26,15 @@ impl Vertex {
             position,
             color,
             uv,
+            normal: Vector3::zeros(),
         }
     }
+
+    #[inline]
+    pub fn with_normal(mut self, normal: Vector3) -> Self {
+        self.normal = normal;
+        self
+    }
 }
 
 impl Hash for Vertex {
@@ -41,6 +50,10 @@ impl Hash for Vertex {
 
         self.uv.x.to_bits().hash(state);
         self.uv.y.to_bits().hash(state);
+
+        self.normal.x.to_bits().hash(state);
+        self.normal.y.to_bits().hash(state);
+        self.normal.z.to_bits().hash(state);
     }
 }
 
@@ -66,6 +79,15 @@ impl LayoutDescriptor for Vertex {
                     shader_location: 2,
                     format: wgpu::VertexFormat::Float32x2,
                 },
+                // Far from the others, instance data starts right after the uv
+                wgpu::VertexAttribute {
+                    offset: (mem::size_of::<Vector3>()
+                        + mem::size_of::<Vector4>()
+                        + mem::size_of::<Vector2>())
+                        as wgpu::BufferAddress,
+                    shader_location: 15,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
             ],
         }
     }
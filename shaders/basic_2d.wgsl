@@ -3,6 +3,7 @@ struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) color: vec4<f32>,
     @location(2) uv_coords: vec2<f32>,
+    @location(15) normal: vec3<f32>,
 }
 
 // Instance data (per-instance data)
@@ -13,6 +14,8 @@ struct InstanceInput {
     @location(6) instance_color: vec4<f32>,
     @location(7) uv_offset: vec2<f32>,
     @location(8) uv_scale: vec2<f32>,
+    // Specular strength and shininess
+    @location(9) shading: vec2<f32>,
 }
 
 // Vertex shader output / Fragment shader input
@@ -20,6 +23,9 @@ struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
     @location(1) uv_coords: vec2<f32>,
+    @location(2) world_position: vec3<f32>,
+    @location(3) normal: vec3<f32>,
+    @location(4) shading: vec2<f32>,
 }
 
 // Camera view-projection matrix
@@ -33,6 +39,32 @@ var texture_atlas: texture_2d<f32>;
 @group(1) @binding(1)
 var texture_sampler: sampler;
 
+struct DirectionalLight {
+    direction: vec4<f32>,
+    color: vec4<f32>,
+}
+
+struct PointLight {
+    // Range in w
+    position: vec4<f32>,
+    color: vec4<f32>,
+}
+
+// Must match MAX_DIRECTIONAL_LIGHTS and MAX_POINT_LIGHTS
+struct Lights {
+    ambient: vec4<f32>,
+    // Direction towards the camera when w is 0
+    camera_position: vec4<f32>,
+    // Directional and point lights
+    counts: vec4<u32>,
+    directional: array<DirectionalLight, 4>,
+    point: array<PointLight, 32>,
+}
+
+// Lights of the layer, only used by the lit variants
+@group(2) @binding(0)
+var<uniform> lights: Lights;
+
 fn rotation_x(angle: f32) -> mat4x4<f32> {
     let c = cos(angle);
     let s = sin(angle);
@@ -112,14 +144,103 @@ fn vs_main(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
     // Remap UV coordinates to atlas region
     out.uv_coords = vertex.uv_coords * instance.uv_scale + instance.uv_offset;
 
+    // Normals are scaled by the cofactors of the scale, which is the inverse
+    // up to a factor and still works with a flat scale, such as a 2d mesh
+    let scale = instance.instance_scale;
+    let cofactor = vec3<f32>(scale.y * scale.z, scale.x * scale.z, scale.x * scale.y);
+    let normal = select(vertex.normal * cofactor, vertex.normal, all(cofactor == vec3<f32>(0.0)));
+
+    out.world_position = world_position.xyz;
+    out.normal = (rotation_mat * vec4<f32>(normal, 0.0)).xyz;
+    out.shading = instance.shading;
+
     return out;
 }
 
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+fn base_color(in: VertexOutput) -> vec4<f32> {
     // Sample the texture
     let texture_color = textureSample(texture_atlas, texture_sampler, in.uv_coords);
 
     // Multiply texture color with vertex/instance color
     return texture_color * in.color;
 }
+
+// Lit color, with Blinn-Phong highlights on top of it only with `specular`
+fn lighting(in: VertexOutput, color: vec3<f32>, specular: bool) -> vec3<f32> {
+    let normal = normalize(in.normal);
+    let eye = lights.camera_position;
+    let view_dir = select(normalize(eye.xyz - in.world_position), normalize(eye.xyz), eye.w == 0.0);
+
+    var diffuse = lights.ambient.rgb;
+    var highlights = vec3<f32>(0.0);
+
+    for (var i = 0u; i < lights.counts.x; i++) {
+        let directional = lights.directional[i];
+        let amount = shade(normal, -directional.direction.xyz, view_dir, in.shading, specular);
+
+        diffuse += amount.x * directional.color.rgb;
+        highlights += amount.y * directional.color.rgb;
+    }
+
+    for (var i = 0u; i < lights.counts.y; i++) {
+        let point = lights.point[i];
+        let offset = point.position.xyz - in.world_position;
+        let dist = length(offset);
+
+        // Smooth falloff reaching zero at the range
+        let falloff = pow(clamp(1.0 - dist / point.position.w, 0.0, 1.0), 2.0);
+        let amount = shade(normal, offset / max(dist, 0.0001), view_dir, in.shading, specular);
+
+        diffuse += amount.x * point.color.rgb * falloff;
+        highlights += amount.y * point.color.rgb * falloff;
+    }
+
+    return color * diffuse + highlights;
+}
+
+// Diffuse and specular amounts of a light
+fn shade(normal: vec3<f32>, to_light: vec3<f32>, view_dir: vec3<f32>, shading: vec2<f32>, specular: bool) -> vec2<f32> {
+    let diffuse = max(dot(normal, to_light), 0.0);
+
+    if !specular || diffuse <= 0.0 {
+        return vec2<f32>(diffuse, 0.0);
+    }
+
+    let half_dir = normalize(to_light + view_dir);
+
+    return vec2<f32>(diffuse, shading.x * pow(max(dot(normal, half_dir), 0.0), shading.y));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = base_color(in);
+
+    // Fully transparent texels don't hide what is behind them in the depth buffer
+    if color.a <= 0.0 {
+        discard;
+    }
+
+    return color;
+}
+
+@fragment
+fn fs_lambert(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = base_color(in);
+
+    if color.a <= 0.0 {
+        discard;
+    }
+
+    return vec4<f32>(lighting(in, color.rgb, false), color.a);
+}
+
+@fragment
+fn fs_blinn_phong(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = base_color(in);
+
+    if color.a <= 0.0 {
+        discard;
+    }
+
+    return vec4<f32>(lighting(in, color.rgb, true), color.a);
+}
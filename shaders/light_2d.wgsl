@@ -0,0 +1,138 @@
+// 2D lights accumulated into the light map of the World layer
+
+// Per light instance data
+struct LightInput {
+    // Position in xy, radius in z, falloff exponent in w
+    @location(0) position: vec4<f32>,
+    @location(1) color: vec4<f32>,
+    // Direction, cosine of the half angle of the cone, softness, casts shadows
+    @location(2) params: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec2<f32>,
+    @location(1) @interpolate(flat) light: vec4<f32>,
+    @location(2) @interpolate(flat) color: vec4<f32>,
+    @location(3) @interpolate(flat) params: vec4<f32>,
+}
+
+struct Lighting {
+    // Number of occluder segments in x
+    counts: vec4<u32>,
+}
+
+// Camera view-projection matrix
+@group(0) @binding(0)
+var<uniform> view_projection: mat4x4<f32>;
+
+@group(1) @binding(0)
+var<uniform> lighting: Lighting;
+
+// Edges of the occluders, from xy to zw
+@group(1) @binding(1)
+var<storage, read> segments: array<vec4<f32>>;
+
+// Rays towards the light, spread over its softness for the penumbra
+const SHADOW_SAMPLES: i32 = 5;
+
+// A quad covering the radius of the light, without any vertex buffer
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, light: LightInput) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+
+    var out: VertexOutput;
+
+    let world = light.position.xy + corners[index] * light.position.z;
+
+    out.clip_position = view_projection * vec4<f32>(world, 0.0, 1.0);
+    out.world_position = world;
+    out.light = light.position;
+    out.color = light.color;
+    out.params = light.params;
+
+    return out;
+}
+
+fn cross_2d(a: vec2<f32>, b: vec2<f32>) -> f32 {
+    return a.x * b.y - a.y * b.x;
+}
+
+// Whether the segment ab crosses the segment cd
+fn intersects(a: vec2<f32>, b: vec2<f32>, c: vec2<f32>, d: vec2<f32>) -> bool {
+    let r = b - a;
+    let s = d - c;
+    let denom = cross_2d(r, s);
+
+    if abs(denom) < 0.000001 {
+        return false;
+    }
+
+    let t = cross_2d(c - a, s) / denom;
+    let u = cross_2d(c - a, r) / denom;
+
+    return t > 0.0 && t < 1.0 && u >= 0.0 && u <= 1.0;
+}
+
+// Fraction of the light source visible from a point
+fn visibility(point: vec2<f32>, light: vec2<f32>, softness: f32) -> f32 {
+    let to_light = light - point;
+    let side = vec2<f32>(-to_light.y, to_light.x) / max(length(to_light), 0.0001);
+
+    var visible = 0.0;
+
+    for (var k = 0; k < SHADOW_SAMPLES; k++) {
+        let t = f32(k) / f32(SHADOW_SAMPLES - 1) * 2.0 - 1.0;
+        let source = light + side * t * softness;
+        var blocked = false;
+
+        for (var i = 0u; i < lighting.counts.x; i++) {
+            let segment = segments[i];
+
+            if intersects(point, source, segment.xy, segment.zw) {
+                blocked = true;
+                break;
+            }
+        }
+
+        visible += select(1.0, 0.0, blocked);
+    }
+
+    return visible / f32(SHADOW_SAMPLES);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let offset = in.world_position - in.light.xy;
+    let dist = length(offset);
+    let radius = in.light.z;
+
+    if dist >= radius {
+        discard;
+    }
+
+    var amount = pow(1.0 - dist / radius, in.light.w);
+
+    // Spot lights fade out at the edges of their cone
+    let cone = in.params.y;
+
+    if cone > -1.0 {
+        let direction = vec2<f32>(cos(in.params.x), sin(in.params.x));
+        let along = dot(offset / max(dist, 0.0001), direction);
+
+        amount *= smoothstep(cone, mix(cone, 1.0, 0.1), along);
+    }
+
+    if in.params.w > 0.0 && amount > 0.0 {
+        amount *= visibility(in.world_position, in.light.xy, in.params.z);
+    }
+
+    return vec4<f32>(in.color.rgb * amount, 0.0);
+}
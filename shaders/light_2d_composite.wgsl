@@ -0,0 +1,19 @@
+// Multiplies the World layer by its light map
+
+// The light map, at the size of the pass it is composited in
+@group(0) @binding(0)
+var light_map: texture_2d<f32>;
+
+// A single triangle covering the whole viewport, without any vertex buffer
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    return vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    // Same pixel as the one being lit, the light map was drawn with the same viewport
+    return vec4<f32>(textureLoad(light_map, vec2<i32>(position.xy), 0).rgb, 1.0);
+}
@@ -0,0 +1,134 @@
+// Built-in post-processing effects, appended to post_common.wgsl
+
+// params[0]: threshold, intensity, radius
+@fragment
+fn fs_bloom(in: VertexOutput) -> @location(0) vec4<f32> {
+    let base = sample_source(in.uv);
+    let threshold = post.params[0].x;
+    let intensity = post.params[0].y;
+    let spread = texel_size() * post.params[0].z;
+
+    // Single pass gaussian of the bright parts, cheaper than a blur chain
+    var bloom = vec3<f32>(0.0);
+    var total = 0.0;
+
+    for (var x = -4; x <= 4; x++) {
+        for (var y = -4; y <= 4; y++) {
+            let offset = vec2<f32>(f32(x), f32(y));
+            let weight = exp(-dot(offset, offset) / 8.0);
+            let color = sample_source(in.uv + offset * spread).rgb;
+
+            bloom += max(color - vec3<f32>(threshold), vec3<f32>(0.0)) * weight;
+            total += weight;
+        }
+    }
+
+    return vec4<f32>(base.rgb + bloom / total * intensity, base.a);
+}
+
+// params[0]: intensity, radius, softness
+// params[1]: color
+@fragment
+fn fs_vignette(in: VertexOutput) -> @location(0) vec4<f32> {
+    let base = sample_source(in.uv);
+    let intensity = post.params[0].x;
+    let radius = post.params[0].y;
+    let softness = post.params[0].z;
+
+    // Corrected for the aspect ratio, so that the vignette stays round
+    let aspect = post.resolution.x / post.resolution.y;
+    let centered = (in.uv - 0.5) * vec2<f32>(aspect, 1.0);
+    let dist = length(centered) / length(vec2<f32>(aspect, 1.0) * 0.5);
+    let amount = smoothstep(radius, radius + softness, dist) * intensity;
+    let color = post.params[1];
+
+    return vec4<f32>(mix(base.rgb, color.rgb, amount * color.a), base.a);
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+// params[0]: intensity, size of the LUT
+// params[1]: uv offset and scale of the LUT in the atlas
+@fragment
+fn fs_color_grading(in: VertexOutput) -> @location(0) vec4<f32> {
+    let base = sample_source(in.uv);
+    let intensity = post.params[0].x;
+    let size = post.params[0].y;
+    let rect = post.params[1];
+
+    // LUTs are authored in sRGB, as a horizontal strip of `size` slices of blue
+    let color = clamp(linear_to_srgb(base.rgb), vec3<f32>(0.0), vec3<f32>(1.0));
+    let blue = color.b * (size - 1.0);
+    let slice = floor(blue);
+    let next = min(slice + 1.0, size - 1.0);
+
+    // Half a texel of margin, so that neighbouring slices and images don't bleed
+    let x = (color.r * (size - 1.0) + 0.5) / (size * size);
+    let y = (color.g * (size - 1.0) + 0.5) / size;
+
+    let uv_a = rect.xy + vec2<f32>(x + slice / size, y) * rect.zw;
+    let uv_b = rect.xy + vec2<f32>(x + next / size, y) * rect.zw;
+
+    let graded = mix(
+        textureSampleLevel(texture_atlas, texture_sampler, uv_a, 0.0).rgb,
+        textureSampleLevel(texture_atlas, texture_sampler, uv_b, 0.0).rgb,
+        blue - slice,
+    );
+
+    return vec4<f32>(mix(base.rgb, graded, intensity), base.a);
+}
+
+// params[0]: curvature, scanline intensity, scanline count, vignette
+@fragment
+fn fs_crt(in: VertexOutput) -> @location(0) vec4<f32> {
+    let curvature = post.params[0].x;
+    let scanlines = post.params[0].y;
+    let lines = select(post.resolution.y * 0.5, post.params[0].z, post.params[0].z > 0.0);
+    let vignette = post.params[0].w;
+
+    // Barrel distortion of the screen
+    let centered = in.uv * 2.0 - 1.0;
+    let bent = centered * (1.0 + curvature * dot(centered.yx, centered.yx));
+    let uv = bent * 0.5 + 0.5;
+
+    if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+
+    var color = sample_source(uv).rgb;
+
+    let scanline = 0.5 + 0.5 * sin(uv.y * lines * 6.2831853);
+    color *= 1.0 - scanlines * (1.0 - scanline);
+
+    // Slight flicker of the whole screen
+    color *= 1.0 - scanlines * 0.03 * sin(post.time * 110.0);
+
+    let edge = uv * (1.0 - uv);
+    color *= mix(1.0, pow(edge.x * edge.y * 16.0, 0.25), vignette);
+
+    return vec4<f32>(color, 1.0);
+}
+
+// params[0]: offset in pixels at the edges
+@fragment
+fn fs_chromatic_aberration(in: VertexOutput) -> @location(0) vec4<f32> {
+    let direction = (in.uv - 0.5) * 2.0;
+    let offset = direction * post.params[0].x * texel_size();
+
+    let red = sample_source(in.uv + offset);
+    let green = sample_source(in.uv);
+    let blue = sample_source(in.uv - offset);
+
+    return vec4<f32>(red.r, green.g, blue.b, green.a);
+}
+
+// Copies the source as is, used to composite the pixel perfect World layer
+@fragment
+fn fs_blit(in: VertexOutput) -> @location(0) vec4<f32> {
+    return sample_source(in.uv);
+}
@@ -0,0 +1,54 @@
+// Shared by every post-processing effect, custom effects are appended to it
+
+struct PostUniforms {
+    // Size of the source in pixels
+    resolution: vec2<f32>,
+    // Seconds since the renderer started
+    time: f32,
+    _padding: f32,
+    // Parameters of the effect, their meaning depends on it
+    params: array<vec4<f32>, 4>,
+}
+
+// Vertex shader output / Fragment shader input
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// Output of the previous effect, or the scene for the first one
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+@group(1) @binding(0)
+var<uniform> post: PostUniforms;
+
+// Texture atlas, to sample images such as color grading LUTs
+@group(2) @binding(0)
+var texture_atlas: texture_2d<f32>;
+@group(2) @binding(1)
+var texture_sampler: sampler;
+
+// A single triangle covering the whole screen, without any vertex buffer
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+
+    return out;
+}
+
+fn sample_source(uv: vec2<f32>) -> vec4<f32> {
+    // Explicit level, so that it can be sampled from non-uniform control flow
+    return textureSampleLevel(source, source_sampler, uv, 0.0);
+}
+
+fn texel_size() -> vec2<f32> {
+    return 1.0 / post.resolution;
+}
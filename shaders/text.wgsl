@@ -97,5 +97,6 @@ fn vs_main(vertex: VertexInput, glyph: GlyphInstance) -> VertexOutput {
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let sampled = textureSample(atlas_texture, atlas_sampler, in.uv);
-    return vec4<f32>(in.color.rgb, in.color.a * sampled.a);
+    // Glyphs are white in the atlas, so this tints glyphs and inline images alike
+    return in.color * sampled;
 }
@@ -4,7 +4,7 @@ pub use math;
 pub use renderer as render;
 
 pub mod assets {
-    pub use assets::{Font, Image};
+    pub use assets::{Font, FontFace, Image, Model, ModelFormat, ModelPrimitive};
 }
 
 pub mod utils {